@@ -0,0 +1,239 @@
+use std::{
+    env, fs,
+    sync::{Arc, Mutex, RwLock},
+    time::{Duration, Instant},
+};
+
+use axum::{
+    extract::{Request, State},
+    http::{header, HeaderValue, StatusCode},
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+use jsonwebtoken::{jwk::JwkSet, Algorithm, DecodingKey, Validation};
+use serde_json::{Map, Value};
+
+use crate::{
+    authz::{Identity, Role},
+    request_id,
+};
+
+pub const DEFAULT_USERNAME_CLAIM: &str = "sub";
+pub const DEFAULT_ROLES_CLAIM: &str = "roles";
+// не чаще одного обращения к JWKS URL при неизвестном kid
+pub const JWKS_REFRESH_INTERVAL: Duration = Duration::from_secs(60);
+
+enum JwksSource {
+    None,
+    File(String),
+    Url(String),
+}
+
+pub struct Authenticator {
+    source: JwksSource,
+    keys: RwLock<JwkSet>,
+    refreshed_at: Mutex<Option<Instant>>,
+    issuer: Option<String>,
+    audience: Vec<String>,
+    username_claim: String,
+    roles_claim: String,
+    // режим совместимости: без токена доверять заголовку X-User-Name
+    allow_user_header: bool,
+}
+
+impl Authenticator {
+    pub async fn from_env() -> Arc<Self> {
+        let source = match (env::var("JWT_JWKS_FILE"), env::var("JWT_JWKS_URL")) {
+            (Ok(path), _) => JwksSource::File(path),
+            (_, Ok(url)) => JwksSource::Url(url),
+            _ => JwksSource::None,
+        };
+        // тестовый режим включается только явно
+        let allow_user_header =
+            env::var("AUTH_ALLOW_USER_HEADER").is_ok_and(|v| v == "true" || v == "1");
+        if matches!(source, JwksSource::None) && !allow_user_header {
+            panic!("Neither JWT_JWKS_FILE nor JWT_JWKS_URL is set and AUTH_ALLOW_USER_HEADER is not enabled");
+        }
+        if allow_user_header {
+            tracing::warn!("X-User-Name header is trusted for requests without a bearer token");
+        }
+
+        let auth = Self {
+            source,
+            keys: RwLock::new(JwkSet { keys: vec![] }),
+            refreshed_at: Mutex::new(None),
+            issuer: env::var("JWT_ISSUER").ok(),
+            audience: env::var("JWT_AUDIENCE")
+                .map(|v| v.split(',').map(|a| a.trim().to_owned()).collect())
+                .unwrap_or_default(),
+            username_claim: env::var("JWT_USERNAME_CLAIM")
+                .unwrap_or_else(|_| DEFAULT_USERNAME_CLAIM.to_owned()),
+            roles_claim: env::var("JWT_ROLES_CLAIM")
+                .unwrap_or_else(|_| DEFAULT_ROLES_CLAIM.to_owned()),
+            allow_user_header,
+        };
+        if let Err(e) = auth.refresh_keys().await {
+            tracing::error!("Failed to load JWKS: {e}");
+        }
+
+        Arc::new(auth)
+    }
+
+    async fn refresh_keys(&self) -> Result<(), String> {
+        let keys = match &self.source {
+            JwksSource::None => return Ok(()),
+            JwksSource::File(path) => {
+                let raw = fs::read_to_string(path).map_err(|e| e.to_string())?;
+                serde_json::from_str::<JwkSet>(&raw).map_err(|e| e.to_string())?
+            }
+            JwksSource::Url(url) => reqwest::get(url)
+                .await
+                .and_then(|r| r.error_for_status())
+                .map_err(|e| e.to_string())?
+                .json::<JwkSet>()
+                .await
+                .map_err(|e| e.to_string())?,
+        };
+        tracing::info!("Loaded {} signing keys from JWKS", keys.keys.len());
+
+        *self.keys.write().unwrap() = keys;
+        *self.refreshed_at.lock().unwrap() = Some(Instant::now());
+        Ok(())
+    }
+
+    fn decoding_key(&self, kid: Option<&str>) -> Option<DecodingKey> {
+        let keys = self.keys.read().unwrap();
+        let jwk = match kid {
+            Some(kid) => keys.find(kid),
+            None if keys.keys.len() == 1 => keys.keys.first(),
+            None => None,
+        }?;
+
+        DecodingKey::from_jwk(jwk).ok()
+    }
+
+    async fn identity(&self, token: &str) -> Result<Identity, String> {
+        let header = jsonwebtoken::decode_header(token).map_err(|e| e.to_string())?;
+        if !matches!(header.alg, Algorithm::RS256 | Algorithm::ES256) {
+            return Err(format!("unsupported algorithm {:?}", header.alg));
+        }
+
+        let mut key = self.decoding_key(header.kid.as_deref());
+        // ключи могли смениться у провайдера
+        if key.is_none() && matches!(self.source, JwksSource::Url(_)) {
+            let stale = self
+                .refreshed_at
+                .lock()
+                .unwrap()
+                .is_none_or(|at| at.elapsed() >= JWKS_REFRESH_INTERVAL);
+            if stale {
+                self.refresh_keys().await?;
+                key = self.decoding_key(header.kid.as_deref());
+            }
+        }
+        let key = key.ok_or_else(|| format!("unknown signing key {:?}", header.kid))?;
+
+        let mut validation = Validation::new(header.alg);
+        if let Some(issuer) = &self.issuer {
+            validation.set_issuer(&[issuer]);
+        }
+        if self.audience.is_empty() {
+            validation.validate_aud = false;
+        } else {
+            validation.set_audience(&self.audience);
+        }
+
+        let claims = jsonwebtoken::decode::<Map<String, Value>>(token, &key, &validation)
+            .map_err(|e| e.to_string())?
+            .claims;
+
+        identity_from_claims(&claims, &self.username_claim, &self.roles_claim)
+    }
+}
+
+pub fn identity_from_claims(
+    claims: &Map<String, Value>,
+    username_claim: &str,
+    roles_claim: &str,
+) -> Result<Identity, String> {
+    let username = claims
+        .get(username_claim)
+        .and_then(Value::as_str)
+        .filter(|username| !username.is_empty())
+        .ok_or_else(|| format!("claim {username_claim} is missing"))?;
+
+    // роли могут лежать во вложенном объекте, например realm_access.roles
+    let mut path = roles_claim.split('.');
+    let roles = path
+        .next()
+        .and_then(|key| claims.get(key))
+        .and_then(|v| path.try_fold(v, |v, key| v.get(key)))
+        .map(|v| match v {
+            Value::Array(items) => items
+                .iter()
+                .filter_map(Value::as_str)
+                .filter_map(Role::parse)
+                .collect(),
+            Value::String(s) => Role::parse_list(s),
+            _ => vec![],
+        })
+        .unwrap_or_default();
+
+    Ok(Identity::new(username.to_owned(), roles))
+}
+
+// имя пользователя из токена передаётся обработчикам и сервисам в заголовке X-User-Name,
+// роли — в расширениях запроса
+pub async fn authenticate(
+    State(auth): State<Arc<Authenticator>>,
+    mut req: Request,
+    next: Next,
+) -> Response {
+    let token = req
+        .headers()
+        .get(header::AUTHORIZATION)
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.strip_prefix("Bearer "))
+        .map(str::to_owned);
+
+    let identity = match token {
+        Some(token) => {
+            let identity = match auth.identity(&token).await {
+                Ok(identity) => identity,
+                Err(e) => {
+                    tracing::warn!("Rejected bearer token: {e}");
+                    return unauthorized();
+                }
+            };
+            let Ok(username) = HeaderValue::from_str(&identity.username) else {
+                return unauthorized();
+            };
+            req.headers_mut().insert("X-User-Name", username);
+            identity
+        }
+        None if auth.allow_user_header => {
+            let Some(username) = req
+                .headers()
+                .get("X-User-Name")
+                .and_then(|v| v.to_str().ok())
+            else {
+                return unauthorized();
+            };
+            // роли из заголовков клиента не принимаются, повышенные права только по токену
+            Identity::new(username.to_owned(), vec![])
+        }
+        None => return unauthorized(),
+    };
+    request_id::set_user(&identity.username);
+    req.extensions_mut().insert(identity);
+
+    next.run(req).await
+}
+
+fn unauthorized() -> Response {
+    (
+        StatusCode::UNAUTHORIZED,
+        [(header::WWW_AUTHENTICATE, "Bearer")],
+    )
+        .into_response()
+}
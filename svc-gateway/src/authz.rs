@@ -0,0 +1,89 @@
+use axum::{
+    extract::{Request, State},
+    http::{header, StatusCode},
+    middleware::Next,
+    response::{IntoResponse, Response},
+    Json,
+};
+
+use crate::dto::Problem;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Role {
+    User,
+    Support,
+    Admin,
+}
+
+impl Role {
+    pub fn parse(s: &str) -> Option<Self> {
+        match s.trim().to_lowercase().as_str() {
+            "user" => Some(Self::User),
+            "support" => Some(Self::Support),
+            "admin" => Some(Self::Admin),
+            _ => None,
+        }
+    }
+
+    pub fn parse_list(s: &str) -> Vec<Self> {
+        s.split([',', ' ']).filter_map(Self::parse).collect()
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Identity {
+    pub username: String,
+    pub roles: Vec<Role>,
+}
+
+impl Identity {
+    // любой аутентифицированный пользователь имеет роль user
+    pub fn new(username: String, mut roles: Vec<Role>) -> Self {
+        if !roles.contains(&Role::User) {
+            roles.push(Role::User);
+        }
+
+        Self { username, roles }
+    }
+}
+
+pub const SUPPORT_ROLES: &[Role] = &[Role::Support, Role::Admin];
+pub const ADMIN_ROLES: &[Role] = &[Role::Admin];
+
+// политика маршрута: достаточно любой из перечисленных ролей
+pub async fn authorize(
+    State(allowed): State<&'static [Role]>,
+    req: Request,
+    next: Next,
+) -> Response {
+    let Some(identity) = req.extensions().get::<Identity>() else {
+        return StatusCode::UNAUTHORIZED.into_response();
+    };
+
+    if !identity.roles.iter().any(|role| allowed.contains(role)) {
+        tracing::warn!(
+            "{} is not allowed to {} {}",
+            identity.username,
+            req.method(),
+            req.uri().path()
+        );
+        return forbidden(req.uri().path());
+    }
+
+    next.run(req).await
+}
+
+fn forbidden(path: &str) -> Response {
+    (
+        StatusCode::FORBIDDEN,
+        [(header::CONTENT_TYPE, "application/problem+json")],
+        Json(Problem {
+            kind: "about:blank".to_owned(),
+            title: "Forbidden".to_owned(),
+            status: StatusCode::FORBIDDEN.as_u16(),
+            detail: "Insufficient role for this operation".to_owned(),
+            instance: path.to_owned(),
+        }),
+    )
+        .into_response()
+}
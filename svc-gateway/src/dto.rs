@@ -31,6 +31,7 @@ pub struct HotelResponse {
     pub address: String,
     pub stars: i32,
     pub price: i32,
+    pub currency: String,
 }
 
 #[derive(Serialize, Deserialize, ToSchema)]
@@ -42,6 +43,59 @@ pub struct HotelInfo {
     stars: i32,
 }
 
+#[derive(Serialize, Deserialize)]
+pub struct PaymentHistoryRequest {
+    pub status: Option<PaymentStatus>,
+    pub page: Option<usize>,
+    pub size: Option<usize>,
+}
+
+#[derive(Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct PaymentHistoryItem {
+    pub payment_uid: Uuid,
+    pub reservation_uid: Option<Uuid>,
+    pub status: PaymentStatus,
+    pub price: i32,
+    pub currency: String,
+}
+
+#[derive(Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct PaymentHistoryResponse {
+    pub page: usize,
+    pub page_size: usize,
+    pub total_elements: usize,
+    pub items: Vec<PaymentHistoryItem>,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct LoyaltyHistoryRequest {
+    pub page: Option<usize>,
+    pub size: Option<usize>,
+}
+
+#[derive(Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct LoyaltyEventItem {
+    pub kind: String,
+    pub reservation_uid: Option<Uuid>,
+    pub reservation_count: Option<i32>,
+    pub points: i32,
+    pub status_from: Option<String>,
+    pub status_to: Option<String>,
+    pub created_at: DateTime<chrono::Local>,
+}
+
+#[derive(Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct LoyaltyHistoryResponse {
+    pub page: usize,
+    pub page_size: usize,
+    pub total_elements: usize,
+    pub items: Vec<LoyaltyEventItem>,
+}
+
 #[derive(Serialize, ToSchema)]
 pub struct UserInfoResponse {
     pub reservations: Vec<ReservationResponse>,
@@ -57,6 +111,7 @@ pub struct ReservationResponse {
     end_date: NaiveDate,
     status: PaymentStatus,
     payment: PaymentInfo,
+    perks: ReservationPerks,
 }
 
 impl ReservationResponse {
@@ -68,6 +123,7 @@ impl ReservationResponse {
             end_date: res.end_date.date_naive(),
             status: res.status,
             payment,
+            perks: res.perks,
         }
     }
 }
@@ -81,6 +137,17 @@ pub struct ReservationServiceResponse {
     pub end_date: DateTime<chrono::Local>,
     pub status: PaymentStatus,
     pub payment_uid: Uuid,
+    #[serde(default)]
+    pub perks: ReservationPerks,
+}
+
+// льготы уровня лояльности, записанные в бронирование
+#[derive(Default, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct ReservationPerks {
+    pub cancellation_extension_hours: i32,
+    pub late_checkout: bool,
+    pub free_nights: i32,
 }
 
 #[derive(Serialize, Deserialize, ToSchema)]
@@ -88,6 +155,7 @@ pub struct ReservationServiceResponse {
 pub struct PaymentInfo {
     pub status: PaymentStatus,
     pub price: i32,
+    pub currency: String,
 }
 
 #[derive(Deserialize)]
@@ -96,6 +164,134 @@ pub struct PaymentInfoServiceResponse {
     pub payment_uid: Uuid,
     pub status: PaymentStatus,
     pub price: i32,
+    pub currency: String,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PostPaymentServiceRequest {
+    pub reservation_uid: Uuid,
+    pub status: PaymentStatus,
+    pub price: i32,
+    pub currency: String,
+    pub base_price: i32,
+    pub base_currency: String,
+    pub exchange_rate: f64,
+    pub hotel_name: String,
+    pub start_date: NaiveDate,
+    pub end_date: NaiveDate,
+    pub nightly_price: i32,
+    pub discount: i32,
+    pub promo_code: Option<String>,
+    pub promo_discount: i32,
+    pub points_discount: i32,
+    pub free_nights_discount: i32,
+    pub items: Vec<PriceItem>,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PricingQuoteServiceRequest {
+    pub country: String,
+    pub city: String,
+    pub amount: i32,
+    pub nights: i32,
+    pub currency: String,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PricingQuoteServiceResponse {
+    pub items: Vec<PriceItem>,
+    pub total: i32,
+}
+
+#[derive(Clone, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct PriceItem {
+    pub name: String,
+    pub amount: i32,
+}
+
+#[derive(Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct PriceBreakdown {
+    pub nights: i32,
+    pub nightly_price: i32,
+    pub subtotal: i32,
+    pub total_discount: i32,
+    pub taxes: Vec<PriceItem>,
+    pub total: i32,
+    pub currency: String,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PromoCodeRedeemServiceRequest {
+    pub amount: i32,
+    pub currency: String,
+    pub loyalty_discount: i32,
+    pub reservation_uid: Uuid,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PromoCodeRedeemServiceResponse {
+    pub redemption_uid: Uuid,
+    pub code: String,
+    pub stackable: bool,
+    pub loyalty_discount: i32,
+    pub promo_discount: i32,
+}
+
+#[derive(Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct DiscountBreakdown {
+    pub loyalty_discount: i32,
+    pub promo_code: Option<String>,
+    pub promo_discount: i32,
+    pub bonus_discount: i32,
+    pub points_used: i32,
+    pub points_discount: i32,
+    pub free_nights: i32,
+    pub free_nights_discount: i32,
+    pub total_discount: i32,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PointsRedeemServiceRequest {
+    pub points: i32,
+    pub max_discount: i32,
+    pub currency: String,
+    pub reservation_uid: Uuid,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PointsHoldServiceResponse {
+    pub hold_uid: Uuid,
+    pub points: i32,
+    pub discount: i32,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PointsAccrualServiceRequest {
+    pub amount: i32,
+    pub currency: String,
+    pub reservation_uid: Uuid,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct ReceiptRequest {
+    pub format: Option<String>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ExchangeRateServiceResponse {
+    pub rate: f64,
 }
 
 #[derive(Deserialize, ToSchema)]
@@ -104,6 +300,10 @@ pub struct CreateReservationRequest {
     pub hotel_uid: Uuid,
     pub start_date: NaiveDate,
     pub end_date: NaiveDate,
+    pub currency: Option<String>,
+    pub promo_code: Option<String>,
+    pub use_points: Option<i32>,
+    pub referral_code: Option<String>,
 }
 
 #[derive(Serialize, ToSchema)]
@@ -114,17 +314,22 @@ pub struct CreateReservationResponse {
     pub start_date: NaiveDate,
     pub end_date: NaiveDate,
     pub discount: i32,
+    pub discount_breakdown: DiscountBreakdown,
+    pub price_breakdown: PriceBreakdown,
     pub status: PaymentStatus,
     pub payment: PaymentInfo,
+    pub perks: ReservationPerks,
 }
 
 #[derive(Serialize, ToSchema)]
 #[serde(rename_all = "camelCase")]
 pub struct PostReservationServiceRequest {
+    pub reservation_uid: Uuid,
     pub hotel_uid: Uuid,
     pub payment_uid: Uuid,
     pub start_date: DateTime<chrono::Local>,
     pub end_date: DateTime<chrono::Local>,
+    pub perks: ReservationPerks,
 }
 
 #[derive(Deserialize, ToSchema)]
@@ -136,6 +341,8 @@ pub struct PostReservationServiceResponse {
     pub start_date: DateTime<chrono::Local>,
     pub end_date: DateTime<chrono::Local>,
     pub status: PaymentStatus,
+    #[serde(default)]
+    pub perks: ReservationPerks,
 }
 
 #[derive(Serialize, Deserialize, ToSchema)]
@@ -144,6 +351,153 @@ pub struct LoyaltyInfoResponse {
     pub status: LoyaltyStatus,
     pub discount: i32,
     pub reservation_count: i32,
+    #[serde(default)]
+    pub points: i32,
+    pub next_requalification: Option<DateTime<chrono::Local>>,
+    pub downgrade_at: Option<DateTime<chrono::Local>>,
+    #[serde(default)]
+    pub bonus_discount: i32,
+    #[serde(default)]
+    pub perks: Vec<String>,
+    pub progress: Option<TierProgress>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LoyaltyTierServiceResponse {
+    pub name: LoyaltyStatus,
+    pub threshold: i32,
+    pub discount: i32,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LoyaltyChangeServiceRequest {
+    pub reservation_uid: Uuid,
+    pub stay_end_date: Option<NaiveDate>,
+    pub referral_code: Option<String>,
+    pub consume_bonus: bool,
+}
+
+#[derive(Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct ReferralCodeResponse {
+    pub code: String,
+    pub created_at: DateTime<chrono::Local>,
+}
+
+#[derive(Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct ReferralResponse {
+    pub code: String,
+    pub referee: String,
+    pub reservation_uid: Uuid,
+    pub status: String,
+    pub created_at: DateTime<chrono::Local>,
+    pub completed_at: Option<DateTime<chrono::Local>>,
+}
+
+#[derive(Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct TierProgress {
+    pub qualifying_reservations: i32,
+    pub next_tier: Option<LoyaltyStatus>,
+    pub reservations_to_next_tier: Option<i32>,
+}
+
+#[derive(Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct ReservationCountRequest {
+    pub set: Option<i32>,
+    pub delta: Option<i32>,
+    pub reason: String,
+}
+
+#[derive(Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct PointsGrantRequest {
+    pub points: i32,
+    pub reason: String,
+}
+
+#[derive(Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct TierPinRequest {
+    pub status: LoyaltyStatus,
+    pub expires_at: DateTime<chrono::Local>,
+    pub reason: String,
+}
+
+#[derive(Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct LoyaltyTierRequest {
+    pub name: LoyaltyStatus,
+    pub threshold: i32,
+    pub discount: i32,
+    #[serde(default)]
+    pub perks: Vec<String>,
+    #[serde(default)]
+    pub points_rate: i32,
+    #[serde(default)]
+    pub cancellation_extension_hours: i32,
+    #[serde(default)]
+    pub late_checkout: bool,
+    #[serde(default)]
+    pub free_night_after: Option<i32>,
+}
+
+#[derive(Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct HotelRequest {
+    pub name: String,
+    pub country: String,
+    pub city: String,
+    pub address: String,
+    pub stars: Option<i32>,
+    pub price: i32,
+    pub currency: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct PromoCodeRequest {
+    pub code: String,
+    pub kind: PromoCodeKind,
+    pub value: i32,
+    pub currency: Option<String>,
+    pub valid_from: Option<DateTime<chrono::Local>>,
+    pub valid_until: Option<DateTime<chrono::Local>>,
+    pub max_uses: Option<i32>,
+    pub max_uses_per_user: Option<i32>,
+    #[serde(default)]
+    pub stackable: bool,
+}
+
+#[derive(Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct PromoCodeResponse {
+    pub code: String,
+    pub kind: PromoCodeKind,
+    pub value: i32,
+    pub currency: Option<String>,
+    pub valid_from: Option<DateTime<chrono::Local>>,
+    pub valid_until: Option<DateTime<chrono::Local>>,
+    pub max_uses: Option<i32>,
+    pub max_uses_per_user: Option<i32>,
+    pub used_count: i32,
+    pub stackable: bool,
+    pub created_at: DateTime<chrono::Local>,
+}
+
+// ответ об ошибке в формате RFC 7807
+#[derive(Serialize, ToSchema)]
+pub struct Problem {
+    #[serde(rename = "type")]
+    pub kind: String,
+    pub title: String,
+    pub status: u16,
+    pub detail: String,
+    pub instance: String,
 }
 
 #[derive(Serialize, Deserialize, ToSchema)]
@@ -153,6 +507,13 @@ pub enum PaymentStatus {
     Canceled,
 }
 
+#[derive(Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum PromoCodeKind {
+    Percentage,
+    Fixed,
+}
+
 #[derive(Serialize, Deserialize, ToSchema)]
 #[serde(rename_all = "SCREAMING_SNAKE_CASE")]
 pub enum LoyaltyStatus {
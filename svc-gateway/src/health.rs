@@ -0,0 +1,119 @@
+use std::{
+    collections::BTreeMap,
+    time::{Duration, Instant},
+};
+
+use axum::{
+    http::StatusCode,
+    response::{IntoResponse, Response},
+    Json,
+};
+use serde::Serialize;
+use utoipa::ToSchema;
+
+use crate::{signature::SendSigned, LOYALTY_ENDPOINT, PAYMENT_ENDPOINT, RESERVATION_ENDPOINT};
+
+pub const CHECK_TIMEOUT: Duration = Duration::from_secs(2);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, ToSchema)]
+#[serde(rename_all = "UPPERCASE")]
+pub enum HealthStatus {
+    Up,
+    Down,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct DependencyHealth {
+    pub status: HealthStatus,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub latency_ms: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub detail: Option<String>,
+}
+
+impl DependencyHealth {
+    fn up(latency: Option<Duration>) -> Self {
+        Self {
+            status: HealthStatus::Up,
+            latency_ms: latency.map(|l| l.as_millis() as u64),
+            detail: None,
+        }
+    }
+
+    fn down(detail: impl Into<String>) -> Self {
+        Self {
+            status: HealthStatus::Down,
+            latency_ms: None,
+            detail: Some(detail.into()),
+        }
+    }
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct HealthReport {
+    pub status: HealthStatus,
+    pub checks: BTreeMap<String, DependencyHealth>,
+}
+
+impl HealthReport {
+    pub fn new(checks: BTreeMap<String, DependencyHealth>) -> Self {
+        let status = if checks.values().all(|c| c.status == HealthStatus::Up) {
+            HealthStatus::Up
+        } else {
+            HealthStatus::Down
+        };
+
+        Self { status, checks }
+    }
+}
+
+impl IntoResponse for HealthReport {
+    fn into_response(self) -> Response {
+        let status = match self.status {
+            HealthStatus::Up => StatusCode::OK,
+            HealthStatus::Down => StatusCode::SERVICE_UNAVAILABLE,
+        };
+
+        (status, Json(self)).into_response()
+    }
+}
+
+async fn check_upstream(endpoint: &str) -> DependencyHealth {
+    let started_at = Instant::now();
+    let resp = reqwest::Client::new()
+        .get(format!("{endpoint}/manage/health/live"))
+        .timeout(CHECK_TIMEOUT)
+        .send_signed()
+        .await;
+
+    match resp {
+        Ok(resp) if resp.status().is_success() => DependencyHealth::up(Some(started_at.elapsed())),
+        Ok(resp) => DependencyHealth::down(format!("responded with {}", resp.status())),
+        Err(e) => DependencyHealth::down(e.to_string()),
+    }
+}
+
+// шлюз готов принимать запросы, если все сервисы доступны
+pub async fn readiness() -> HealthReport {
+    let (reservation, payment, loyalty) = futures::join!(
+        check_upstream(RESERVATION_ENDPOINT),
+        check_upstream(PAYMENT_ENDPOINT),
+        check_upstream(LOYALTY_ENDPOINT),
+    );
+    for (service, health) in [
+        ("reservation", &reservation),
+        ("payment", &payment),
+        ("loyalty", &loyalty),
+    ] {
+        if health.status == HealthStatus::Down {
+            tracing::warn!("{service} service is not reachable: {:?}", health.detail);
+        }
+    }
+
+    HealthReport::new(BTreeMap::from([
+        ("reservation".to_owned(), reservation),
+        ("payment".to_owned(), payment),
+        ("loyalty".to_owned(), loyalty),
+    ]))
+}
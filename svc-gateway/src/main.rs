@@ -1,15 +1,26 @@
-use std::env;
+use std::{env, net::SocketAddr, sync::Arc};
 
+use auth::Authenticator;
+use authz::{ADMIN_ROLES, SUPPORT_ROLES};
 use dto::*;
+use rate_limit::{ProxyTrust, RateLimiter};
 use routes::*;
 use tokio::net::TcpListener;
 use utoipa::OpenApi;
 use utoipa_axum::{router::OpenApiRouter, routes};
 use utoipa_swagger_ui::SwaggerUi;
 
+mod auth;
+mod authz;
 mod dto;
+mod health;
 mod logger;
+mod monitoring;
+mod rate_limit;
+mod request_id;
 mod routes;
+mod signature;
+mod telemetry;
 
 #[cfg(test)]
 mod tests;
@@ -18,63 +29,177 @@ mod tests;
 #[openapi(
     paths(
         check_health,
+        check_liveness,
+        check_readiness,
+        get_metrics,
         get_me,
+        get_my_payments,
         get_hotels,
         get_loyalty,
+        get_loyalty_history,
+        post_referral_code,
+        get_referrals,
         get_reservation,
+        get_reservation_receipt,
         get_reservations,
         post_reservation,
-        delete_reservation
+        delete_reservation,
+        get_admin_loyalty_members,
+        get_admin_loyalty_member,
+        put_admin_reservation_count,
+        post_admin_points,
+        put_admin_tier_pin,
+        delete_admin_tier_pin,
+        put_admin_loyalty_tiers,
+        get_admin_loyalty_stats,
+        post_admin_refund,
+        post_admin_promo_code,
+        get_admin_promo_codes,
+        post_admin_hotel,
+        put_admin_hotel,
+        get_admin_log_levels,
+        put_admin_log_levels
     ),
     components(schemas(
         PaginationResponse,
         PaginationRequest,
         LoyaltyStatus,
         LoyaltyInfoResponse,
+        TierProgress,
+        LoyaltyEventItem,
+        LoyaltyHistoryResponse,
+        ReferralCodeResponse,
+        ReferralResponse,
+        ReservationCountRequest,
+        PointsGrantRequest,
+        TierPinRequest,
+        LoyaltyTierRequest,
+        HotelRequest,
+        PromoCodeRequest,
+        PromoCodeResponse,
+        PromoCodeKind,
         PaymentInfo,
         PaymentStatus,
+        PaymentHistoryItem,
+        PaymentHistoryResponse,
         HotelResponse,
         HotelInfo,
         UserInfoResponse,
         ReservationResponse,
         CreateReservationRequest,
-        CreateReservationResponse
+        CreateReservationResponse,
+        ReservationPerks,
+        DiscountBreakdown,
+        PriceBreakdown,
+        PriceItem,
+        Problem,
+        logger::LogLevels,
+        health::HealthStatus,
+        health::DependencyHealth,
+        health::HealthReport
     ))
 )]
 struct ApiDoc;
 
 pub const SERVICE_ENDPOINT: &str = "0.0.0.0:8080";
+pub const DEFAULT_RATE_LIMITS_PATH: &str = "rate_limits.json";
 pub const RESERVATION_ENDPOINT: &str = "http://reservation-bmstu-rsoi:8070";
 pub const PAYMENT_ENDPOINT: &str = "http://payment-bmstu-rsoi:8060";
 pub const LOYALTY_ENDPOINT: &str = "http://loyalty-bmstu-rsoi:8050";
+// валюта, в которой программа лояльности начисляет баллы
+pub const POINTS_CURRENCY: &str = "RUB";
 // pub const RESERVATION_ENDPOINT: &str = "http://localhost:8070";
 // pub const PAYMENT_ENDPOINT: &str = "http://localhost:8060";
 // pub const LOYALTY_ENDPOINT: &str = "http://localhost:8050";
 
 #[tokio::main(flavor = "multi_thread", worker_threads = 4)]
 async fn main() {
-    let _logger_handler = logger::init();
-    log::debug!("Logger initialized. Hello, world!");
+    logger::init("gateway");
+    let _tracer_provider = telemetry::init("gateway");
+    monitoring::init();
+    tracing::debug!("Logger initialized. Hello, world!");
 
     let app = app().await;
 
-    log::info!("Listening on {}", SERVICE_ENDPOINT);
+    tracing::info!("Listening on {}", SERVICE_ENDPOINT);
     let listener = TcpListener::bind(SERVICE_ENDPOINT).await.unwrap();
-    axum::serve(listener, app.into_make_service())
-        .await
-        .unwrap();
+    axum::serve(
+        listener,
+        app.into_make_service_with_connect_info::<SocketAddr>(),
+    )
+    .await
+    .unwrap();
 }
 
 async fn app() -> axum::Router {
     let swagger = SwaggerUi::new("/swagger-ui").url("/openapi.json", ApiDoc::openapi());
-    let app = OpenApiRouter::with_openapi(ApiDoc::openapi())
-        .routes(routes!(check_health))
-        .routes(routes!(get_hotels))
+    signature::init();
+    let authenticator = Authenticator::from_env().await;
+    let rate_limits_path =
+        env::var("RATE_LIMITS_PATH").unwrap_or_else(|_| DEFAULT_RATE_LIMITS_PATH.to_owned());
+    let limiter = Arc::new(
+        RateLimiter::load(&rate_limits_path)
+            .unwrap_or_else(|e| panic!("Failed to load rate limits: {e}"))
+            .with_proxy_trust(ProxyTrust::from_env()),
+    );
+    let support = OpenApiRouter::new()
+        .routes(routes!(get_admin_loyalty_members))
+        .routes(routes!(get_admin_loyalty_member))
+        .routes(routes!(put_admin_reservation_count))
+        .routes(routes!(post_admin_points))
+        .routes(routes!(get_admin_loyalty_stats))
+        .routes(routes!(post_admin_refund))
+        .route_layer(axum::middleware::from_fn_with_state(
+            SUPPORT_ROLES,
+            authz::authorize,
+        ));
+    let admin = OpenApiRouter::new()
+        .routes(routes!(put_admin_tier_pin, delete_admin_tier_pin))
+        .routes(routes!(put_admin_loyalty_tiers))
+        .routes(routes!(post_admin_promo_code, get_admin_promo_codes))
+        .routes(routes!(post_admin_hotel))
+        .routes(routes!(put_admin_hotel))
+        .routes(routes!(get_admin_log_levels, put_admin_log_levels))
+        .route_layer(axum::middleware::from_fn_with_state(
+            ADMIN_ROLES,
+            authz::authorize,
+        ));
+    let protected = OpenApiRouter::new()
         .routes(routes!(get_loyalty))
+        .routes(routes!(get_loyalty_history))
+        .routes(routes!(post_referral_code))
+        .routes(routes!(get_referrals))
         .routes(routes!(get_reservations, post_reservation))
         .routes(routes!(delete_reservation, get_reservation))
-        .routes(routes!(get_me));
+        .routes(routes!(get_reservation_receipt))
+        .routes(routes!(get_me))
+        .routes(routes!(get_my_payments))
+        .merge(support)
+        .merge(admin)
+        // лимиты проверяются после аутентификации, чтобы учитывать пользователя
+        .route_layer(axum::middleware::from_fn_with_state(
+            limiter.clone(),
+            rate_limit::limit_rate,
+        ))
+        .route_layer(axum::middleware::from_fn_with_state(
+            authenticator,
+            auth::authenticate,
+        ));
+    let app = OpenApiRouter::with_openapi(ApiDoc::openapi())
+        .routes(routes!(get_hotels))
+        .route_layer(axum::middleware::from_fn_with_state(
+            limiter,
+            rate_limit::limit_rate,
+        ))
+        .routes(routes!(check_health))
+        .routes(routes!(check_liveness))
+        .routes(routes!(check_readiness))
+        .routes(routes!(get_metrics))
+        .merge(protected);
 
-    axum::Router::from(app).merge(swagger)
+    axum::Router::from(app)
+        .merge(swagger)
+        .layer(axum::middleware::from_fn(monitoring::track_request))
+        .layer(axum::middleware::from_fn(telemetry::trace_request))
+        .layer(axum::middleware::from_fn(request_id::propagate_request_id))
 }
-
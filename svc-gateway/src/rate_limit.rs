@@ -0,0 +1,254 @@
+use std::{
+    collections::HashMap,
+    env, fs,
+    net::{IpAddr, SocketAddr},
+    path::Path,
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
+
+use axum::{
+    extract::{ConnectInfo, MatchedPath, Request, State},
+    http::{HeaderMap, HeaderValue, StatusCode},
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+use serde::Deserialize;
+
+use crate::authz::Identity;
+
+// после стольких корзин из хранилища удаляются уже восстановившиеся
+pub const MAX_IDLE_BUCKETS: usize = 10_000;
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RateLimitRule {
+    pub method: Option<String>,
+    pub path: String,
+    pub capacity: u32,
+    pub refill_per_second: f64,
+}
+
+impl RateLimitRule {
+    fn matches(&self, method: &str, path: &str) -> bool {
+        self.path == path
+            && self
+                .method
+                .as_ref()
+                .is_none_or(|m| m.eq_ignore_ascii_case(method))
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct Decision {
+    pub allowed: bool,
+    pub limit: u32,
+    pub remaining: u32,
+    // через сколько корзина снова заполнится
+    pub reset: Duration,
+    // через сколько появится следующий токен
+    pub retry_after: Duration,
+}
+
+// хранилище корзин; по умолчанию в памяти процесса, для нескольких реплик можно подключить внешнее
+pub trait RateLimitStore: Send + Sync {
+    // токен списывается сразу из всех корзин или ни из одной
+    fn take(&self, keys: &[String], rule: &RateLimitRule) -> Vec<Decision>;
+}
+
+struct Bucket {
+    tokens: f64,
+    updated_at: Instant,
+}
+
+#[derive(Default)]
+pub struct MemoryStore {
+    buckets: Mutex<HashMap<String, Bucket>>,
+}
+
+impl RateLimitStore for MemoryStore {
+    fn take(&self, keys: &[String], rule: &RateLimitRule) -> Vec<Decision> {
+        let now = Instant::now();
+        let capacity = rule.capacity as f64;
+        let refill = |bucket: &Bucket| {
+            let elapsed = now.duration_since(bucket.updated_at).as_secs_f64();
+            (bucket.tokens + elapsed * rule.refill_per_second).min(capacity)
+        };
+
+        let mut buckets = self.buckets.lock().unwrap();
+        if buckets.len() > MAX_IDLE_BUCKETS {
+            buckets.retain(|_, bucket| refill(bucket) < capacity);
+        }
+        for key in keys {
+            let bucket = buckets.entry(key.to_owned()).or_insert(Bucket {
+                tokens: capacity,
+                updated_at: now,
+            });
+            bucket.tokens = refill(bucket);
+            bucket.updated_at = now;
+        }
+        let allowed = keys.iter().all(|key| buckets[key].tokens >= 1.0);
+
+        let seconds = |tokens: f64| {
+            if rule.refill_per_second > 0.0 {
+                Duration::from_secs_f64(tokens.max(0.0) / rule.refill_per_second)
+            } else {
+                Duration::MAX
+            }
+        };
+        keys.iter()
+            .map(|key| {
+                let bucket = buckets.get_mut(key).unwrap();
+                // при отказе запрещающими считаются только исчерпанные корзины
+                let has_token = bucket.tokens >= 1.0;
+                if allowed {
+                    bucket.tokens -= 1.0;
+                }
+                Decision {
+                    allowed: has_token,
+                    limit: rule.capacity,
+                    remaining: bucket.tokens.floor() as u32,
+                    reset: seconds(capacity - bucket.tokens),
+                    retry_after: seconds(1.0 - bucket.tokens),
+                }
+            })
+            .collect()
+    }
+}
+
+// от кого принимается X-Real-IP; без настройки заголовок игнорируется
+#[derive(Debug, Clone, Default)]
+pub struct ProxyTrust {
+    pub trust_all: bool,
+    pub proxies: Vec<IpAddr>,
+}
+
+impl ProxyTrust {
+    pub fn from_env() -> Self {
+        let trust_all = env::var("TRUST_PROXY_HEADERS").is_ok_and(|v| v == "true" || v == "1");
+        let proxies = env::var("TRUSTED_PROXIES")
+            .map(|v| {
+                v.split(',')
+                    .map(str::trim)
+                    .filter(|s| !s.is_empty())
+                    .map(|s| {
+                        s.parse::<IpAddr>().unwrap_or_else(|e| {
+                            panic!("Invalid address {s} in TRUSTED_PROXIES: {e}")
+                        })
+                    })
+                    .collect()
+            })
+            .unwrap_or_default();
+
+        Self { trust_all, proxies }
+    }
+
+    fn trusts(&self, peer: Option<IpAddr>) -> bool {
+        self.trust_all || peer.is_some_and(|ip| self.proxies.contains(&ip))
+    }
+}
+
+pub struct RateLimiter {
+    rules: Vec<RateLimitRule>,
+    store: Box<dyn RateLimitStore>,
+    proxy_trust: ProxyTrust,
+}
+
+impl RateLimiter {
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, String> {
+        let path = path.as_ref();
+        let data = fs::read_to_string(path)
+            .map_err(|e| format!("Failed to read {}: {e}", path.display()))?;
+        let rules = serde_json::from_str::<Vec<RateLimitRule>>(&data)
+            .map_err(|e| format!("Failed to parse {}: {e}", path.display()))?;
+
+        Ok(Self::new(rules, Box::new(MemoryStore::default())))
+    }
+
+    pub fn new(rules: Vec<RateLimitRule>, store: Box<dyn RateLimitStore>) -> Self {
+        Self {
+            rules,
+            store,
+            proxy_trust: ProxyTrust::default(),
+        }
+    }
+
+    pub fn with_proxy_trust(mut self, proxy_trust: ProxyTrust) -> Self {
+        self.proxy_trust = proxy_trust;
+        self
+    }
+}
+
+// лимит считается отдельно для пользователя и для адреса клиента
+pub async fn limit_rate(
+    State(limiter): State<Arc<RateLimiter>>,
+    req: Request,
+    next: Next,
+) -> Response {
+    let method = req.method().as_str();
+    let Some(path) = req
+        .extensions()
+        .get::<MatchedPath>()
+        .map(MatchedPath::as_str)
+    else {
+        return next.run(req).await;
+    };
+    let Some(rule) = limiter.rules.iter().find(|r| r.matches(method, path)) else {
+        return next.run(req).await;
+    };
+
+    let mut keys = vec![format!(
+        "{method} {path} ip:{}",
+        client_ip(&req, &limiter.proxy_trust)
+    )];
+    if let Some(identity) = req.extensions().get::<Identity>() {
+        keys.push(format!("{method} {path} user:{}", identity.username));
+    }
+
+    let decisions = limiter.store.take(&keys, rule);
+    if let Some((key, denied)) = keys.iter().zip(&decisions).find(|(_, d)| !d.allowed) {
+        tracing::warn!("Rate limit exceeded for {key}");
+        let mut resp = StatusCode::TOO_MANY_REQUESTS.into_response();
+        set_headers(resp.headers_mut(), denied);
+        resp.headers_mut().insert(
+            "Retry-After",
+            HeaderValue::from(denied.retry_after.as_secs_f64().ceil() as u64),
+        );
+        return resp;
+    }
+    let decision = decisions.into_iter().min_by_key(|d| d.remaining);
+
+    let mut resp = next.run(req).await;
+    if let Some(decision) = decision {
+        set_headers(resp.headers_mut(), &decision);
+    }
+    resp
+}
+
+// за ingress адрес клиента передаётся в X-Real-IP, которому верим только от доверенных прокси
+fn client_ip(req: &Request, proxy_trust: &ProxyTrust) -> String {
+    let peer = req
+        .extensions()
+        .get::<ConnectInfo<SocketAddr>>()
+        .map(|ConnectInfo(addr)| addr.ip());
+    let forwarded = req
+        .headers()
+        .get("X-Real-IP")
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.trim().parse::<IpAddr>().ok())
+        .filter(|_| proxy_trust.trusts(peer));
+
+    forwarded
+        .or(peer)
+        .map(|ip| ip.to_string())
+        .unwrap_or_else(|| "unknown".to_owned())
+}
+
+fn set_headers(headers: &mut HeaderMap, decision: &Decision) {
+    headers.insert("RateLimit-Limit", HeaderValue::from(decision.limit));
+    headers.insert("RateLimit-Remaining", HeaderValue::from(decision.remaining));
+    headers.insert(
+        "RateLimit-Reset",
+        HeaderValue::from(decision.reset.as_secs_f64().ceil() as u64),
+    );
+}
@@ -1,13 +1,22 @@
+use std::collections::BTreeMap;
+
 use axum::{
-    extract::{Path, Query},
-    http::{HeaderMap, StatusCode},
-    response::IntoResponse,
+    extract::{Path, Query, RawQuery},
+    http::{header, HeaderMap, StatusCode},
+    response::{IntoResponse, Response},
     Json,
 };
 use chrono::NaiveTime;
 use uuid::Uuid;
 
-use crate::{dto::*, LOYALTY_ENDPOINT, PAYMENT_ENDPOINT, RESERVATION_ENDPOINT};
+use crate::{
+    dto::*,
+    health::{self, HealthReport},
+    logger::{self, LogLevels},
+    monitoring,
+    signature::SendSigned,
+    LOYALTY_ENDPOINT, PAYMENT_ENDPOINT, POINTS_CURRENCY, RESERVATION_ENDPOINT,
+};
 
 #[utoipa::path(
     get,
@@ -20,6 +29,47 @@ pub async fn check_health() -> impl IntoResponse {
     StatusCode::OK
 }
 
+#[utoipa::path(
+    get,
+    path = "/manage/health/live",
+    responses(
+        (status = OK, body = HealthReport, description = "Процесс работает")
+    )
+)]
+pub async fn check_liveness() -> impl IntoResponse {
+    HealthReport::new(BTreeMap::new())
+}
+
+#[utoipa::path(
+    get,
+    path = "/manage/health/ready",
+    responses(
+        (status = OK, body = HealthReport, description = "Сервис готов принимать запросы"),
+        (
+            status = SERVICE_UNAVAILABLE,
+            body = HealthReport,
+            description = "Не пройдена одна из проверок: доступность сервисов",
+        ),
+    )
+)]
+pub async fn check_readiness() -> impl IntoResponse {
+    health::readiness().await
+}
+
+#[utoipa::path(
+    get,
+    path = "/manage/metrics",
+    responses(
+        (status = OK, body = String, content_type = "text/plain", description = "Метрики в формате Prometheus")
+    )
+)]
+pub async fn get_metrics() -> impl IntoResponse {
+    (
+        [(header::CONTENT_TYPE, "text/plain; version=0.0.4")],
+        monitoring::render(),
+    )
+}
+
 #[utoipa::path(
     get,
     path = "/api/v1/hotels",
@@ -44,16 +94,16 @@ pub async fn get_hotels(
     let resp = client
         .get(format!("{RESERVATION_ENDPOINT}/api/v1/hotels"))
         .query(&pagination)
-        .send()
+        .send_signed()
         .await
         .map_err(|e| {
-            log::error!("Failed to issue request to reservation service: {e}");
+            tracing::error!("Failed to issue request to reservation service: {e}");
             StatusCode::SERVICE_UNAVAILABLE
         })?
         .json::<PaginationResponse>()
         .await
         .map_err(|e| {
-            log::error!("Failed to parse reservation service response: {e}");
+            tracing::error!("Failed to parse reservation service response: {e}");
             StatusCode::INTERNAL_SERVER_ERROR
         })?;
 
@@ -85,10 +135,10 @@ pub async fn get_me(headers: HeaderMap) -> Result<impl IntoResponse, StatusCode>
     let loyalty = reqwest::Client::new()
         .get(format!("{LOYALTY_ENDPOINT}/api/v1/loyalty"))
         .header("X-User-Name", username)
-        .send()
+        .send_signed()
         .await
         .map_err(|e| {
-            log::error!("Failed to issue request to reservation service: {e}");
+            tracing::error!("Failed to issue request to reservation service: {e}");
             StatusCode::SERVICE_UNAVAILABLE
         })?
         .error_for_status()
@@ -96,17 +146,17 @@ pub async fn get_me(headers: HeaderMap) -> Result<impl IntoResponse, StatusCode>
         .json::<LoyaltyInfoResponse>()
         .await
         .map_err(|e| {
-            log::error!("Failed to parse reservation service response: {e}");
+            tracing::error!("Failed to parse reservation service response: {e}");
             StatusCode::INTERNAL_SERVER_ERROR
         })?;
 
     let reservations = reqwest::Client::new()
         .get(format!("{RESERVATION_ENDPOINT}/api/v1/reservations"))
         .header("X-User-Name", username)
-        .send()
+        .send_signed()
         .await
         .map_err(|e| {
-            log::error!("Failed to issue request to reservation service: {e}");
+            tracing::error!("Failed to issue request to reservation service: {e}");
             StatusCode::SERVICE_UNAVAILABLE
         })?
         .error_for_status()
@@ -114,7 +164,7 @@ pub async fn get_me(headers: HeaderMap) -> Result<impl IntoResponse, StatusCode>
         .json::<Vec<ReservationServiceResponse>>()
         .await
         .map_err(|e| {
-            log::error!("Failed to parse reservation service response: {e}");
+            tracing::error!("Failed to parse reservation service response: {e}");
             StatusCode::INTERNAL_SERVER_ERROR
         })?;
 
@@ -126,16 +176,17 @@ pub async fn get_me(headers: HeaderMap) -> Result<impl IntoResponse, StatusCode>
                     "{}/api/v1/payment/{}",
                     PAYMENT_ENDPOINT, el.payment_uid
                 ))
-                .send()
+                .header("X-User-Name", username)
+                .send_signed()
                 .await
                 .map_err(|e| {
-                    log::error!("Failed to issue request to reservation service: {e}");
+                    tracing::error!("Failed to issue request to reservation service: {e}");
                     StatusCode::SERVICE_UNAVAILABLE
                 })?
                 .json::<PaymentInfo>()
                 .await
                 .map_err(|e| {
-                    log::error!("Failed to parse reservation service response: {e}");
+                    tracing::error!("Failed to parse reservation service response: {e}");
                     StatusCode::INTERNAL_SERVER_ERROR
                 })?;
 
@@ -154,6 +205,56 @@ pub async fn get_me(headers: HeaderMap) -> Result<impl IntoResponse, StatusCode>
     ))
 }
 
+#[utoipa::path(
+    get,
+    path = "/api/v1/me/payments",
+    responses(
+        (
+            status = OK,
+            description = "История оплат пользователя",
+            body = PaymentHistoryResponse,
+            content_type = "application/json",
+        ),
+    ),
+    params(
+        ("X-User-Name", Header, description = "Имя пользователя"),
+        ("status", Query, description = "Фильтр по статусу оплаты"),
+        ("page", Query, description = "Номер страницы"),
+        ("size", Query, description = "Количество элементов страницы"),
+    ),
+)]
+pub async fn get_my_payments(
+    headers: HeaderMap,
+    Query(query): Query<PaymentHistoryRequest>,
+) -> Result<impl IntoResponse, StatusCode> {
+    let username = headers
+        .get("X-User-Name")
+        .ok_or(StatusCode::BAD_REQUEST)?
+        .to_str()
+        .map_err(|_| StatusCode::BAD_REQUEST)?;
+
+    let resp = reqwest::Client::new()
+        .get(format!("{PAYMENT_ENDPOINT}/api/v1/payments"))
+        .header("X-User-Name", username)
+        .query(&query)
+        .send_signed()
+        .await
+        .map_err(|e| {
+            tracing::error!("Failed to issue request to payment service: {e}");
+            StatusCode::SERVICE_UNAVAILABLE
+        })?
+        .error_for_status()
+        .map_err(|e| e.status().unwrap_or(StatusCode::INTERNAL_SERVER_ERROR))?
+        .json::<PaymentHistoryResponse>()
+        .await
+        .map_err(|e| {
+            tracing::error!("Failed to parse payment service response: {e}");
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?;
+
+    Ok(Json(resp))
+}
+
 #[utoipa::path(
     get,
     path = "/api/v1/reservations",
@@ -179,16 +280,16 @@ pub async fn get_reservations(headers: HeaderMap) -> Result<impl IntoResponse, S
     let resp = reqwest::Client::new()
         .get(format!("{RESERVATION_ENDPOINT}/api/v1/reservations"))
         .header("X-User-Name", username)
-        .send()
+        .send_signed()
         .await
         .map_err(|e| {
-            log::error!("Failed to issue request to reservation service: {e}");
+            tracing::error!("Failed to issue request to reservation service: {e}");
             StatusCode::SERVICE_UNAVAILABLE
         })?
         .json::<Vec<ReservationServiceResponse>>()
         .await
         .map_err(|e| {
-            log::error!("Failed to parse reservation service response: {e}");
+            tracing::error!("Failed to parse reservation service response: {e}");
             StatusCode::INTERNAL_SERVER_ERROR
         })?;
 
@@ -200,16 +301,17 @@ pub async fn get_reservations(headers: HeaderMap) -> Result<impl IntoResponse, S
                     "{}/api/v1/payment/{}",
                     PAYMENT_ENDPOINT, el.payment_uid
                 ))
-                .send()
+                .header("X-User-Name", username)
+                .send_signed()
                 .await
                 .map_err(|e| {
-                    log::error!("Failed to issue request to reservation service: {e}");
+                    tracing::error!("Failed to issue request to reservation service: {e}");
                     StatusCode::SERVICE_UNAVAILABLE
                 })?
                 .json::<PaymentInfo>()
                 .await
                 .map_err(|e| {
-                    log::error!("Failed to parse reservation service response: {e}");
+                    tracing::error!("Failed to parse reservation service response: {e}");
                     StatusCode::INTERNAL_SERVER_ERROR
                 })?;
 
@@ -232,6 +334,7 @@ pub async fn get_reservations(headers: HeaderMap) -> Result<impl IntoResponse, S
             body = CreateReservationResponse,
             content_type = "application/json",
         ),
+        (status = BAD_REQUEST, description = "Дата выезда не позже даты заезда"),
     ),
     params(
         ("X-User-Name", Header, description = "Имя пользователя")
@@ -247,17 +350,24 @@ pub async fn post_reservation(
         .to_str()
         .map_err(|_| StatusCode::BAD_REQUEST)?;
 
+    // выезд должен быть позже заезда, иначе стоимость и льготы не считаются
+    if req.end_date <= req.start_date {
+        return Err(StatusCode::BAD_REQUEST);
+    }
+
     let client = reqwest::Client::new();
+    let reservation_uid = Uuid::new_v4();
+
     // 1) запросить отель
     let hotel = client
         .get(format!(
             "{}/api/v1/hotel/{}",
             RESERVATION_ENDPOINT, req.hotel_uid
         ))
-        .send()
+        .send_signed()
         .await
         .map_err(|e| {
-            log::error!("Failed to issue request to reservation service: {e}");
+            tracing::error!("Failed to issue request to reservation service: {e}");
             StatusCode::SERVICE_UNAVAILABLE
         })?
         .error_for_status()
@@ -265,120 +375,478 @@ pub async fn post_reservation(
         .json::<HotelResponse>()
         .await
         .map_err(|e| {
-            log::error!("Failed to parse reservation service response: {e}");
+            tracing::error!("Failed to parse reservation service response: {e}");
             StatusCode::INTERNAL_SERVER_ERROR
         })?;
 
     // 2) рассчитать по нему стоимость (end_date - start_date)
     let cost = ((req.end_date - req.start_date).num_days() * hotel.price as i64) as i32;
 
-    // 3) рассчитать скидку
-    let loyalty = client
-        .get(format!("{}/api/v1/loyalty", LOYALTY_ENDPOINT))
+    // 2.1) запросить льготы уровня лояльности
+    let mut perks = client
+        .get(format!("{}/api/v1/loyalty/perks", LOYALTY_ENDPOINT))
         .header("X-User-Name", username)
-        .send()
-        .await
-        .map_err(|e| {
-            log::error!("Failed to issue request to loyalty service: {e}");
-            StatusCode::SERVICE_UNAVAILABLE
-        })?;
-    let loyalty = match loyalty.status() {
-        StatusCode::NOT_FOUND => LoyaltyInfoResponse {
-            status: LoyaltyStatus::Bronze,
-            discount: 5,
-            reservation_count: 1,
-        },
-        StatusCode::OK => loyalty.json::<LoyaltyInfoResponse>().await.map_err(|e| {
-            log::error!("Failed to parse loyalty service response: {e}");
-            StatusCode::INTERNAL_SERVER_ERROR
-        })?,
-        unknown_status_code => return Err(unknown_status_code),
-    };
-
-    let cost = cost - (cost * loyalty.discount / 100);
-
-    // 4) запись в payment
-    let payment = client
-        .post(format!("{}/api/v1/payment", PAYMENT_ENDPOINT))
-        .json(&PaymentInfo {
-            status: PaymentStatus::Paid,
-            price: cost as i32,
-        })
-        .send()
+        .send_signed()
         .await
         .map_err(|e| {
-            log::error!("Failed to issue request to payment service: {e}");
+            tracing::error!("Failed to issue request to loyalty service: {e}");
             StatusCode::SERVICE_UNAVAILABLE
         })?
         .error_for_status()
         .map_err(|e| e.status().unwrap_or(StatusCode::INTERNAL_SERVER_ERROR))?
-        .json::<PaymentInfoServiceResponse>()
+        .json::<ReservationPerks>()
         .await
         .map_err(|e| {
-            log::error!("Failed to parse payment service response: {e}");
+            tracing::error!("Failed to parse loyalty service response: {e}");
             StatusCode::INTERNAL_SERVER_ERROR
         })?;
-    log::debug!("Successfully created payment record");
+    perks.free_nights = perks
+        .free_nights
+        .clamp(0, (req.end_date - req.start_date).num_days() as i32);
 
-    // 5) запись в loyalty
-    client
-        .put(format!("{}/api/v1/loyalty", LOYALTY_ENDPOINT))
-        .header("X-User-Name", username)
-        .send()
-        .await
-        .map_err(|e| {
-            log::error!("Failed to issue request to loyalty service: {e}");
-            StatusCode::SERVICE_UNAVAILABLE
-        })?
-        .error_for_status()
-        .map_err(|e| e.status().unwrap_or(StatusCode::INTERNAL_SERVER_ERROR))?;
-    log::debug!("Successfully created loyalty record");
+    // остальные скидки считаются от стоимости платных ночей
+    let free_nights_discount = perks.free_nights * hotel.price;
+    let paid_cost = cost - free_nights_discount;
 
-    // 6) запись в reservation
-    let reservation = client
-        .post(format!("{}/api/v1/reservations", RESERVATION_ENDPOINT))
+    // 3) рассчитать скидку
+    let loyalty = client
+        .get(format!("{}/api/v1/loyalty", LOYALTY_ENDPOINT))
         .header("X-User-Name", username)
-        .json(&PostReservationServiceRequest {
-            hotel_uid: req.hotel_uid,
-            payment_uid: payment.payment_uid,
-            start_date: req
-                .start_date
-                .and_time(NaiveTime::from_hms_opt(0, 0, 0).unwrap())
-                .and_utc()
-                .into(),
-            end_date: req
-                .end_date
-                .and_time(NaiveTime::from_hms_opt(0, 0, 0).unwrap())
-                .and_utc()
-                .into(),
-        })
-        .send()
+        .send_signed()
         .await
         .map_err(|e| {
-            log::error!("Failed to issue request to reservation service: {e}");
+            tracing::error!("Failed to issue request to loyalty service: {e}");
             StatusCode::SERVICE_UNAVAILABLE
-        })?
-        .error_for_status()
-        .map_err(|e| e.status().unwrap_or(StatusCode::INTERNAL_SERVER_ERROR))?
-        .json::<PostReservationServiceResponse>()
-        .await
-        .map_err(|e| {
-            log::error!("Failed to parse reservation service response: {e}");
-            StatusCode::INTERNAL_SERVER_ERROR
         })?;
-    log::debug!("Successfully created reservation record");
+    let is_new_member = loyalty.status() == StatusCode::NOT_FOUND;
+    let loyalty = match loyalty.status() {
+        // новый участник получает начальный уровень из правил программы лояльности
+        StatusCode::NOT_FOUND => {
+            let base_tier = fetch_base_tier(&client).await?;
+            LoyaltyInfoResponse {
+                status: base_tier.name,
+                discount: base_tier.discount,
+                reservation_count: 1,
+                points: 0,
+                next_requalification: None,
+                downgrade_at: None,
+                bonus_discount: 0,
+                perks: vec![],
+                progress: None,
+            }
+        }
+        StatusCode::OK => loyalty.json::<LoyaltyInfoResponse>().await.map_err(|e| {
+            tracing::error!("Failed to parse loyalty service response: {e}");
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?,
+        unknown_status_code => return Err(unknown_status_code),
+    };
+    // у участника может не остаться учтённых бронирований, например после отката неудавшегося
+    let is_first_booking = is_new_member || loyalty.reservation_count == 0;
+
+    // реферальный код принимается только при первом бронировании
+    if let Some(code) = &req.referral_code {
+        if !is_first_booking {
+            return Err(StatusCode::UNPROCESSABLE_ENTITY);
+        }
+        if !is_valid_code(code) {
+            return Err(StatusCode::BAD_REQUEST);
+        }
+        let referral = client
+            .get(service_url(
+                LOYALTY_ENDPOINT,
+                &["api", "v1", "loyalty", "referral-codes", code],
+            )?)
+            .header("X-User-Name", username)
+            .send_signed()
+            .await
+            .map_err(|e| {
+                tracing::error!("Failed to issue request to loyalty service: {e}");
+                StatusCode::SERVICE_UNAVAILABLE
+            })?;
+        match referral.status() {
+            StatusCode::OK => {}
+            StatusCode::NOT_FOUND | StatusCode::UNPROCESSABLE_ENTITY => {
+                return Err(StatusCode::UNPROCESSABLE_ENTITY)
+            }
+            unknown_status_code => return Err(unknown_status_code),
+        }
+    }
+
+    // разовая реферальная скидка добавляется к скидке по уровню
+    let loyalty_percent = (loyalty.discount + loyalty.bonus_discount).min(100);
+    let base_currency = hotel.currency.to_uppercase();
+
+    // 3.1) зарезервировать промокод; применение отменяется, если бронирование не удалось
+    let (loyalty_discount, promo) = match &req.promo_code {
+        None => (paid_cost * loyalty_percent / 100, None),
+        Some(code) => {
+            if !is_valid_code(code) {
+                return Err(StatusCode::BAD_REQUEST);
+            }
+            let promo = client
+                .post(service_url(
+                    PAYMENT_ENDPOINT,
+                    &["api", "v1", "promo-codes", code, "redeem"],
+                )?)
+                .header("X-User-Name", username)
+                .json(&PromoCodeRedeemServiceRequest {
+                    amount: paid_cost,
+                    currency: base_currency.clone(),
+                    loyalty_discount: loyalty_percent,
+                    reservation_uid,
+                })
+                .send_signed()
+                .await
+                .map_err(|e| {
+                    tracing::error!("Failed to issue request to payment service: {e}");
+                    StatusCode::SERVICE_UNAVAILABLE
+                })?
+                .error_for_status()
+                .map_err(|e| e.status().unwrap_or(StatusCode::INTERNAL_SERVER_ERROR))?
+                .json::<PromoCodeRedeemServiceResponse>()
+                .await
+                .map_err(|e| {
+                    tracing::error!("Failed to parse payment service response: {e}");
+                    StatusCode::INTERNAL_SERVER_ERROR
+                })?;
+            tracing::debug!("Applied promo code {}", promo.code);
+
+            (promo.loyalty_discount, Some(promo))
+        }
+    };
+
+    let discount = match &promo {
+        Some(promo) if !promo.stackable => 0,
+        _ => loyalty_percent,
+    };
+    let bonus_discount = if discount > 0 {
+        loyalty.bonus_discount
+    } else {
+        0
+    };
+    let promo_discount = promo.as_ref().map_or(0, |p| p.promo_discount);
+
+    // 3.2) списать баллы лояльности; резерв снимается, если бронирование не удалось
+    let points_hold = async {
+        match req.use_points.filter(|points| *points > 0) {
+            None => Ok(None),
+            Some(points) => {
+                // баллы считаются в валюте баллов, скидка переводится в валюту брони
+                let rate = fetch_exchange_rate(&client, &base_currency, POINTS_CURRENCY).await?;
+                let max_discount = paid_cost - loyalty_discount - promo_discount;
+                // скидки уже покрыли стоимость, списывать баллы не на что
+                if to_points_currency(max_discount, rate) <= 0 {
+                    return Ok(None);
+                }
+                let mut hold = client
+                    .post(format!("{}/api/v1/loyalty/points/redeem", LOYALTY_ENDPOINT))
+                    .header("X-User-Name", username)
+                    .json(&PointsRedeemServiceRequest {
+                        points,
+                        max_discount: to_points_currency(max_discount, rate),
+                        currency: POINTS_CURRENCY.to_owned(),
+                        reservation_uid,
+                    })
+                    .send_signed()
+                    .await
+                    .map_err(|e| {
+                        tracing::error!("Failed to issue request to loyalty service: {e}");
+                        StatusCode::SERVICE_UNAVAILABLE
+                    })?
+                    .error_for_status()
+                    .map_err(|e| e.status().unwrap_or(StatusCode::INTERNAL_SERVER_ERROR))?
+                    .json::<PointsHoldServiceResponse>()
+                    .await
+                    .map_err(|e| {
+                        tracing::error!("Failed to parse loyalty service response: {e}");
+                        StatusCode::INTERNAL_SERVER_ERROR
+                    })?;
+                hold.discount = from_points_currency(hold.discount, rate, max_discount);
+                tracing::debug!("Holding {} loyalty points", hold.points);
+
+                Ok(Some(hold))
+            }
+        }
+    }
+    .await;
+    let points_hold = match points_hold {
+        Ok(hold) => hold,
+        Err(status) => {
+            if let Some(promo) = &promo {
+                settle_promo_redemption(&client, username, promo.redemption_uid, false).await;
+            }
+            return Err(status);
+        }
+    };
+
+    let points_discount = points_hold.as_ref().map_or(0, |h| h.discount);
+    let discount_breakdown = DiscountBreakdown {
+        loyalty_discount,
+        promo_code: promo.as_ref().map(|p| p.code.clone()),
+        promo_discount,
+        bonus_discount,
+        points_used: points_hold.as_ref().map_or(0, |h| h.points),
+        points_discount,
+        free_nights: perks.free_nights,
+        free_nights_discount,
+        total_discount: loyalty_discount + promo_discount + points_discount + free_nights_discount,
+    };
+
+    let cost = cost - discount_breakdown.total_discount;
+
+    let mut created_payment = None;
+    let mut counted_loyalty = false;
+    let booking = async {
+        // 3.3) рассчитать налоги и сборы
+        let nights = (req.end_date - req.start_date).num_days() as i32;
+        let taxes = client
+            .post(format!("{}/api/v1/pricing/quote", PAYMENT_ENDPOINT))
+            .json(&PricingQuoteServiceRequest {
+                country: hotel.country.clone(),
+                city: hotel.city.clone(),
+                amount: cost,
+                nights,
+                currency: base_currency.clone(),
+            })
+            .send_signed()
+            .await
+            .map_err(|e| {
+                tracing::error!("Failed to issue request to payment service: {e}");
+                StatusCode::SERVICE_UNAVAILABLE
+            })?
+            .error_for_status()
+            .map_err(|e| e.status().unwrap_or(StatusCode::INTERNAL_SERVER_ERROR))?
+            .json::<PricingQuoteServiceResponse>()
+            .await
+            .map_err(|e| {
+                tracing::error!("Failed to parse payment service response: {e}");
+                StatusCode::INTERNAL_SERVER_ERROR
+            })?;
+
+        let price_breakdown = PriceBreakdown {
+            nights,
+            nightly_price: hotel.price,
+            subtotal: nights * hotel.price,
+            total_discount: discount_breakdown.total_discount,
+            taxes: taxes.items,
+            total: cost + taxes.total,
+            currency: base_currency.clone(),
+        };
+        let cost = price_breakdown.total;
+
+        // 4) перевести стоимость в валюту пользователя
+        let currency = req
+            .currency
+            .as_deref()
+            .map(str::to_uppercase)
+            .unwrap_or_else(|| base_currency.clone());
+
+        let exchange_rate = fetch_exchange_rate(&client, &base_currency, &currency).await?;
+
+        let price = (cost as f64 * exchange_rate).round() as i32;
+
+        // 5) запись в payment
+        let payment = client
+            .post(format!("{}/api/v1/payment", PAYMENT_ENDPOINT))
+            .header("X-User-Name", username)
+            .json(&PostPaymentServiceRequest {
+                reservation_uid,
+                status: PaymentStatus::Paid,
+                price,
+                currency,
+                base_price: cost,
+                base_currency,
+                exchange_rate,
+                hotel_name: hotel.name.clone(),
+                start_date: req.start_date,
+                end_date: req.end_date,
+                nightly_price: hotel.price,
+                discount,
+                promo_code: discount_breakdown.promo_code.clone(),
+                promo_discount: discount_breakdown.promo_discount,
+                points_discount: discount_breakdown.points_discount,
+                free_nights_discount: discount_breakdown.free_nights_discount,
+                items: price_breakdown.taxes.clone(),
+            })
+            .send_signed()
+            .await
+            .map_err(|e| {
+                tracing::error!("Failed to issue request to payment service: {e}");
+                StatusCode::SERVICE_UNAVAILABLE
+            })?
+            .error_for_status()
+            .map_err(|e| e.status().unwrap_or(StatusCode::INTERNAL_SERVER_ERROR))?
+            .json::<PaymentInfoServiceResponse>()
+            .await
+            .map_err(|e| {
+                tracing::error!("Failed to parse payment service response: {e}");
+                StatusCode::INTERNAL_SERVER_ERROR
+            })?;
+        tracing::debug!("Successfully created payment record");
+        created_payment = Some(payment.payment_uid);
+
+        // 6) запись в loyalty
+        client
+            .put(format!("{}/api/v1/loyalty", LOYALTY_ENDPOINT))
+            .header("X-User-Name", username)
+            .query(&LoyaltyChangeServiceRequest {
+                reservation_uid,
+                stay_end_date: Some(req.end_date),
+                referral_code: req.referral_code.clone(),
+                consume_bonus: bonus_discount > 0,
+            })
+            .send_signed()
+            .await
+            .map_err(|e| {
+                tracing::error!("Failed to issue request to loyalty service: {e}");
+                StatusCode::SERVICE_UNAVAILABLE
+            })?
+            .error_for_status()
+            .map_err(|e| e.status().unwrap_or(StatusCode::INTERNAL_SERVER_ERROR))?;
+        tracing::debug!("Successfully created loyalty record");
+        counted_loyalty = true;
+
+        // 7) запись в reservation
+        let reservation = client
+            .post(format!("{}/api/v1/reservations", RESERVATION_ENDPOINT))
+            .header("X-User-Name", username)
+            .json(&PostReservationServiceRequest {
+                reservation_uid,
+                hotel_uid: req.hotel_uid,
+                payment_uid: payment.payment_uid,
+                start_date: req
+                    .start_date
+                    .and_time(NaiveTime::from_hms_opt(0, 0, 0).unwrap())
+                    .and_utc()
+                    .into(),
+                end_date: req
+                    .end_date
+                    .and_time(NaiveTime::from_hms_opt(0, 0, 0).unwrap())
+                    .and_utc()
+                    .into(),
+                perks,
+            })
+            .send_signed()
+            .await
+            .map_err(|e| {
+                tracing::error!("Failed to issue request to reservation service: {e}");
+                StatusCode::SERVICE_UNAVAILABLE
+            })?
+            .error_for_status()
+            .map_err(|e| e.status().unwrap_or(StatusCode::INTERNAL_SERVER_ERROR))?
+            .json::<PostReservationServiceResponse>()
+            .await
+            .map_err(|e| {
+                tracing::error!("Failed to parse reservation service response: {e}");
+                StatusCode::INTERNAL_SERVER_ERROR
+            })?;
+        tracing::debug!("Successfully created reservation record");
+
+        Ok((price_breakdown, payment, reservation))
+    };
+    let booking: Result<_, StatusCode> = booking.await;
+
+    // оплата отменяется, если бронирование не удалось записать до конца
+    if let (Err(_), Some(payment_uid)) = (&booking, created_payment) {
+        if let Err(e) = client
+            .delete(format!(
+                "{}/api/v1/payment/{}",
+                PAYMENT_ENDPOINT, payment_uid
+            ))
+            .header("X-User-Name", username)
+            .send_signed()
+            .await
+            .and_then(|r| r.error_for_status())
+        {
+            tracing::error!("Failed to cancel payment {payment_uid}: {e}");
+        }
+    }
+
+    // учтённое бронирование вычитается из программы лояльности; повторный вызов безопасен
+    if booking.is_err() && counted_loyalty {
+        if let Err(e) = client
+            .delete(format!("{}/api/v1/loyalty", LOYALTY_ENDPOINT))
+            .header("X-User-Name", username)
+            .query(&[("reservationUid", reservation_uid)])
+            .send_signed()
+            .await
+            .and_then(|r| r.error_for_status())
+        {
+            tracing::error!("Failed to remove reservation {reservation_uid} from loyalty: {e}");
+        }
+    }
+
+    if let Some(hold) = &points_hold {
+        let action = match &booking {
+            Ok(_) => client.post(format!(
+                "{}/api/v1/loyalty/points/holds/{}/capture",
+                LOYALTY_ENDPOINT, hold.hold_uid
+            )),
+            Err(_) => client.delete(format!(
+                "{}/api/v1/loyalty/points/holds/{}",
+                LOYALTY_ENDPOINT, hold.hold_uid
+            )),
+        };
+        if let Err(e) = action
+            .header("X-User-Name", username)
+            .send_signed()
+            .await
+            .and_then(|r| r.error_for_status())
+        {
+            tracing::error!(
+                "Failed to settle loyalty points hold {}: {e}",
+                hold.hold_uid
+            );
+        }
+    }
+
+    if let Some(promo) = &promo {
+        settle_promo_redemption(&client, username, promo.redemption_uid, booking.is_ok()).await;
+    }
+
+    let (price_breakdown, payment, reservation) = booking?;
+
+    // 8) начислить баллы за бронирование; сумма переводится в валюту баллов
+    match fetch_exchange_rate(&client, &price_breakdown.currency, POINTS_CURRENCY).await {
+        Err(_) => tracing::warn!(
+            "Failed to accrue loyalty points for reservation {reservation_uid}: no exchange rate"
+        ),
+        Ok(rate) => {
+            if let Err(e) = client
+                .post(format!("{}/api/v1/loyalty/points/accrue", LOYALTY_ENDPOINT))
+                .header("X-User-Name", username)
+                .json(&PointsAccrualServiceRequest {
+                    amount: (price_breakdown.total as f64 * rate).round() as i32,
+                    currency: POINTS_CURRENCY.to_owned(),
+                    reservation_uid,
+                })
+                .send_signed()
+                .await
+                .and_then(|r| r.error_for_status())
+            {
+                tracing::warn!(
+                    "Failed to accrue loyalty points for reservation {reservation_uid}: {e}"
+                );
+            }
+        }
+    }
 
     Ok(Json(CreateReservationResponse {
         reservation_uid: reservation.reservation_uid,
         hotel_uid: reservation.hotel_uid,
         start_date: reservation.start_date.naive_utc().date(),
         end_date: reservation.end_date.naive_utc().date(),
-        discount: loyalty.discount,
+        discount,
+        discount_breakdown,
+        price_breakdown,
         status: reservation.status,
         payment: PaymentInfo {
             status: payment.status,
             price: payment.price,
+            currency: payment.currency,
         },
+        perks: reservation.perks,
     }))
 }
 
@@ -414,16 +882,16 @@ pub async fn get_reservation(
             "{RESERVATION_ENDPOINT}/api/v1/reservations/{reservation_uid}"
         ))
         .header("X-User-Name", username)
-        .send()
+        .send_signed()
         .await
         .map_err(|e| {
-            log::error!("Failed to issue request to reservation service: {e}");
+            tracing::error!("Failed to issue request to reservation service: {e}");
             StatusCode::SERVICE_UNAVAILABLE
         })?
         .json::<ReservationServiceResponse>()
         .await
         .map_err(|e| {
-            log::error!("Failed to parse reservation service response: {e}");
+            tracing::error!("Failed to parse reservation service response: {e}");
             StatusCode::INTERNAL_SERVER_ERROR
         })?;
 
@@ -432,16 +900,17 @@ pub async fn get_reservation(
             "{}/api/v1/payment/{}",
             PAYMENT_ENDPOINT, reservation.payment_uid
         ))
-        .send()
+        .header("X-User-Name", username)
+        .send_signed()
         .await
         .map_err(|e| {
-            log::error!("Failed to issue request to reservation service: {e}");
+            tracing::error!("Failed to issue request to reservation service: {e}");
             StatusCode::SERVICE_UNAVAILABLE
         })?
         .json::<PaymentInfo>()
         .await
         .map_err(|e| {
-            log::error!("Failed to parse reservation service response: {e}");
+            tracing::error!("Failed to parse reservation service response: {e}");
             StatusCode::INTERNAL_SERVER_ERROR
         })?;
 
@@ -451,6 +920,84 @@ pub async fn get_reservation(
     )))
 }
 
+#[utoipa::path(
+    get,
+    path = "/api/v1/reservations/{reservationUid}/receipt",
+    responses(
+        (status = OK, description = "Квитанция об оплате бронирования", content_type = "text/html"),
+        (
+            status = OK,
+            description = "Квитанция об оплате бронирования",
+            content_type = "application/pdf",
+        ),
+    ),
+    params(
+        ("X-User-Name", Header, description = "Имя пользователя"),
+        ("reservationUid", Path, description = "Идентификатор брони"),
+        ("format", Query, description = "Формат квитанции: html или pdf"),
+    ),
+)]
+pub async fn get_reservation_receipt(
+    Path(reservation_uid): Path<Uuid>,
+    Query(query): Query<ReceiptRequest>,
+    headers: HeaderMap,
+) -> Result<impl IntoResponse, StatusCode> {
+    let username = headers
+        .get("X-User-Name")
+        .ok_or(StatusCode::BAD_REQUEST)?
+        .to_str()
+        .map_err(|_| StatusCode::BAD_REQUEST)?;
+
+    let client = reqwest::Client::new();
+    let reservation = client
+        .get(format!(
+            "{RESERVATION_ENDPOINT}/api/v1/reservations/{reservation_uid}"
+        ))
+        .header("X-User-Name", username)
+        .send_signed()
+        .await
+        .map_err(|e| {
+            tracing::error!("Failed to issue request to reservation service: {e}");
+            StatusCode::SERVICE_UNAVAILABLE
+        })?
+        .error_for_status()
+        .map_err(|e| e.status().unwrap_or(StatusCode::INTERNAL_SERVER_ERROR))?
+        .json::<ReservationServiceResponse>()
+        .await
+        .map_err(|e| {
+            tracing::error!("Failed to parse reservation service response: {e}");
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?;
+
+    let receipt = client
+        .get(format!(
+            "{}/api/v1/payment/{}/receipt",
+            PAYMENT_ENDPOINT, reservation.payment_uid
+        ))
+        .header("X-User-Name", username)
+        .query(&query)
+        .send_signed()
+        .await
+        .map_err(|e| {
+            tracing::error!("Failed to issue request to payment service: {e}");
+            StatusCode::SERVICE_UNAVAILABLE
+        })?
+        .error_for_status()
+        .map_err(|e| e.status().unwrap_or(StatusCode::INTERNAL_SERVER_ERROR))?;
+
+    let content_type = receipt
+        .headers()
+        .get(header::CONTENT_TYPE)
+        .cloned()
+        .ok_or(StatusCode::INTERNAL_SERVER_ERROR)?;
+    let body = receipt.bytes().await.map_err(|e| {
+        tracing::error!("Failed to read payment service response: {e}");
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
+
+    Ok(([(header::CONTENT_TYPE, content_type)], body))
+}
+
 #[utoipa::path(
     delete,
     path = "/api/v1/reservations/{reservationUid}",
@@ -483,10 +1030,10 @@ pub async fn delete_reservation(
             RESERVATION_ENDPOINT, reservation_uid
         ))
         .header("X-User-Name", username)
-        .send()
+        .send_signed()
         .await
         .map_err(|e| {
-            log::error!("Failed to issue request to reservation service: {e}");
+            tracing::error!("Failed to issue request to reservation service: {e}");
             StatusCode::SERVICE_UNAVAILABLE
         })?
         .error_for_status()
@@ -494,7 +1041,7 @@ pub async fn delete_reservation(
         .json::<ReservationServiceResponse>()
         .await
         .map_err(|e| {
-            log::error!("Failed to parse reservation service response: {e}");
+            tracing::error!("Failed to parse reservation service response: {e}");
             StatusCode::INTERNAL_SERVER_ERROR
         })?;
 
@@ -504,10 +1051,10 @@ pub async fn delete_reservation(
             RESERVATION_ENDPOINT, reservation_uid
         ))
         .header("X-User-Name", username)
-        .send()
+        .send_signed()
         .await
         .map_err(|e| {
-            log::error!("Failed to issue request to reservation service: {e}");
+            tracing::error!("Failed to issue request to reservation service: {e}");
             StatusCode::SERVICE_UNAVAILABLE
         })?
         .error_for_status()
@@ -519,26 +1066,37 @@ pub async fn delete_reservation(
             PAYMENT_ENDPOINT, reservation.payment_uid
         ))
         .header("X-User-Name", username)
-        .send()
+        .send_signed()
         .await
         .map_err(|e| {
-            log::error!("Failed to issue request to payment service: {e}");
+            tracing::error!("Failed to issue request to payment service: {e}");
             StatusCode::SERVICE_UNAVAILABLE
         })?
         .error_for_status()
         .map_err(|e| e.status().unwrap_or(StatusCode::INTERNAL_SERVER_ERROR))?;
 
-    client
+    // вместе с бронированием отменяются начисленные по нему и потраченные на него баллы
+    let loyalty = client
         .delete(format!("{}/api/v1/loyalty", LOYALTY_ENDPOINT))
         .header("X-User-Name", username)
-        .send()
+        .query(&[("reservationUid", reservation_uid)])
+        .send_signed()
         .await
         .map_err(|e| {
-            log::error!("Failed to issue request to loyalty service: {e}");
+            tracing::error!("Failed to issue request to loyalty service: {e}");
             StatusCode::SERVICE_UNAVAILABLE
-        })?
-        .error_for_status()
-        .map_err(|e| e.status().unwrap_or(StatusCode::INTERNAL_SERVER_ERROR))?;
+        })?;
+    // бронирования, созданные до учёта по UUID, в программе лояльности не числятся
+    match loyalty.status() {
+        StatusCode::NOT_FOUND => {
+            tracing::warn!("Reservation {reservation_uid} was not counted by loyalty service")
+        }
+        _ => {
+            loyalty
+                .error_for_status()
+                .map_err(|e| e.status().unwrap_or(StatusCode::INTERNAL_SERVER_ERROR))?;
+        }
+    }
 
     Ok(StatusCode::NO_CONTENT)
 }
@@ -563,10 +1121,10 @@ pub async fn get_loyalty(headers: HeaderMap) -> Result<impl IntoResponse, Status
     let resp = reqwest::Client::new()
         .get(format!("{LOYALTY_ENDPOINT}/api/v1/loyalty"))
         .header("X-User-Name", username)
-        .send()
+        .send_signed()
         .await
         .map_err(|e| {
-            log::error!("Failed to issue request to reservation service: {e}");
+            tracing::error!("Failed to issue request to reservation service: {e}");
             StatusCode::SERVICE_UNAVAILABLE
         })?
         .error_for_status()
@@ -574,9 +1132,906 @@ pub async fn get_loyalty(headers: HeaderMap) -> Result<impl IntoResponse, Status
         .json::<LoyaltyInfoResponse>()
         .await
         .map_err(|e| {
-            log::error!("Failed to parse reservation service response: {e}");
+            tracing::error!("Failed to parse reservation service response: {e}");
             StatusCode::INTERNAL_SERVER_ERROR
         })?;
 
     Ok((StatusCode::OK, Json(resp)))
 }
+
+#[utoipa::path(
+    get,
+    path = "/api/v1/loyalty/history",
+    responses(
+        (
+            status = OK,
+            description = "История изменений программы лояльности",
+            body = LoyaltyHistoryResponse,
+            content_type = "application/json",
+        ),
+    ),
+    params(
+        ("X-User-Name", Header, description = "Имя пользователя"),
+        ("page", Query, description = "Номер страницы"),
+        ("size", Query, description = "Количество элементов страницы"),
+    ),
+)]
+pub async fn get_loyalty_history(
+    headers: HeaderMap,
+    Query(query): Query<LoyaltyHistoryRequest>,
+) -> Result<impl IntoResponse, StatusCode> {
+    let username = headers
+        .get("X-User-Name")
+        .ok_or(StatusCode::BAD_REQUEST)?
+        .to_str()
+        .map_err(|_| StatusCode::BAD_REQUEST)?;
+
+    let resp = reqwest::Client::new()
+        .get(format!("{LOYALTY_ENDPOINT}/api/v1/loyalty/history"))
+        .header("X-User-Name", username)
+        .query(&query)
+        .send_signed()
+        .await
+        .map_err(|e| {
+            tracing::error!("Failed to issue request to loyalty service: {e}");
+            StatusCode::SERVICE_UNAVAILABLE
+        })?
+        .error_for_status()
+        .map_err(|e| e.status().unwrap_or(StatusCode::INTERNAL_SERVER_ERROR))?
+        .json::<LoyaltyHistoryResponse>()
+        .await
+        .map_err(|e| {
+            tracing::error!("Failed to parse loyalty service response: {e}");
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?;
+
+    Ok(Json(resp))
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/v1/loyalty/referral-code",
+    responses(
+        (
+            status = OK,
+            description = "Реферальный код пользователя",
+            body = ReferralCodeResponse,
+            content_type = "application/json",
+        ),
+    ),
+    params(
+        ("X-User-Name", Header, description = "Имя пользователя")
+    ),
+)]
+pub async fn post_referral_code(headers: HeaderMap) -> Result<impl IntoResponse, StatusCode> {
+    let username = headers
+        .get("X-User-Name")
+        .ok_or(StatusCode::BAD_REQUEST)?
+        .to_str()
+        .map_err(|_| StatusCode::BAD_REQUEST)?;
+
+    let resp = reqwest::Client::new()
+        .post(format!("{LOYALTY_ENDPOINT}/api/v1/loyalty/referral-code"))
+        .header("X-User-Name", username)
+        .send_signed()
+        .await
+        .map_err(|e| {
+            tracing::error!("Failed to issue request to loyalty service: {e}");
+            StatusCode::SERVICE_UNAVAILABLE
+        })?
+        .error_for_status()
+        .map_err(|e| e.status().unwrap_or(StatusCode::INTERNAL_SERVER_ERROR))?
+        .json::<ReferralCodeResponse>()
+        .await
+        .map_err(|e| {
+            tracing::error!("Failed to parse loyalty service response: {e}");
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?;
+
+    Ok(Json(resp))
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/v1/loyalty/referrals",
+    responses(
+        (
+            status = OK,
+            description = "Приглашённые пользователем участники",
+            body = Vec<ReferralResponse>,
+            content_type = "application/json",
+        ),
+    ),
+    params(
+        ("X-User-Name", Header, description = "Имя пользователя")
+    ),
+)]
+pub async fn get_referrals(headers: HeaderMap) -> Result<impl IntoResponse, StatusCode> {
+    let username = headers
+        .get("X-User-Name")
+        .ok_or(StatusCode::BAD_REQUEST)?
+        .to_str()
+        .map_err(|_| StatusCode::BAD_REQUEST)?;
+
+    let resp = reqwest::Client::new()
+        .get(format!("{LOYALTY_ENDPOINT}/api/v1/loyalty/referrals"))
+        .header("X-User-Name", username)
+        .send_signed()
+        .await
+        .map_err(|e| {
+            tracing::error!("Failed to issue request to loyalty service: {e}");
+            StatusCode::SERVICE_UNAVAILABLE
+        })?
+        .error_for_status()
+        .map_err(|e| e.status().unwrap_or(StatusCode::INTERNAL_SERVER_ERROR))?
+        .json::<Vec<ReferralResponse>>()
+        .await
+        .map_err(|e| {
+            tracing::error!("Failed to parse loyalty service response: {e}");
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?;
+
+    Ok(Json(resp))
+}
+
+// ответ внутреннего сервиса передаётся клиенту как есть
+async fn forward(
+    req: reqwest::RequestBuilder,
+    service: &str,
+) -> Result<impl IntoResponse, StatusCode> {
+    let resp = req.send_signed().await.map_err(|e| {
+        tracing::error!("Failed to issue request to {service} service: {e}");
+        StatusCode::SERVICE_UNAVAILABLE
+    })?;
+
+    let status = resp.status();
+    let mut headers = HeaderMap::new();
+    if let Some(content_type) = resp.headers().get(header::CONTENT_TYPE) {
+        headers.insert(header::CONTENT_TYPE, content_type.clone());
+    }
+    let body = resp.bytes().await.map_err(|e| {
+        tracing::error!("Failed to read {service} service response: {e}");
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
+
+    Ok((status, headers, body))
+}
+
+// значения от клиента попадают в путь только отдельным закодированным сегментом;
+// сегменты "." и ".." url молча пропускает, поэтому они не принимаются
+pub fn service_url(endpoint: &str, segments: &[&str]) -> Result<reqwest::Url, StatusCode> {
+    if segments.iter().any(|s| matches!(*s, "." | "..")) {
+        return Err(StatusCode::BAD_REQUEST);
+    }
+
+    let mut url = reqwest::Url::parse(endpoint).expect("Service endpoint must be a valid URL");
+    url.path_segments_mut()
+        .expect("Service endpoint must be a base URL")
+        .pop_if_empty()
+        .extend(segments);
+    Ok(url)
+}
+
+// уровень с наименьшим порогом
+async fn fetch_base_tier(
+    client: &reqwest::Client,
+) -> Result<LoyaltyTierServiceResponse, StatusCode> {
+    client
+        .get(format!("{}/api/v1/loyalty/tiers", LOYALTY_ENDPOINT))
+        .send_signed()
+        .await
+        .map_err(|e| {
+            tracing::error!("Failed to issue request to loyalty service: {e}");
+            StatusCode::SERVICE_UNAVAILABLE
+        })?
+        .error_for_status()
+        .map_err(|e| e.status().unwrap_or(StatusCode::INTERNAL_SERVER_ERROR))?
+        .json::<Vec<LoyaltyTierServiceResponse>>()
+        .await
+        .map_err(|e| {
+            tracing::error!("Failed to parse loyalty service response: {e}");
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?
+        .into_iter()
+        .min_by_key(|t| t.threshold)
+        .ok_or_else(|| {
+            tracing::error!("Loyalty service has no tiers configured");
+            StatusCode::INTERNAL_SERVER_ERROR
+        })
+}
+
+// наибольшая скидка в валюте баллов, которую не превысит скидка в валюте брони
+pub fn to_points_currency(amount: i32, rate: f64) -> i32 {
+    (amount as f64 * rate).floor() as i32
+}
+
+// скидка за баллы в валюте брони, не больше допустимой
+pub fn from_points_currency(amount: i32, rate: f64, max: i32) -> i32 {
+    ((amount as f64 / rate).round() as i32).min(max)
+}
+
+// курс на сегодня; отсутствие курса означает, что валюта не поддерживается
+async fn fetch_exchange_rate(
+    client: &reqwest::Client,
+    from: &str,
+    to: &str,
+) -> Result<f64, StatusCode> {
+    if from == to {
+        return Ok(1.0);
+    }
+
+    let rate = client
+        .get(format!("{}/api/v1/exchange-rate", PAYMENT_ENDPOINT))
+        .query(&[
+            ("from", from),
+            ("to", to),
+            ("date", &chrono::Local::now().date_naive().to_string()),
+        ])
+        .send_signed()
+        .await
+        .map_err(|e| {
+            tracing::error!("Failed to issue request to payment service: {e}");
+            StatusCode::SERVICE_UNAVAILABLE
+        })?;
+    match rate.status() {
+        StatusCode::NOT_FOUND => {
+            tracing::warn!("No exchange rate from {from} to {to}");
+            Err(StatusCode::BAD_REQUEST)
+        }
+        StatusCode::OK => Ok(rate
+            .json::<ExchangeRateServiceResponse>()
+            .await
+            .map_err(|e| {
+                tracing::error!("Failed to parse payment service response: {e}");
+                StatusCode::INTERNAL_SERVER_ERROR
+            })?
+            .rate),
+        unknown_status_code => Err(unknown_status_code),
+    }
+}
+
+// промокод подтверждается после успешного бронирования, иначе его применение отменяется
+async fn settle_promo_redemption(
+    client: &reqwest::Client,
+    username: &str,
+    redemption_uid: Uuid,
+    capture: bool,
+) {
+    let action = if capture {
+        client.post(format!(
+            "{}/api/v1/promo-redemptions/{}/capture",
+            PAYMENT_ENDPOINT, redemption_uid
+        ))
+    } else {
+        client.delete(format!(
+            "{}/api/v1/promo-redemptions/{}",
+            PAYMENT_ENDPOINT, redemption_uid
+        ))
+    };
+    if let Err(e) = action
+        .header("X-User-Name", username)
+        .send_signed()
+        .await
+        .and_then(|r| r.error_for_status())
+    {
+        tracing::error!("Failed to settle promo code redemption {redemption_uid}: {e}");
+    }
+}
+
+// промокоды и реферальные коды состоят только из букв, цифр, '-' и '_'
+fn is_valid_code(code: &str) -> bool {
+    !code.is_empty()
+        && code
+            .bytes()
+            .all(|b| b.is_ascii_alphanumeric() || b == b'-' || b == b'_')
+}
+
+fn with_query(url: String, query: Option<String>) -> String {
+    match query {
+        Some(query) => format!("{url}?{query}"),
+        None => url,
+    }
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/v1/admin/loyalty/members",
+    responses(
+        (
+            status = OK,
+            description = "Участники программы лояльности",
+            content_type = "application/json",
+        ),
+        (
+            status = FORBIDDEN,
+            description = "Недостаточно прав",
+            body = Problem,
+            content_type = "application/problem+json",
+        ),
+    ),
+    params(
+        ("status", Query, description = "Фильтр по уровню"),
+        ("page", Query, description = "Номер страницы"),
+        ("size", Query, description = "Количество элементов страницы"),
+    ),
+)]
+pub async fn get_admin_loyalty_members(
+    RawQuery(query): RawQuery,
+) -> Result<impl IntoResponse, StatusCode> {
+    forward(
+        reqwest::Client::new().get(with_query(
+            format!("{}/api/v1/admin/loyalty/members", LOYALTY_ENDPOINT),
+            query,
+        )),
+        "loyalty",
+    )
+    .await
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/v1/admin/loyalty/members/{username}",
+    responses(
+        (
+            status = OK,
+            description = "Данные программы лояльности пользователя",
+            body = LoyaltyInfoResponse,
+            content_type = "application/json",
+        ),
+        (
+            status = FORBIDDEN,
+            description = "Недостаточно прав",
+            body = Problem,
+            content_type = "application/problem+json",
+        ),
+    ),
+    params(
+        ("username", Path, description = "Имя пользователя"),
+    ),
+)]
+pub async fn get_admin_loyalty_member(
+    Path(username): Path<String>,
+) -> Result<impl IntoResponse, StatusCode> {
+    forward(
+        reqwest::Client::new().get(service_url(
+            LOYALTY_ENDPOINT,
+            &["api", "v1", "admin", "loyalty", "members", &username],
+        )?),
+        "loyalty",
+    )
+    .await
+}
+
+#[utoipa::path(
+    put,
+    path = "/api/v1/admin/loyalty/members/{username}/reservation-count",
+    request_body = ReservationCountRequest,
+    responses(
+        (
+            status = OK,
+            description = "Счётчик бронирований изменён",
+            body = LoyaltyInfoResponse,
+            content_type = "application/json",
+        ),
+        (
+            status = FORBIDDEN,
+            description = "Недостаточно прав",
+            body = Problem,
+            content_type = "application/problem+json",
+        ),
+    ),
+    params(
+        ("username", Path, description = "Имя пользователя"),
+        ("X-User-Name", Header, description = "Имя сотрудника"),
+    ),
+)]
+pub async fn put_admin_reservation_count(
+    Path(username): Path<String>,
+    headers: HeaderMap,
+    Json(req): Json<ReservationCountRequest>,
+) -> Result<impl IntoResponse, StatusCode> {
+    let actor = headers
+        .get("X-User-Name")
+        .ok_or(StatusCode::BAD_REQUEST)?
+        .to_str()
+        .map_err(|_| StatusCode::BAD_REQUEST)?;
+
+    forward(
+        reqwest::Client::new()
+            .put(service_url(
+                LOYALTY_ENDPOINT,
+                &[
+                    "api",
+                    "v1",
+                    "admin",
+                    "loyalty",
+                    "members",
+                    &username,
+                    "reservation-count",
+                ],
+            )?)
+            .header("X-User-Name", actor)
+            .json(&req),
+        "loyalty",
+    )
+    .await
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/v1/admin/loyalty/members/{username}/points",
+    request_body = PointsGrantRequest,
+    responses(
+        (
+            status = OK,
+            description = "Баллы начислены или списаны",
+            body = LoyaltyInfoResponse,
+            content_type = "application/json",
+        ),
+        (
+            status = FORBIDDEN,
+            description = "Недостаточно прав",
+            body = Problem,
+            content_type = "application/problem+json",
+        ),
+    ),
+    params(
+        ("username", Path, description = "Имя пользователя"),
+        ("X-User-Name", Header, description = "Имя сотрудника"),
+    ),
+)]
+pub async fn post_admin_points(
+    Path(username): Path<String>,
+    headers: HeaderMap,
+    Json(req): Json<PointsGrantRequest>,
+) -> Result<impl IntoResponse, StatusCode> {
+    let actor = headers
+        .get("X-User-Name")
+        .ok_or(StatusCode::BAD_REQUEST)?
+        .to_str()
+        .map_err(|_| StatusCode::BAD_REQUEST)?;
+
+    forward(
+        reqwest::Client::new()
+            .post(service_url(
+                LOYALTY_ENDPOINT,
+                &[
+                    "api", "v1", "admin", "loyalty", "members", &username, "points",
+                ],
+            )?)
+            .header("X-User-Name", actor)
+            .json(&req),
+        "loyalty",
+    )
+    .await
+}
+
+#[utoipa::path(
+    put,
+    path = "/api/v1/admin/loyalty/members/{username}/tier-pin",
+    request_body = TierPinRequest,
+    responses(
+        (
+            status = OK,
+            description = "Уровень закреплён",
+            body = LoyaltyInfoResponse,
+            content_type = "application/json",
+        ),
+        (
+            status = FORBIDDEN,
+            description = "Недостаточно прав",
+            body = Problem,
+            content_type = "application/problem+json",
+        ),
+    ),
+    params(
+        ("username", Path, description = "Имя пользователя"),
+        ("X-User-Name", Header, description = "Имя администратора"),
+    ),
+)]
+pub async fn put_admin_tier_pin(
+    Path(username): Path<String>,
+    headers: HeaderMap,
+    Json(req): Json<TierPinRequest>,
+) -> Result<impl IntoResponse, StatusCode> {
+    let actor = headers
+        .get("X-User-Name")
+        .ok_or(StatusCode::BAD_REQUEST)?
+        .to_str()
+        .map_err(|_| StatusCode::BAD_REQUEST)?;
+
+    forward(
+        reqwest::Client::new()
+            .put(service_url(
+                LOYALTY_ENDPOINT,
+                &[
+                    "api", "v1", "admin", "loyalty", "members", &username, "tier-pin",
+                ],
+            )?)
+            .header("X-User-Name", actor)
+            .json(&req),
+        "loyalty",
+    )
+    .await
+}
+
+#[utoipa::path(
+    delete,
+    path = "/api/v1/admin/loyalty/members/{username}/tier-pin",
+    responses(
+        (
+            status = OK,
+            description = "Закрепление уровня снято",
+            body = LoyaltyInfoResponse,
+            content_type = "application/json",
+        ),
+        (
+            status = FORBIDDEN,
+            description = "Недостаточно прав",
+            body = Problem,
+            content_type = "application/problem+json",
+        ),
+    ),
+    params(
+        ("username", Path, description = "Имя пользователя"),
+        ("reason", Query, description = "Причина"),
+        ("X-User-Name", Header, description = "Имя администратора"),
+    ),
+)]
+pub async fn delete_admin_tier_pin(
+    Path(username): Path<String>,
+    RawQuery(query): RawQuery,
+    headers: HeaderMap,
+) -> Result<impl IntoResponse, StatusCode> {
+    let actor = headers
+        .get("X-User-Name")
+        .ok_or(StatusCode::BAD_REQUEST)?
+        .to_str()
+        .map_err(|_| StatusCode::BAD_REQUEST)?;
+
+    let mut url = service_url(
+        LOYALTY_ENDPOINT,
+        &[
+            "api", "v1", "admin", "loyalty", "members", &username, "tier-pin",
+        ],
+    )?;
+    url.set_query(query.as_deref());
+
+    forward(
+        reqwest::Client::new()
+            .delete(url)
+            .header("X-User-Name", actor),
+        "loyalty",
+    )
+    .await
+}
+
+#[utoipa::path(
+    put,
+    path = "/api/v1/admin/loyalty/tiers",
+    request_body(
+        content = Vec<LoyaltyTierRequest>,
+        description = "Пороги, скидки и льготы уровней BRONZE, SILVER и GOLD; \
+                       набор уровней фиксирован",
+    ),
+    responses(
+        (status = OK, description = "Правила уровней обновлены", content_type = "application/json"),
+        (
+            status = UNPROCESSABLE_ENTITY,
+            description = "Некорректный набор уровней: нужны ровно BRONZE, SILVER и GOLD \
+                           с возрастающими порогами, начиная с 0, и неубывающими скидками",
+        ),
+        (
+            status = FORBIDDEN,
+            description = "Недостаточно прав",
+            body = Problem,
+            content_type = "application/problem+json",
+        ),
+    ),
+)]
+pub async fn put_admin_loyalty_tiers(
+    Json(req): Json<Vec<LoyaltyTierRequest>>,
+) -> Result<impl IntoResponse, StatusCode> {
+    forward(
+        reqwest::Client::new()
+            .put(format!("{}/api/v1/admin/loyalty/tiers", LOYALTY_ENDPOINT))
+            .json(&req),
+        "loyalty",
+    )
+    .await
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/v1/admin/loyalty/stats/{report}",
+    responses(
+        (
+            status = OK,
+            description = "Отчёт по программе лояльности",
+            content_type = "application/json",
+        ),
+        (status = OK, description = "Отчёт в формате CSV", content_type = "text/csv"),
+        (
+            status = FORBIDDEN,
+            description = "Недостаточно прав",
+            body = Problem,
+            content_type = "application/problem+json",
+        ),
+    ),
+    params(
+        ("report", Path, description = "Отчёт: summary, top или tier-migrations"),
+        ("format", Query, description = "Формат ответа: json или csv"),
+    ),
+)]
+pub async fn get_admin_loyalty_stats(
+    Path(report): Path<String>,
+    RawQuery(query): RawQuery,
+) -> Result<impl IntoResponse, StatusCode> {
+    let path = match report.as_str() {
+        "summary" => "/api/v1/admin/loyalty/stats",
+        "top" => "/api/v1/admin/loyalty/stats/top",
+        "tier-migrations" => "/api/v1/admin/loyalty/stats/tier-migrations",
+        _ => return Err(StatusCode::NOT_FOUND),
+    };
+
+    forward(
+        reqwest::Client::new().get(with_query(format!("{LOYALTY_ENDPOINT}{path}"), query)),
+        "loyalty",
+    )
+    .await
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/v1/admin/payments/{paymentUid}/refund",
+    responses(
+        (status = NO_CONTENT, description = "Оплата возвращена"),
+        (
+            status = FORBIDDEN,
+            description = "Недостаточно прав",
+            body = Problem,
+            content_type = "application/problem+json",
+        ),
+    ),
+    params(
+        ("paymentUid", Path, description = "Идентификатор оплаты"),
+        ("X-User-Name", Header, description = "Имя сотрудника"),
+    ),
+)]
+pub async fn post_admin_refund(
+    Path(payment_uid): Path<Uuid>,
+    headers: HeaderMap,
+) -> Result<impl IntoResponse, StatusCode> {
+    let actor = headers
+        .get("X-User-Name")
+        .ok_or(StatusCode::BAD_REQUEST)?
+        .to_str()
+        .map_err(|_| StatusCode::BAD_REQUEST)?;
+
+    tracing::info!("{actor} requested refund of payment {payment_uid}");
+
+    forward(
+        reqwest::Client::new()
+            .post(format!(
+                "{}/api/v1/admin/payments/{}/refund",
+                PAYMENT_ENDPOINT, payment_uid
+            ))
+            .header("X-User-Name", actor),
+        "payment",
+    )
+    .await
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/v1/admin/promo-codes",
+    request_body = PromoCodeRequest,
+    responses(
+        (
+            status = CREATED,
+            description = "Промокод создан",
+            body = PromoCodeResponse,
+            content_type = "application/json",
+        ),
+        (status = CONFLICT, description = "Промокод с таким кодом уже существует"),
+        (
+            status = FORBIDDEN,
+            description = "Недостаточно прав",
+            body = Problem,
+            content_type = "application/problem+json",
+        ),
+    ),
+)]
+pub async fn post_admin_promo_code(
+    Json(req): Json<PromoCodeRequest>,
+) -> Result<impl IntoResponse, StatusCode> {
+    forward(
+        reqwest::Client::new()
+            .post(format!("{}/api/v1/admin/promo-codes", PAYMENT_ENDPOINT))
+            .json(&req),
+        "payment",
+    )
+    .await
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/v1/admin/promo-codes",
+    responses(
+        (
+            status = OK,
+            description = "Список промокодов",
+            body = Vec<PromoCodeResponse>,
+            content_type = "application/json",
+        ),
+        (
+            status = FORBIDDEN,
+            description = "Недостаточно прав",
+            body = Problem,
+            content_type = "application/problem+json",
+        ),
+    ),
+)]
+pub async fn get_admin_promo_codes() -> Result<impl IntoResponse, StatusCode> {
+    forward(
+        reqwest::Client::new().get(format!("{}/api/v1/admin/promo-codes", PAYMENT_ENDPOINT)),
+        "payment",
+    )
+    .await
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/v1/admin/hotels",
+    request_body = HotelRequest,
+    responses(
+        (
+            status = CREATED,
+            description = "Отель добавлен",
+            body = HotelResponse,
+            content_type = "application/json",
+        ),
+        (
+            status = FORBIDDEN,
+            description = "Недостаточно прав",
+            body = Problem,
+            content_type = "application/problem+json",
+        ),
+    ),
+)]
+pub async fn post_admin_hotel(
+    Json(req): Json<HotelRequest>,
+) -> Result<impl IntoResponse, StatusCode> {
+    forward(
+        reqwest::Client::new()
+            .post(format!("{}/api/v1/hotels", RESERVATION_ENDPOINT))
+            .json(&req),
+        "reservation",
+    )
+    .await
+}
+
+#[utoipa::path(
+    put,
+    path = "/api/v1/admin/hotels/{hotelUid}",
+    request_body = HotelRequest,
+    responses(
+        (
+            status = OK,
+            description = "Данные отеля обновлены",
+            body = HotelResponse,
+            content_type = "application/json",
+        ),
+        (
+            status = FORBIDDEN,
+            description = "Недостаточно прав",
+            body = Problem,
+            content_type = "application/problem+json",
+        ),
+    ),
+    params(
+        ("hotelUid", Path, description = "Идентификатор отеля"),
+    ),
+)]
+pub async fn put_admin_hotel(
+    Path(hotel_uid): Path<Uuid>,
+    Json(req): Json<HotelRequest>,
+) -> Result<impl IntoResponse, StatusCode> {
+    forward(
+        reqwest::Client::new()
+            .put(format!(
+                "{}/api/v1/hotel/{}",
+                RESERVATION_ENDPOINT, hotel_uid
+            ))
+            .json(&req),
+        "reservation",
+    )
+    .await
+}
+
+fn service_endpoint(service: &str) -> Result<&'static str, StatusCode> {
+    match service {
+        "reservation" => Ok(RESERVATION_ENDPOINT),
+        "payment" => Ok(PAYMENT_ENDPOINT),
+        "loyalty" => Ok(LOYALTY_ENDPOINT),
+        _ => Err(StatusCode::NOT_FOUND),
+    }
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/v1/admin/log-levels/{service}",
+    responses(
+        (status = OK, body = LogLevels, description = "Текущие уровни журнала сервиса"),
+        (status = NOT_FOUND, description = "Сервис не найден"),
+        (
+            status = FORBIDDEN,
+            description = "Недостаточно прав",
+            body = Problem,
+            content_type = "application/problem+json",
+        ),
+    ),
+    params(
+        ("service", Path, description = "gateway, reservation, payment или loyalty"),
+    ),
+)]
+pub async fn get_admin_log_levels(Path(service): Path<String>) -> Result<Response, StatusCode> {
+    if service == "gateway" {
+        return Ok(Json(logger::levels()).into_response());
+    }
+
+    forward(
+        reqwest::Client::new().get(format!(
+            "{}/api/v1/admin/log-levels",
+            service_endpoint(&service)?
+        )),
+        &service,
+    )
+    .await
+    .map(IntoResponse::into_response)
+}
+
+#[utoipa::path(
+    put,
+    path = "/api/v1/admin/log-levels/{service}",
+    request_body = LogLevels,
+    responses(
+        (status = OK, body = LogLevels, description = "Уровни журнала сервиса изменены"),
+        (status = BAD_REQUEST, description = "Неизвестный уровень журнала"),
+        (status = NOT_FOUND, description = "Сервис не найден"),
+        (
+            status = FORBIDDEN,
+            description = "Недостаточно прав",
+            body = Problem,
+            content_type = "application/problem+json",
+        ),
+    ),
+    params(
+        ("service", Path, description = "gateway, reservation, payment или loyalty"),
+    ),
+)]
+pub async fn put_admin_log_levels(
+    Path(service): Path<String>,
+    Json(levels): Json<LogLevels>,
+) -> Result<Response, StatusCode> {
+    if service == "gateway" {
+        logger::set_levels(levels.clone()).map_err(|e| {
+            tracing::warn!("Rejected log levels: {e}");
+            StatusCode::BAD_REQUEST
+        })?;
+        tracing::info!("Log levels changed to {levels:?}");
+        return Ok(Json(levels).into_response());
+    }
+
+    forward(
+        reqwest::Client::new()
+            .put(format!(
+                "{}/api/v1/admin/log-levels",
+                service_endpoint(&service)?
+            ))
+            .json(&levels),
+        &service,
+    )
+    .await
+    .map(IntoResponse::into_response)
+}
@@ -0,0 +1,95 @@
+use std::{
+    env,
+    sync::OnceLock,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use hmac::{Hmac, Mac};
+use reqwest::{header::HeaderValue, Request, RequestBuilder, Response};
+use sha2::{Digest, Sha256};
+use uuid::Uuid;
+
+use crate::{
+    request_id::{self, REQUEST_ID_HEADER},
+    telemetry,
+};
+
+fn signing_key() -> Option<&'static [u8]> {
+    static KEY: OnceLock<Option<Vec<u8>>> = OnceLock::new();
+    KEY.get_or_init(|| {
+        let key = env::var("SERVICE_SIGNING_KEY")
+            .ok()
+            .filter(|k| !k.is_empty())
+            .map(String::into_bytes);
+        // без ключа запросы уходят неподписанными только при явном разрешении
+        let allow_unsigned =
+            env::var("SIGNATURE_ALLOW_UNSIGNED").is_ok_and(|v| v == "true" || v == "1");
+        if key.is_none() && !allow_unsigned {
+            panic!("SERVICE_SIGNING_KEY is not set and SIGNATURE_ALLOW_UNSIGNED is not enabled");
+        }
+        key
+    })
+    .as_deref()
+}
+
+pub fn init() {
+    if signing_key().is_none() {
+        tracing::warn!("SERVICE_SIGNING_KEY is not set, internal requests are sent unsigned");
+    }
+}
+
+// подпись HMAC-SHA256 от метода, пути с параметрами, пользователя, времени, nonce и тела запроса
+fn sign(key: &[u8], req: &mut Request) {
+    let timestamp = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs();
+    let nonce = Uuid::new_v4().to_string();
+    let body = req.body().and_then(|b| b.as_bytes()).unwrap_or_default();
+    let path = match req.url().query() {
+        Some(query) => format!("{}?{query}", req.url().path()),
+        None => req.url().path().to_owned(),
+    };
+
+    let user = req
+        .headers()
+        .get("X-User-Name")
+        .map_or(&[][..], |v| v.as_bytes());
+
+    let mut mac = Hmac::<Sha256>::new_from_slice(key).expect("HMAC accepts keys of any size");
+    mac.update(format!("{}\n{path}\n", req.method()).as_bytes());
+    mac.update(user);
+    mac.update(
+        format!(
+            "\n{timestamp}\n{nonce}\n{}",
+            hex::encode(Sha256::digest(body))
+        )
+        .as_bytes(),
+    );
+    let signature = hex::encode(mac.finalize().into_bytes());
+
+    let headers = req.headers_mut();
+    headers.insert("X-Signature-Timestamp", HeaderValue::from(timestamp));
+    headers.insert("X-Signature-Nonce", HeaderValue::from_str(&nonce).unwrap());
+    headers.insert("X-Signature", HeaderValue::from_str(&signature).unwrap());
+}
+
+pub trait SendSigned {
+    async fn send_signed(self) -> reqwest::Result<Response>;
+}
+
+impl SendSigned for RequestBuilder {
+    async fn send_signed(self) -> reqwest::Result<Response> {
+        let (client, req) = self.build_split();
+        let mut req = req?;
+        // сервисы пишут в журнал тот же идентификатор запроса, что и шлюз
+        if let Some(value) = request_id::current().and_then(|id| HeaderValue::from_str(&id).ok()) {
+            req.headers_mut().insert(REQUEST_ID_HEADER, value);
+        }
+        if let Some(key) = signing_key() {
+            sign(key, &mut req);
+        }
+
+        telemetry::execute(&client, req).await
+    }
+}
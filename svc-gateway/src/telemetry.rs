@@ -0,0 +1,180 @@
+use std::{env, time::Instant};
+
+use axum::{
+    extract::{MatchedPath, Request},
+    middleware::Next,
+    response::Response,
+};
+use opentelemetry::{global, trace::TracerProvider as _};
+use opentelemetry_http::{HeaderExtractor, HeaderInjector};
+use opentelemetry_sdk::{
+    error::OTelSdkResult,
+    propagation::TraceContextPropagator,
+    trace::{SdkTracerProvider, SpanData, SpanExporter},
+    Resource,
+};
+use reqwest::Client;
+use tracing::{field, Instrument, Level};
+use tracing_opentelemetry::OpenTelemetrySpanExt;
+use tracing_subscriber::{filter::Targets, layer::SubscriberExt, util::SubscriberInitExt};
+
+use crate::request_id;
+
+// куда отправлять спаны: otlp (адрес из OTEL_EXPORTER_OTLP_ENDPOINT), stdout или none
+pub fn init(service_name: &str) -> SdkTracerProvider {
+    global::set_text_map_propagator(TraceContextPropagator::new());
+
+    let exporter = env::var("OTEL_TRACES_EXPORTER").unwrap_or_else(|_| {
+        match env::var("OTEL_EXPORTER_OTLP_ENDPOINT") {
+            Ok(_) => "otlp".to_owned(),
+            Err(_) => "none".to_owned(),
+        }
+    });
+    let resource = Resource::builder()
+        .with_service_name(
+            env::var("OTEL_SERVICE_NAME").unwrap_or_else(|_| service_name.to_owned()),
+        )
+        .build();
+    let builder = SdkTracerProvider::builder().with_resource(resource);
+    let provider = match exporter.as_str() {
+        "otlp" => match opentelemetry_otlp::SpanExporter::builder()
+            .with_http()
+            .build()
+        {
+            Ok(exporter) => builder.with_batch_exporter(exporter),
+            Err(e) => {
+                tracing::error!("Failed to create OTLP exporter: {e}");
+                builder
+            }
+        },
+        "stdout" => builder.with_simple_exporter(StdoutExporter),
+        "none" => builder,
+        other => {
+            tracing::warn!("Unknown traces exporter {other}, spans are not exported");
+            builder
+        }
+    }
+    .build();
+    global::set_tracer_provider(provider.clone());
+
+    // события по-прежнему пишутся в журнал через log4rs, подписчик нужен только для спанов
+    tracing_subscriber::registry()
+        .with(tracing_opentelemetry::layer().with_tracer(provider.tracer(service_name.to_owned())))
+        .with(
+            Targets::new()
+                .with_target(env!("CARGO_CRATE_NAME"), Level::DEBUG)
+                .with_default(Level::INFO),
+        )
+        .init();
+
+    provider
+}
+
+// серверный спан на каждый запрос, родительский контекст берётся из заголовка traceparent
+pub async fn trace_request(req: Request, next: Next) -> Response {
+    let route = req
+        .extensions()
+        .get::<MatchedPath>()
+        .map_or(req.uri().path(), MatchedPath::as_str)
+        .to_owned();
+    let span = tracing::info_span!(
+        "request",
+        otel.name = format!("{} {route}", req.method()),
+        otel.kind = "server",
+        otel.status_code = field::Empty,
+        http.request.method = %req.method(),
+        http.route = route,
+        http.response.status_code = field::Empty,
+        request_id = request_id::current(),
+    );
+    let parent = global::get_text_map_propagator(|p| p.extract(&HeaderExtractor(req.headers())));
+    let _ = span.set_parent(parent);
+
+    let resp = next.run(req).instrument(span.clone()).await;
+    span.record("http.response.status_code", resp.status().as_u16());
+    if resp.status().is_server_error() {
+        span.record("otel.status_code", "ERROR");
+    }
+    resp
+}
+
+// для локального запуска: одна строка на завершённый спан
+#[derive(Debug)]
+struct StdoutExporter;
+
+impl SpanExporter for StdoutExporter {
+    async fn export(&self, batch: Vec<SpanData>) -> OTelSdkResult {
+        for span in batch {
+            let duration = span
+                .end_time
+                .duration_since(span.start_time)
+                .unwrap_or_default();
+            let attributes = span
+                .attributes
+                .iter()
+                .map(|kv| format!("{}={}", kv.key, kv.value))
+                .collect::<Vec<_>>()
+                .join(" ");
+            println!(
+                "span trace_id={} span_id={} parent_id={} name=\"{}\" duration={:.3}ms {attributes}",
+                span.span_context.trace_id(),
+                span.span_context.span_id(),
+                span.parent_span_id,
+                span.name,
+                duration.as_secs_f64() * 1000.0,
+            );
+        }
+
+        Ok(())
+    }
+}
+
+// клиентский спан и метрики на вызов сервиса, контекст спана передаётся дальше в заголовке traceparent
+pub async fn execute(
+    client: &Client,
+    mut req: reqwest::Request,
+) -> reqwest::Result<reqwest::Response> {
+    let service = req.url().host_str().unwrap_or_default().to_owned();
+    let method = req.method().to_string();
+    let span = tracing::info_span!(
+        "upstream",
+        otel.name = format!("{method} {service}"),
+        otel.kind = "client",
+        otel.status_code = field::Empty,
+        http.request.method = %req.method(),
+        url.full = %req.url(),
+        http.response.status_code = field::Empty,
+    );
+    let cx = span.context();
+    global::get_text_map_propagator(|p| {
+        p.inject_context(&cx, &mut HeaderInjector(req.headers_mut()))
+    });
+
+    let started_at = Instant::now();
+    let resp = client.execute(req).instrument(span.clone()).await;
+    let elapsed = started_at.elapsed();
+
+    let (status, error) = match &resp {
+        Ok(resp) => {
+            span.record("http.response.status_code", resp.status().as_u16());
+            let error = resp.status().is_server_error().then_some("status");
+            (resp.status().as_u16().to_string(), error)
+        }
+        Err(e) if e.is_timeout() => ("none".to_owned(), Some("timeout")),
+        Err(e) if e.is_connect() => ("none".to_owned(), Some("connect")),
+        Err(_) => ("none".to_owned(), Some("request")),
+    };
+    metrics::histogram!(
+        "upstream_request_duration_seconds",
+        "service" => service.clone(),
+        "method" => method,
+        "status" => status
+    )
+    .record(elapsed);
+    if let Some(kind) = error {
+        span.record("otel.status_code", "ERROR");
+        metrics::counter!("upstream_errors_total", "service" => service, "kind" => kind)
+            .increment(1);
+    }
+    resp
+}
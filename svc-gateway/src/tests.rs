@@ -1,2 +1,217 @@
+use std::time::Duration;
+
+use axum::http::StatusCode;
+use serde_json::{json, Map, Value};
+
+use crate::{
+    auth::identity_from_claims,
+    authz::Role,
+    logger::LogLevels,
+    rate_limit::{MemoryStore, RateLimitRule, RateLimitStore},
+    routes::{from_points_currency, service_url, to_points_currency},
+};
+
 #[test]
 fn hello_world() {}
+
+fn claims(value: Value) -> Map<String, Value> {
+    value.as_object().unwrap().clone()
+}
+
+#[test]
+fn identity_from_flat_claims() {
+    let identity = identity_from_claims(
+        &claims(json!({"sub": "Test Max", "roles": ["admin", "unknown"]})),
+        "sub",
+        "roles",
+    )
+    .unwrap();
+
+    assert_eq!(identity.username, "Test Max");
+    // роль user есть у любого аутентифицированного пользователя
+    assert_eq!(identity.roles, [Role::Admin, Role::User]);
+}
+
+#[test]
+fn identity_from_nested_and_string_roles() {
+    let identity = identity_from_claims(
+        &claims(json!({
+            "preferred_username": "Test Max",
+            "realm_access": {"roles": ["Support", "user"]},
+        })),
+        "preferred_username",
+        "realm_access.roles",
+    )
+    .unwrap();
+    assert_eq!(identity.roles, [Role::Support, Role::User]);
+
+    let identity = identity_from_claims(
+        &claims(json!({"sub": "Test Max", "scope": "support admin,user"})),
+        "sub",
+        "scope",
+    )
+    .unwrap();
+    assert_eq!(identity.roles, [Role::Support, Role::Admin, Role::User]);
+}
+
+#[test]
+fn identity_without_roles_claim_is_user() {
+    for value in [
+        json!({"sub": "Test Max"}),
+        json!({"sub": "Test Max", "roles": 42}),
+        json!({"sub": "Test Max", "realm_access": "admin"}),
+    ] {
+        let identity = identity_from_claims(&claims(value), "sub", "realm_access.roles").unwrap();
+        assert_eq!(identity.roles, [Role::User]);
+    }
+}
+
+#[test]
+fn identity_requires_username_claim() {
+    for value in [
+        json!({"roles": ["admin"]}),
+        json!({"sub": "", "roles": ["admin"]}),
+        json!({"sub": 42}),
+    ] {
+        assert_eq!(
+            identity_from_claims(&claims(value), "sub", "roles").err(),
+            Some("claim sub is missing".to_owned())
+        );
+    }
+}
+
+fn rule(capacity: u32, refill_per_second: f64) -> RateLimitRule {
+    RateLimitRule {
+        method: Some("POST".to_owned()),
+        path: "/api/v1/reservations".to_owned(),
+        capacity,
+        refill_per_second,
+    }
+}
+
+#[test]
+fn memory_store_takes_until_empty() {
+    let store = MemoryStore::default();
+    let rule = rule(2, 0.5);
+    let keys = ["ip:10.0.0.1".to_owned()];
+
+    let first = store.take(&keys, &rule);
+    assert!(first[0].allowed);
+    assert_eq!(first[0].limit, 2);
+    assert_eq!(first[0].remaining, 1);
+
+    let second = store.take(&keys, &rule);
+    assert!(second[0].allowed);
+    assert_eq!(second[0].remaining, 0);
+
+    let denied = store.take(&keys, &rule);
+    assert!(!denied[0].allowed);
+    assert_eq!(denied[0].remaining, 0);
+    // один токен восстанавливается за две секунды
+    assert!(denied[0].retry_after > Duration::from_millis(1900));
+    assert!(denied[0].retry_after <= Duration::from_secs(2));
+    assert!(denied[0].reset <= Duration::from_secs(4));
+
+    // корзины разных ключей независимы
+    assert!(store.take(&["ip:10.0.0.2".to_owned()], &rule)[0].allowed);
+}
+
+#[test]
+fn memory_store_without_refill_never_resets() {
+    let store = MemoryStore::default();
+    let rule = rule(1, 0.0);
+    let keys = ["user:Test Max".to_owned()];
+
+    assert!(store.take(&keys, &rule)[0].allowed);
+    let denied = store.take(&keys, &rule);
+    assert!(!denied[0].allowed);
+    assert_eq!(denied[0].retry_after, Duration::MAX);
+}
+
+#[test]
+fn memory_store_takes_from_all_buckets_or_none() {
+    let store = MemoryStore::default();
+    let rule = rule(2, 0.0);
+    let ip = "ip:10.0.0.1".to_owned();
+    let user = "user:Test Max".to_owned();
+
+    store.take(std::slice::from_ref(&user), &rule);
+    store.take(std::slice::from_ref(&user), &rule);
+
+    let decisions = store.take(&[ip.clone(), user], &rule);
+    assert!(decisions[0].allowed);
+    assert!(!decisions[1].allowed);
+    // отказ по пользователю не тратит токены адреса
+    assert_eq!(decisions[0].remaining, 2);
+
+    let decisions = store.take(std::slice::from_ref(&ip), &rule);
+    assert!(decisions[0].allowed);
+    assert_eq!(decisions[0].remaining, 1);
+}
+
+#[test]
+fn log_levels_parse_directives() {
+    let levels =
+        LogLevels::parse(" info, diesel=warn ,bmstu_rsoi_lab2_gateway::routes=debug,,").unwrap();
+
+    assert_eq!(levels.root, "info");
+    assert_eq!(levels.modules.len(), 2);
+    assert_eq!(levels.modules["diesel"], "warn");
+    assert_eq!(levels.modules["bmstu_rsoi_lab2_gateway::routes"], "debug");
+}
+
+#[test]
+fn log_levels_parse_defaults_and_errors() {
+    let levels = LogLevels::parse("").unwrap();
+    assert_eq!(levels.root, "debug");
+    assert!(levels.modules.is_empty());
+
+    assert_eq!(
+        LogLevels::parse("verbose").err(),
+        Some("unknown log level verbose".to_owned())
+    );
+    assert_eq!(
+        LogLevels::parse("info,diesel=loud").err(),
+        Some("unknown log level loud".to_owned())
+    );
+}
+
+#[test]
+fn service_url_encodes_each_segment() {
+    let endpoint = "http://loyalty-bmstu-rsoi:8050";
+
+    // имя пользователя не может выйти за пределы своего сегмента
+    let url = service_url(endpoint, &["members", "../tiers?", "points"]).unwrap();
+    assert_eq!(
+        url.as_str(),
+        "http://loyalty-bmstu-rsoi:8050/members/..%2Ftiers%3F/points"
+    );
+    assert_eq!(url.query(), None);
+
+    assert_eq!(
+        service_url(endpoint, &["members", "..", "points"]).err(),
+        Some(StatusCode::BAD_REQUEST)
+    );
+    assert_eq!(
+        service_url(endpoint, &["members", "."]).err(),
+        Some(StatusCode::BAD_REQUEST)
+    );
+}
+
+#[test]
+fn points_discount_in_booking_currency() {
+    // бронь в EUR, баллы в RUB по курсу 100
+    let rate = 100.0;
+    let max_discount = 50;
+
+    assert_eq!(to_points_currency(max_discount, rate), 5000);
+    // 1000 баллов дают 1000 RUB, то есть 10 EUR, а не 1000 EUR
+    assert_eq!(from_points_currency(1000, rate, max_discount), 10);
+    assert_eq!(from_points_currency(5000, rate, max_discount), 50);
+
+    // бронь в RUB
+    assert_eq!(to_points_currency(300, 1.0), 300);
+    assert_eq!(from_points_currency(300, 1.0, 300), 300);
+    // округление не превышает допустимую скидку
+    assert_eq!(from_points_currency(99, 0.011, 9_000), 9_000);
+}
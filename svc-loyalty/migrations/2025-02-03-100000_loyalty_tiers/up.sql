@@ -0,0 +1,14 @@
+CREATE TABLE IF NOT EXISTS loyalty_tier
+(
+    id        SERIAL PRIMARY KEY,
+    name      VARCHAR(80) NOT NULL UNIQUE
+        CHECK (name IN ('BRONZE', 'SILVER', 'GOLD')),
+    threshold INT         NOT NULL UNIQUE CHECK (threshold >= 0),
+    discount  INT         NOT NULL CHECK (discount BETWEEN 0 AND 100),
+    perks     TEXT[]      NOT NULL DEFAULT '{}'
+);
+
+INSERT INTO loyalty_tier(name, threshold, discount)
+VALUES ('BRONZE', 0, 5),
+       ('SILVER', 10, 7),
+       ('GOLD', 20, 10);
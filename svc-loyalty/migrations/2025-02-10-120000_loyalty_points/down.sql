@@ -0,0 +1,7 @@
+DROP TABLE IF EXISTS points_hold;
+
+ALTER TABLE loyalty_tier
+    DROP COLUMN points_rate;
+
+ALTER TABLE loyalty
+    DROP COLUMN points;
@@ -0,0 +1,21 @@
+ALTER TABLE loyalty
+    ADD COLUMN points INT NOT NULL DEFAULT 0 CHECK (points >= 0);
+
+ALTER TABLE loyalty_tier
+    ADD COLUMN points_rate INT NOT NULL DEFAULT 0 CHECK (points_rate >= 0);
+
+UPDATE loyalty_tier SET points_rate = 1 WHERE name = 'BRONZE';
+UPDATE loyalty_tier SET points_rate = 2 WHERE name = 'SILVER';
+UPDATE loyalty_tier SET points_rate = 3 WHERE name = 'GOLD';
+
+CREATE TABLE IF NOT EXISTS points_hold
+(
+    id              SERIAL PRIMARY KEY,
+    hold_uid        UUID        NOT NULL UNIQUE,
+    username        VARCHAR(80) NOT NULL,
+    points          INT         NOT NULL CHECK (points > 0),
+    reservation_uid UUID,
+    status          VARCHAR(20) NOT NULL
+        CHECK (status IN ('HELD', 'CAPTURED', 'RELEASED')),
+    created_at      TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT now()
+);
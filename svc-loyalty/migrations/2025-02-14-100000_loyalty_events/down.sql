@@ -0,0 +1,3 @@
+DROP TABLE IF EXISTS loyalty_events;
+
+DROP FUNCTION IF EXISTS loyalty_events_append_only();
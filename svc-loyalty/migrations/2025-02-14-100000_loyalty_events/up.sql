@@ -0,0 +1,31 @@
+CREATE TABLE IF NOT EXISTS loyalty_events
+(
+    id                SERIAL PRIMARY KEY,
+    username          VARCHAR(80) NOT NULL,
+    kind              VARCHAR(40) NOT NULL
+        CHECK (kind IN ('RESERVATION_ADDED', 'RESERVATION_REMOVED', 'TIER_CHANGED',
+                        'POINTS_ACCRUED', 'POINTS_HELD', 'POINTS_CAPTURED', 'POINTS_RELEASED')),
+    reservation_uid   UUID,
+    reservation_count INT,
+    points            INT         NOT NULL DEFAULT 0,
+    status_from       VARCHAR(80),
+    status_to         VARCHAR(80),
+    created_at        TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT now()
+);
+
+CREATE INDEX IF NOT EXISTS loyalty_events_username_idx
+    ON loyalty_events (username, id);
+
+-- история только дополняется
+CREATE OR REPLACE FUNCTION loyalty_events_append_only() RETURNS TRIGGER AS
+$$
+BEGIN
+    RAISE EXCEPTION 'loyalty_events is append-only';
+END;
+$$ LANGUAGE plpgsql;
+
+CREATE TRIGGER loyalty_events_append_only
+    BEFORE UPDATE OR DELETE
+    ON loyalty_events
+    FOR EACH ROW
+EXECUTE FUNCTION loyalty_events_append_only();
@@ -0,0 +1,13 @@
+ALTER TABLE loyalty_events
+    DROP CONSTRAINT loyalty_events_kind_check,
+    ADD CONSTRAINT loyalty_events_kind_check
+        CHECK (kind IN ('RESERVATION_ADDED', 'RESERVATION_REMOVED', 'TIER_CHANGED',
+                        'POINTS_ACCRUED', 'POINTS_HELD', 'POINTS_CAPTURED', 'POINTS_RELEASED'));
+
+DROP INDEX IF EXISTS loyalty_events_kind_idx;
+
+DROP INDEX IF EXISTS loyalty_requalify_at_idx;
+
+ALTER TABLE loyalty
+    DROP COLUMN downgrade_at,
+    DROP COLUMN requalify_at;
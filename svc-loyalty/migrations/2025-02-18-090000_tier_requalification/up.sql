@@ -0,0 +1,17 @@
+-- текущие участники сохраняют уровень до первой переаттестации
+ALTER TABLE loyalty
+    ADD COLUMN requalify_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT now() + INTERVAL '1 year',
+    ADD COLUMN downgrade_at TIMESTAMP WITH TIME ZONE;
+
+CREATE INDEX IF NOT EXISTS loyalty_requalify_at_idx
+    ON loyalty (requalify_at);
+
+CREATE INDEX IF NOT EXISTS loyalty_events_kind_idx
+    ON loyalty_events (username, kind, created_at);
+
+ALTER TABLE loyalty_events
+    DROP CONSTRAINT loyalty_events_kind_check,
+    ADD CONSTRAINT loyalty_events_kind_check
+        CHECK (kind IN ('RESERVATION_ADDED', 'RESERVATION_REMOVED', 'TIER_CHANGED',
+                        'TIER_DOWNGRADE_SCHEDULED', 'POINTS_ACCRUED', 'POINTS_HELD',
+                        'POINTS_CAPTURED', 'POINTS_RELEASED'));
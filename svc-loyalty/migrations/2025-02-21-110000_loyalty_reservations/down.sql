@@ -0,0 +1,4 @@
+ALTER TABLE loyalty
+    DROP CONSTRAINT loyalty_reservation_count_check;
+
+DROP TABLE IF EXISTS loyalty_reservation;
@@ -0,0 +1,32 @@
+CREATE TABLE IF NOT EXISTS loyalty_reservation
+(
+    id              SERIAL PRIMARY KEY,
+    reservation_uid UUID        NOT NULL UNIQUE,
+    username        VARCHAR(80) NOT NULL,
+    counted_at      TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT now(),
+    canceled_at     TIMESTAMP WITH TIME ZONE
+);
+
+CREATE INDEX IF NOT EXISTS loyalty_reservation_username_idx
+    ON loyalty_reservation (username, counted_at);
+
+-- бронирования, уже учтённые до появления таблицы
+INSERT INTO loyalty_reservation (reservation_uid, username, counted_at)
+SELECT DISTINCT ON (reservation_uid) reservation_uid, username, created_at
+FROM loyalty_events
+WHERE kind = 'RESERVATION_ADDED'
+  AND reservation_uid IS NOT NULL
+ORDER BY reservation_uid, created_at;
+
+UPDATE loyalty_reservation r
+SET canceled_at = e.created_at
+FROM loyalty_events e
+WHERE e.kind = 'RESERVATION_REMOVED'
+  AND e.reservation_uid = r.reservation_uid;
+
+UPDATE loyalty
+SET reservation_count = 0
+WHERE reservation_count < 0;
+
+ALTER TABLE loyalty
+    ADD CONSTRAINT loyalty_reservation_count_check CHECK (reservation_count >= 0);
@@ -0,0 +1,17 @@
+ALTER TABLE loyalty_events
+    DROP CONSTRAINT loyalty_events_kind_check,
+    ADD CONSTRAINT loyalty_events_kind_check
+        CHECK (kind IN ('RESERVATION_ADDED', 'RESERVATION_REMOVED', 'TIER_CHANGED',
+                        'TIER_DOWNGRADE_SCHEDULED', 'POINTS_ACCRUED', 'POINTS_HELD',
+                        'POINTS_CAPTURED', 'POINTS_RELEASED')),
+    DROP COLUMN discount;
+
+ALTER TABLE loyalty_reservation
+    DROP COLUMN stay_end_date;
+
+ALTER TABLE loyalty
+    DROP COLUMN bonus_discount;
+
+DROP TABLE IF EXISTS referral;
+
+DROP TABLE IF EXISTS referral_code;
@@ -0,0 +1,40 @@
+CREATE TABLE IF NOT EXISTS referral_code
+(
+    id         SERIAL PRIMARY KEY,
+    code       VARCHAR(20) NOT NULL UNIQUE,
+    username   VARCHAR(80) NOT NULL UNIQUE,
+    created_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT now()
+);
+
+CREATE TABLE IF NOT EXISTS referral
+(
+    id              SERIAL PRIMARY KEY,
+    code            VARCHAR(20) NOT NULL,
+    referrer        VARCHAR(80) NOT NULL,
+    referee         VARCHAR(80) NOT NULL UNIQUE,
+    reservation_uid UUID        NOT NULL,
+    status          VARCHAR(20) NOT NULL
+        CHECK (status IN ('PENDING', 'COMPLETED', 'CANCELED')),
+    created_at      TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT now(),
+    completed_at    TIMESTAMP WITH TIME ZONE
+);
+
+CREATE INDEX IF NOT EXISTS referral_referrer_idx
+    ON referral (referrer);
+
+-- разовая скидка в процентах, применяется к следующему бронированию
+ALTER TABLE loyalty
+    ADD COLUMN bonus_discount INT NOT NULL DEFAULT 0
+        CHECK (bonus_discount >= 0 AND bonus_discount <= 100);
+
+ALTER TABLE loyalty_reservation
+    ADD COLUMN stay_end_date DATE;
+
+ALTER TABLE loyalty_events
+    ADD COLUMN discount INT,
+    DROP CONSTRAINT loyalty_events_kind_check,
+    ADD CONSTRAINT loyalty_events_kind_check
+        CHECK (kind IN ('RESERVATION_ADDED', 'RESERVATION_REMOVED', 'TIER_CHANGED',
+                        'TIER_DOWNGRADE_SCHEDULED', 'POINTS_ACCRUED', 'POINTS_HELD',
+                        'POINTS_CAPTURED', 'POINTS_RELEASED', 'REFERRAL_BONUS',
+                        'BONUS_DISCOUNT_USED'));
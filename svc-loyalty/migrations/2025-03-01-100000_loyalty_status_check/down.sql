@@ -0,0 +1,2 @@
+ALTER TABLE loyalty_events
+    DROP CONSTRAINT loyalty_events_status_check;
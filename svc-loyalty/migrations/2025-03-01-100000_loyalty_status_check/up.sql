@@ -0,0 +1,4 @@
+ALTER TABLE loyalty_events
+    ADD CONSTRAINT loyalty_events_status_check
+        CHECK (status_from IN ('BRONZE', 'SILVER', 'GOLD')
+            AND status_to IN ('BRONZE', 'SILVER', 'GOLD'));
@@ -0,0 +1,15 @@
+DROP INDEX IF EXISTS loyalty_status_idx;
+
+ALTER TABLE loyalty_events
+    DROP CONSTRAINT loyalty_events_kind_check,
+    ADD CONSTRAINT loyalty_events_kind_check
+        CHECK (kind IN ('RESERVATION_ADDED', 'RESERVATION_REMOVED', 'TIER_CHANGED',
+                        'TIER_DOWNGRADE_SCHEDULED', 'POINTS_ACCRUED', 'POINTS_HELD',
+                        'POINTS_CAPTURED', 'POINTS_RELEASED', 'REFERRAL_BONUS',
+                        'BONUS_DISCOUNT_USED')),
+    DROP COLUMN reason,
+    DROP COLUMN actor;
+
+ALTER TABLE loyalty
+    DROP COLUMN pinned_until,
+    DROP COLUMN pinned_status;
@@ -0,0 +1,18 @@
+ALTER TABLE loyalty
+    ADD COLUMN pinned_status VARCHAR(80)
+        CHECK (pinned_status IN ('BRONZE', 'SILVER', 'GOLD')),
+    ADD COLUMN pinned_until  TIMESTAMP WITH TIME ZONE;
+
+ALTER TABLE loyalty_events
+    ADD COLUMN actor  VARCHAR(80),
+    ADD COLUMN reason TEXT,
+    DROP CONSTRAINT loyalty_events_kind_check,
+    ADD CONSTRAINT loyalty_events_kind_check
+        CHECK (kind IN ('RESERVATION_ADDED', 'RESERVATION_REMOVED', 'TIER_CHANGED',
+                        'TIER_DOWNGRADE_SCHEDULED', 'POINTS_ACCRUED', 'POINTS_HELD',
+                        'POINTS_CAPTURED', 'POINTS_RELEASED', 'REFERRAL_BONUS',
+                        'BONUS_DISCOUNT_USED', 'RESERVATION_COUNT_ADJUSTED',
+                        'POINTS_GRANTED', 'TIER_PINNED', 'TIER_UNPINNED'));
+
+CREATE INDEX IF NOT EXISTS loyalty_status_idx
+    ON loyalty (status);
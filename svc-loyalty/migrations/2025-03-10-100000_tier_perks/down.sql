@@ -0,0 +1,4 @@
+ALTER TABLE loyalty_tier
+    DROP COLUMN free_night_after,
+    DROP COLUMN late_checkout,
+    DROP COLUMN cancellation_extension_hours;
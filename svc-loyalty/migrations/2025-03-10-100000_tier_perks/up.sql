@@ -0,0 +1,15 @@
+ALTER TABLE loyalty_tier
+    ADD COLUMN cancellation_extension_hours INT     NOT NULL DEFAULT 0
+        CHECK (cancellation_extension_hours >= 0),
+    ADD COLUMN late_checkout                BOOLEAN NOT NULL DEFAULT FALSE,
+    -- каждое N-е проживание участника включает одну бесплатную ночь
+    ADD COLUMN free_night_after             INT CHECK (free_night_after > 0);
+
+UPDATE loyalty_tier
+SET cancellation_extension_hours = 24
+WHERE name = 'SILVER';
+
+UPDATE loyalty_tier
+SET cancellation_extension_hours = 48,
+    late_checkout                = TRUE
+WHERE name = 'GOLD';
@@ -0,0 +1 @@
+DROP INDEX IF EXISTS loyalty_events_kind_created_at_idx;
@@ -0,0 +1,2 @@
+CREATE INDEX IF NOT EXISTS loyalty_events_kind_created_at_idx
+    ON loyalty_events (kind, created_at);
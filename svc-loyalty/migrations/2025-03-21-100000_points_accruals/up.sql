@@ -0,0 +1,29 @@
+CREATE TABLE IF NOT EXISTS points_accrual
+(
+    id              SERIAL PRIMARY KEY,
+    reservation_uid UUID        NOT NULL,
+    username        VARCHAR(80) NOT NULL,
+    kind            VARCHAR(20) NOT NULL
+        CHECK (kind IN ('ACCRUED', 'REVERSED')),
+    points          INT         NOT NULL,
+    created_at      TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT now(),
+    UNIQUE (reservation_uid, kind)
+);
+
+-- начисления до появления таблицы; повторные начисления по одной брони суммируются
+INSERT INTO points_accrual (reservation_uid, username, kind, points, created_at)
+SELECT reservation_uid, MIN(username), 'ACCRUED', SUM(points), MIN(created_at)
+FROM loyalty_events
+WHERE kind = 'POINTS_ACCRUED'
+  AND reservation_uid IS NOT NULL
+GROUP BY reservation_uid;
+
+ALTER TABLE loyalty_events
+    DROP CONSTRAINT loyalty_events_kind_check,
+    ADD CONSTRAINT loyalty_events_kind_check
+        CHECK (kind IN ('RESERVATION_ADDED', 'RESERVATION_REMOVED', 'TIER_CHANGED',
+                        'TIER_DOWNGRADE_SCHEDULED', 'POINTS_ACCRUED', 'POINTS_HELD',
+                        'POINTS_CAPTURED', 'POINTS_RELEASED', 'REFERRAL_BONUS',
+                        'BONUS_DISCOUNT_USED', 'RESERVATION_COUNT_ADJUSTED',
+                        'POINTS_GRANTED', 'TIER_PINNED', 'TIER_UNPINNED',
+                        'POINTS_REVERSED'));
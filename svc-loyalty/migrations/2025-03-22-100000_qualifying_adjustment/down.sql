@@ -0,0 +1,2 @@
+ALTER TABLE loyalty
+    DROP COLUMN qualifying_adjustment;
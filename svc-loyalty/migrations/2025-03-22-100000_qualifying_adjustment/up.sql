@@ -0,0 +1,3 @@
+-- поправка администратора к числу бронирований, учитываемых при переаттестации
+ALTER TABLE loyalty
+    ADD COLUMN qualifying_adjustment INT NOT NULL DEFAULT 0;
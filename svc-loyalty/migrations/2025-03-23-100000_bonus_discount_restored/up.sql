@@ -0,0 +1,9 @@
+ALTER TABLE loyalty_events
+    DROP CONSTRAINT loyalty_events_kind_check,
+    ADD CONSTRAINT loyalty_events_kind_check
+        CHECK (kind IN ('RESERVATION_ADDED', 'RESERVATION_REMOVED', 'TIER_CHANGED',
+                        'TIER_DOWNGRADE_SCHEDULED', 'POINTS_ACCRUED', 'POINTS_HELD',
+                        'POINTS_CAPTURED', 'POINTS_RELEASED', 'REFERRAL_BONUS',
+                        'BONUS_DISCOUNT_USED', 'RESERVATION_COUNT_ADJUSTED',
+                        'POINTS_GRANTED', 'TIER_PINNED', 'TIER_UNPINNED',
+                        'POINTS_REVERSED', 'BONUS_DISCOUNT_RESTORED'));
@@ -1,33 +1,157 @@
-use diesel::prelude::*;
-use serde::Serialize;
+use std::{fmt::Display, io::Write, str::FromStr};
+
+use chrono::{DateTime, NaiveDate};
+use diesel::{
+    deserialize::{self, FromSql, FromSqlRow},
+    expression::AsExpression,
+    pg::{Pg, PgValue},
+    prelude::*,
+    serialize::{self, IsNull, Output, ToSql},
+    sql_types::Text,
+};
+use serde::{Deserialize, Serialize};
 use utoipa::ToSchema;
+use uuid::Uuid;
+
+// стоимость одного балла в валюте бронирования
+pub const POINT_VALUE: i32 = 1;
+// валюта, в которой считаются начисляемые баллы
+pub const POINTS_CURRENCY: &str = "RUB";
 
-#[derive(Serialize, Queryable, Selectable, Insertable)]
+#[derive(Serialize, Queryable, Selectable, Insertable, ToSchema)]
 #[diesel(table_name = crate::schema::loyalty)]
 #[diesel(check_for_backend(diesel::pg::Pg))]
+#[serde(rename_all = "camelCase")]
 pub struct Loyalty {
     pub username: String,
     pub reservation_count: i32,
-    pub status: String,
+    pub status: LoyaltyStatus,
     pub discount: i32,
+    pub points: i32,
+    pub requalify_at: DateTime<chrono::Local>,
+    pub downgrade_at: Option<DateTime<chrono::Local>>,
+    pub bonus_discount: i32,
+    pub pinned_status: Option<LoyaltyStatus>,
+    pub pinned_until: Option<DateTime<chrono::Local>>,
 }
 
 impl Loyalty {
-    pub fn new(username: String) -> Self {
+    pub fn new(
+        username: String,
+        tier: &LoyaltyTier,
+        requalify_at: DateTime<chrono::Local>,
+    ) -> Self {
         Self {
             username,
             reservation_count: 1,
-            status: "BRONZE".to_owned(),
-            discount: 5,
+            status: tier.name,
+            discount: tier.discount,
+            points: 0,
+            requalify_at,
+            downgrade_at: None,
+            bonus_discount: 0,
+            pinned_status: None,
+            pinned_until: None,
+        }
+    }
+}
+
+// хранится в текстовых колонках с CHECK-ограничением на допустимые значения
+#[derive(
+    Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, ToSchema, AsExpression, FromSqlRow,
+)]
+#[diesel(sql_type = Text)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum LoyaltyStatus {
+    Bronze,
+    Silver,
+    Gold,
+}
+
+impl LoyaltyStatus {
+    // набор уровней фиксирован, правила задают только их пороги, скидки и льготы
+    pub const ALL: [Self; 3] = [Self::Bronze, Self::Silver, Self::Gold];
+}
+
+impl Display for LoyaltyStatus {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Bronze => f.write_str("BRONZE"),
+            Self::Silver => f.write_str("SILVER"),
+            Self::Gold => f.write_str("GOLD"),
         }
     }
+}
+
+impl FromStr for LoyaltyStatus {
+    type Err = String;
 
-    // returns status and discount
-    pub fn loyalty_from_counter(counter: i32) -> (String, i32) {
-        match counter {
-            _ if counter >= 20 => ("GOLD".to_owned(), 10),
-            _ if counter >= 10 => ("SILVER".to_owned(), 7),
-            _ => ("BRONZE".to_owned(), 5),
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "BRONZE" => Ok(Self::Bronze),
+            "SILVER" => Ok(Self::Silver),
+            "GOLD" => Ok(Self::Gold),
+            _ => Err(format!("Unknown loyalty status: {s}")),
+        }
+    }
+}
+
+impl ToSql<Text, Pg> for LoyaltyStatus {
+    fn to_sql<'b>(&'b self, out: &mut Output<'b, '_, Pg>) -> serialize::Result {
+        out.write_all(self.to_string().as_bytes())?;
+        Ok(IsNull::No)
+    }
+}
+
+impl FromSql<Text, Pg> for LoyaltyStatus {
+    fn from_sql(bytes: PgValue<'_>) -> deserialize::Result<Self> {
+        Ok(std::str::from_utf8(bytes.as_bytes())?.parse()?)
+    }
+}
+
+#[derive(Clone, Serialize, Deserialize, Queryable, Selectable, Insertable, ToSchema)]
+#[diesel(table_name = crate::schema::loyalty_tier)]
+#[diesel(check_for_backend(diesel::pg::Pg))]
+#[serde(rename_all = "camelCase")]
+pub struct LoyaltyTier {
+    pub name: LoyaltyStatus,
+    pub threshold: i32,
+    pub discount: i32,
+    #[serde(default)]
+    pub perks: Vec<String>,
+    #[serde(default)]
+    pub points_rate: i32,
+    #[serde(default)]
+    pub cancellation_extension_hours: i32,
+    #[serde(default)]
+    pub late_checkout: bool,
+    #[serde(default)]
+    pub free_night_after: Option<i32>,
+}
+
+// льготы уровня, применяемые к очередному бронированию
+#[derive(Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct BookingPerks {
+    pub status: LoyaltyStatus,
+    pub cancellation_extension_hours: i32,
+    pub late_checkout: bool,
+    pub free_nights: i32,
+}
+
+impl BookingPerks {
+    pub fn new(tier: LoyaltyTier, reservation_count: i32) -> Self {
+        let next_stay = reservation_count + 1;
+        let free_nights = match tier.free_night_after {
+            Some(n) if next_stay % n == 0 => 1,
+            _ => 0,
+        };
+
+        Self {
+            status: tier.name,
+            cancellation_extension_hours: tier.cancellation_extension_hours,
+            late_checkout: tier.late_checkout,
+            free_nights,
         }
     }
 }
@@ -35,18 +159,471 @@ impl Loyalty {
 #[derive(Serialize, ToSchema)]
 #[serde(rename_all = "camelCase")]
 pub struct LoyaltyResponse {
-    pub status: String,
+    pub status: LoyaltyStatus,
     pub discount: i32,
     pub reservation_count: i32,
+    pub points: i32,
+    pub next_requalification: DateTime<chrono::Local>,
+    pub downgrade_at: Option<DateTime<chrono::Local>>,
+    pub bonus_discount: i32,
+    pub perks: Vec<String>,
+    pub progress: TierProgress,
 }
 
-impl From<Loyalty> for LoyaltyResponse {
-    fn from(value: Loyalty) -> Self {
+impl LoyaltyResponse {
+    pub fn new(value: Loyalty, perks: Vec<String>, progress: TierProgress) -> Self {
         Self {
             status: value.status,
             discount: value.discount,
             reservation_count: value.reservation_count,
+            points: value.points,
+            next_requalification: value.requalify_at,
+            downgrade_at: value.downgrade_at,
+            bonus_discount: value.bonus_discount,
+            perks,
+            progress,
+        }
+    }
+}
+
+#[derive(Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct TierProgress {
+    pub qualifying_reservations: i32,
+    pub next_tier: Option<LoyaltyStatus>,
+    pub reservations_to_next_tier: Option<i32>,
+}
+
+#[derive(Deserialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct PointsAccrualRequest {
+    pub amount: i32,
+    pub currency: String,
+    pub reservation_uid: Uuid,
+}
+
+impl PointsAccrualRequest {
+    // баллы за сумму по ставке уровня; None, если их число не помещается в баланс
+    pub fn points(&self, points_rate: i32) -> Option<i32> {
+        let points = i64::from(self.amount) * i64::from(points_rate) / 100 / i64::from(POINT_VALUE);
+        i32::try_from(points).ok()
+    }
+}
+
+#[derive(Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct PointsAccrualResponse {
+    pub accrued: i32,
+    pub balance: i32,
+}
+
+#[derive(Deserialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct PointsRedeemRequest {
+    pub points: i32,
+    pub max_discount: Option<i32>,
+    pub currency: String,
+    pub reservation_uid: Option<Uuid>,
+}
+
+#[derive(Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct PointsHoldResponse {
+    pub hold_uid: Uuid,
+    pub points: i32,
+    pub discount: i32,
+    pub status: PointsHoldStatus,
+}
+
+#[derive(Queryable, Selectable, Insertable)]
+#[diesel(table_name = crate::schema::points_hold)]
+#[diesel(check_for_backend(diesel::pg::Pg))]
+pub struct PointsHold {
+    pub hold_uid: Uuid,
+    pub username: String,
+    pub points: i32,
+    pub reservation_uid: Option<Uuid>,
+    pub status: String,
+}
+
+#[derive(Queryable, Selectable, Insertable)]
+#[diesel(table_name = crate::schema::points_accrual)]
+#[diesel(check_for_backend(diesel::pg::Pg))]
+pub struct PointsAccrual {
+    pub reservation_uid: Uuid,
+    pub username: String,
+    pub kind: String,
+    pub points: i32,
+}
+
+pub enum PointsAccrualKind {
+    Accrued,
+    Reversed,
+}
+
+impl Display for PointsAccrualKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Accrued => f.write_str("ACCRUED"),
+            Self::Reversed => f.write_str("REVERSED"),
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, ToSchema, PartialEq)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum PointsHoldStatus {
+    Held,
+    Captured,
+    Released,
+}
+
+impl Display for PointsHoldStatus {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Held => f.write_str("HELD"),
+            Self::Captured => f.write_str("CAPTURED"),
+            Self::Released => f.write_str("RELEASED"),
         }
     }
 }
 
+impl FromStr for PointsHoldStatus {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "HELD" => Ok(Self::Held),
+            "CAPTURED" => Ok(Self::Captured),
+            "RELEASED" => Ok(Self::Released),
+            _ => Err(()),
+        }
+    }
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LoyaltyChangeQuery {
+    pub reservation_uid: Uuid,
+    pub stay_end_date: Option<NaiveDate>,
+    pub referral_code: Option<String>,
+    #[serde(default)]
+    pub consume_bonus: bool,
+}
+
+#[derive(Queryable, Selectable, Insertable)]
+#[diesel(table_name = crate::schema::loyalty_reservation)]
+#[diesel(check_for_backend(diesel::pg::Pg))]
+pub struct LoyaltyReservation {
+    pub reservation_uid: Uuid,
+    pub username: String,
+    #[diesel(skip_insertion)]
+    pub counted_at: DateTime<chrono::Local>,
+    pub canceled_at: Option<DateTime<chrono::Local>>,
+    pub stay_end_date: Option<NaiveDate>,
+}
+
+impl LoyaltyReservation {
+    pub fn new(reservation_uid: Uuid, username: &str, stay_end_date: Option<NaiveDate>) -> Self {
+        Self {
+            reservation_uid,
+            username: username.to_owned(),
+            counted_at: chrono::Local::now(),
+            canceled_at: None,
+            stay_end_date,
+        }
+    }
+}
+
+#[derive(Serialize, Queryable, Selectable, Insertable, ToSchema)]
+#[diesel(table_name = crate::schema::loyalty_events)]
+#[diesel(check_for_backend(diesel::pg::Pg))]
+#[serde(rename_all = "camelCase")]
+pub struct LoyaltyEvent {
+    #[serde(skip)]
+    pub username: String,
+    pub kind: String,
+    pub reservation_uid: Option<Uuid>,
+    pub reservation_count: Option<i32>,
+    pub points: i32,
+    pub status_from: Option<LoyaltyStatus>,
+    pub status_to: Option<LoyaltyStatus>,
+    pub discount: Option<i32>,
+    pub actor: Option<String>,
+    pub reason: Option<String>,
+    #[diesel(skip_insertion)]
+    pub created_at: DateTime<chrono::Local>,
+}
+
+impl LoyaltyEvent {
+    pub fn new(username: &str, kind: LoyaltyEventKind, reservation_uid: Option<Uuid>) -> Self {
+        Self {
+            username: username.to_owned(),
+            kind: kind.to_string(),
+            reservation_uid,
+            reservation_count: None,
+            points: 0,
+            status_from: None,
+            status_to: None,
+            discount: None,
+            actor: None,
+            reason: None,
+            created_at: chrono::Local::now(),
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum LoyaltyEventKind {
+    ReservationAdded,
+    ReservationRemoved,
+    TierChanged,
+    TierDowngradeScheduled,
+    PointsAccrued,
+    PointsHeld,
+    PointsCaptured,
+    PointsReleased,
+    ReferralBonus,
+    BonusDiscountUsed,
+    ReservationCountAdjusted,
+    PointsGranted,
+    TierPinned,
+    TierUnpinned,
+    PointsReversed,
+    BonusDiscountRestored,
+}
+
+impl Display for LoyaltyEventKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::ReservationAdded => f.write_str("RESERVATION_ADDED"),
+            Self::ReservationRemoved => f.write_str("RESERVATION_REMOVED"),
+            Self::TierChanged => f.write_str("TIER_CHANGED"),
+            Self::TierDowngradeScheduled => f.write_str("TIER_DOWNGRADE_SCHEDULED"),
+            Self::PointsAccrued => f.write_str("POINTS_ACCRUED"),
+            Self::PointsHeld => f.write_str("POINTS_HELD"),
+            Self::PointsCaptured => f.write_str("POINTS_CAPTURED"),
+            Self::PointsReleased => f.write_str("POINTS_RELEASED"),
+            Self::ReferralBonus => f.write_str("REFERRAL_BONUS"),
+            Self::BonusDiscountUsed => f.write_str("BONUS_DISCOUNT_USED"),
+            Self::ReservationCountAdjusted => f.write_str("RESERVATION_COUNT_ADJUSTED"),
+            Self::PointsGranted => f.write_str("POINTS_GRANTED"),
+            Self::TierPinned => f.write_str("TIER_PINNED"),
+            Self::TierUnpinned => f.write_str("TIER_UNPINNED"),
+            Self::PointsReversed => f.write_str("POINTS_REVERSED"),
+            Self::BonusDiscountRestored => f.write_str("BONUS_DISCOUNT_RESTORED"),
+        }
+    }
+}
+
+#[derive(Deserialize)]
+pub struct LoyaltyHistoryQuery {
+    pub page: Option<usize>,
+    pub size: Option<usize>,
+}
+
+#[derive(Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct LoyaltyHistory {
+    pub page: usize,
+    pub page_size: usize,
+    pub total_elements: usize,
+    pub items: Vec<LoyaltyEvent>,
+}
+
+#[derive(Serialize, Queryable, Selectable, Insertable, ToSchema)]
+#[diesel(table_name = crate::schema::referral_code)]
+#[diesel(check_for_backend(diesel::pg::Pg))]
+#[serde(rename_all = "camelCase")]
+pub struct ReferralCode {
+    pub code: String,
+    #[serde(skip)]
+    pub username: String,
+    #[diesel(skip_insertion)]
+    pub created_at: DateTime<chrono::Local>,
+}
+
+impl ReferralCode {
+    pub fn new(username: &str) -> Self {
+        Self {
+            code: Uuid::new_v4().simple().to_string()[..8].to_uppercase(),
+            username: username.to_owned(),
+            created_at: chrono::Local::now(),
+        }
+    }
+}
+
+#[derive(Serialize, Queryable, Selectable, Insertable, ToSchema)]
+#[diesel(table_name = crate::schema::referral)]
+#[diesel(check_for_backend(diesel::pg::Pg))]
+#[serde(rename_all = "camelCase")]
+pub struct Referral {
+    pub code: String,
+    #[serde(skip)]
+    pub referrer: String,
+    pub referee: String,
+    pub reservation_uid: Uuid,
+    pub status: String,
+    #[diesel(skip_insertion)]
+    pub created_at: DateTime<chrono::Local>,
+    pub completed_at: Option<DateTime<chrono::Local>>,
+}
+
+#[derive(Serialize, Deserialize, ToSchema, PartialEq)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum ReferralStatus {
+    Pending,
+    Completed,
+    Canceled,
+}
+
+impl Display for ReferralStatus {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Pending => f.write_str("PENDING"),
+            Self::Completed => f.write_str("COMPLETED"),
+            Self::Canceled => f.write_str("CANCELED"),
+        }
+    }
+}
+
+#[derive(Deserialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct ReservationCountAdjustment {
+    pub set: Option<i32>,
+    pub delta: Option<i32>,
+    pub reason: String,
+}
+
+#[derive(Deserialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct PointsGrant {
+    pub points: i32,
+    pub reason: String,
+}
+
+#[derive(Deserialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct TierPin {
+    pub status: LoyaltyStatus,
+    pub expires_at: DateTime<chrono::Local>,
+    pub reason: String,
+}
+
+#[derive(Deserialize)]
+pub struct AdminReasonQuery {
+    pub reason: String,
+}
+
+#[derive(Deserialize)]
+pub struct LoyaltyMemberListQuery {
+    pub status: Option<LoyaltyStatus>,
+    pub page: Option<usize>,
+    pub size: Option<usize>,
+}
+
+#[derive(Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct LoyaltyMemberList {
+    pub page: usize,
+    pub page_size: usize,
+    pub total_elements: usize,
+    pub items: Vec<Loyalty>,
+}
+
+#[derive(Clone, Copy, Default, Deserialize, ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum StatsFormat {
+    #[default]
+    Json,
+    Csv,
+}
+
+#[derive(Deserialize)]
+pub struct StatsQuery {
+    pub format: Option<StatsFormat>,
+}
+
+#[derive(Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct TierStats {
+    pub status: LoyaltyStatus,
+    pub members: i64,
+    pub average_reservations: f64,
+    pub points: i64,
+}
+
+#[derive(Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct LoyaltyStats {
+    pub total_members: i64,
+    pub average_reservations: f64,
+    pub tiers: Vec<TierStats>,
+}
+
+#[derive(Clone, Copy, Default, Deserialize, ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum TopMembersOrder {
+    #[default]
+    Reservations,
+    Points,
+}
+
+#[derive(Deserialize)]
+pub struct TopMembersQuery {
+    pub by: Option<TopMembersOrder>,
+    pub limit: Option<i64>,
+    pub format: Option<StatsFormat>,
+}
+
+#[derive(Serialize, Queryable, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct TopMember {
+    pub username: String,
+    pub status: LoyaltyStatus,
+    pub reservation_count: i32,
+    pub points: i32,
+}
+
+#[derive(Clone, Copy, Default, Deserialize, ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum StatsInterval {
+    Day,
+    Week,
+    #[default]
+    Month,
+}
+
+impl Display for StatsInterval {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let s = match self {
+            StatsInterval::Day => "day",
+            StatsInterval::Week => "week",
+            StatsInterval::Month => "month",
+        };
+
+        f.write_str(s)
+    }
+}
+
+#[derive(Deserialize)]
+pub struct TierMigrationQuery {
+    pub from: Option<NaiveDate>,
+    pub to: Option<NaiveDate>,
+    pub interval: Option<StatsInterval>,
+    pub format: Option<StatsFormat>,
+}
+
+#[derive(Serialize, QueryableByName, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct TierMigration {
+    #[diesel(sql_type = diesel::sql_types::Date)]
+    pub period: NaiveDate,
+    #[diesel(sql_type = Text)]
+    pub status_from: LoyaltyStatus,
+    #[diesel(sql_type = Text)]
+    pub status_to: LoyaltyStatus,
+    #[diesel(sql_type = diesel::sql_types::BigInt)]
+    pub count: i64,
+}
@@ -0,0 +1,11 @@
+use diesel::prelude::*;
+
+use crate::{dto::LoyaltyEvent, schema::loyalty_events};
+
+pub fn record_event(conn: &mut PgConnection, event: &LoyaltyEvent) -> QueryResult<()> {
+    diesel::insert_into(loyalty_events::table)
+        .values(event)
+        .execute(conn)?;
+
+    Ok(())
+}
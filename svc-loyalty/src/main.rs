@@ -3,33 +3,111 @@ use std::env;
 use diesel::prelude::*;
 use diesel_migrations::{embed_migrations, EmbeddedMigrations, MigrationHarness};
 use dto::*;
+use referrals::ReferralBonus;
 use routes::*;
+use signature::SignatureVerifier;
+use tiers::QualificationPolicy;
 use tokio::net::TcpListener;
 use utoipa::OpenApi;
 use utoipa_axum::{router::OpenApiRouter, routes};
 use utoipa_swagger_ui::SwaggerUi;
 
+mod diesel_paginate;
 mod dto;
+mod events;
+mod health;
 mod logger;
+mod monitoring;
+mod referrals;
+mod request_id;
 mod routes;
 mod schema;
+mod stats;
+mod telemetry;
+mod tiers;
 
 #[cfg(test)]
 mod tests;
 
 #[derive(utoipa::OpenApi)]
 #[openapi(
-    paths(check_health, put_loyalty, delete_loyalty, get_loyalty),
-    components(schemas(LoyaltyResponse))
+    paths(
+        check_health,
+        check_liveness,
+        check_readiness,
+        get_metrics,
+        put_loyalty,
+        delete_loyalty,
+        get_loyalty,
+        get_loyalty_history,
+        get_booking_perks,
+        get_tiers,
+        put_tiers,
+        post_points_accrue,
+        post_points_redeem,
+        capture_points_hold,
+        release_points_hold,
+        post_referral_code,
+        get_referral_code,
+        get_referrals,
+        get_admin_members,
+        get_admin_member,
+        put_admin_reservation_count,
+        post_admin_points,
+        put_admin_tier_pin,
+        delete_admin_tier_pin,
+        get_loyalty_stats,
+        get_top_members,
+        get_tier_migrations,
+        get_log_levels,
+        put_log_levels
+    ),
+    components(schemas(
+        LoyaltyResponse,
+        LoyaltyStatus,
+        TierProgress,
+        LoyaltyTier,
+        BookingPerks,
+        LoyaltyEvent,
+        LoyaltyEventKind,
+        LoyaltyHistory,
+        PointsAccrualRequest,
+        PointsAccrualResponse,
+        PointsRedeemRequest,
+        PointsHoldResponse,
+        PointsHoldStatus,
+        ReferralCode,
+        Referral,
+        ReferralStatus,
+        Loyalty,
+        LoyaltyMemberList,
+        ReservationCountAdjustment,
+        PointsGrant,
+        TierPin,
+        LoyaltyStats,
+        TierStats,
+        TopMember,
+        TopMembersOrder,
+        TierMigration,
+        StatsInterval,
+        StatsFormat,
+        logger::LogLevels,
+        health::HealthStatus,
+        health::DependencyHealth,
+        health::HealthReport
+    ))
 )]
 struct ApiDoc;
 
 pub const MIGRATIONS: EmbeddedMigrations = embed_migrations!();
 pub const SERVICE_ENDPOINT: &str = "0.0.0.0:8050";
+// наибольший размер страницы истории лояльности и списка участников
+pub const MAX_PAGE_SIZE: usize = 100;
 
 #[derive(Debug, Clone)]
 struct AppState {
     database_url: String,
+    qualification: QualificationPolicy,
 }
 
 #[tokio::main(flavor = "multi_thread", worker_threads = 4)]
@@ -37,12 +115,14 @@ async fn main() {
     let database_url =
         env::var("DATABASE_URL").expect("DATABASE_URL environment variable was not specified");
 
-    let _logger_handler = logger::init();
-    log::debug!("Logger initialized. Hello, world!");
+    logger::init("loyalty");
+    let _tracer_provider = telemetry::init("loyalty");
+    monitoring::init();
+    tracing::debug!("Logger initialized. Hello, world!");
 
     let app = app(database_url).await;
 
-    log::info!("Listening on {}", SERVICE_ENDPOINT);
+    tracing::info!("Listening on {}", SERVICE_ENDPOINT);
     let listener = TcpListener::bind(SERVICE_ENDPOINT).await.unwrap();
     axum::serve(listener, app.into_make_service())
         .await
@@ -53,13 +133,58 @@ async fn app(database_url: String) -> axum::Router {
     init_db(database_url.as_str());
 
     let swagger = SwaggerUi::new("/swagger-ui").url("/openapi.json", ApiDoc::openapi());
-    let state = AppState { database_url };
+    let qualification = QualificationPolicy::from_env();
+    tokio::spawn(tiers::run_requalification(
+        database_url.clone(),
+        qualification,
+    ));
+    tokio::spawn(referrals::run_referral_completion(
+        database_url.clone(),
+        ReferralBonus::from_env(),
+    ));
+
+    let state = AppState {
+        database_url,
+        qualification,
+    };
     let app = OpenApiRouter::with_openapi(ApiDoc::openapi())
-        .routes(routes!(check_health))
         .routes(routes!(get_loyalty, delete_loyalty, put_loyalty))
+        .routes(routes!(get_loyalty_history))
+        .routes(routes!(get_booking_perks))
+        .routes(routes!(get_tiers))
+        .routes(routes!(put_tiers))
+        .routes(routes!(post_points_accrue))
+        .routes(routes!(post_points_redeem))
+        .routes(routes!(capture_points_hold))
+        .routes(routes!(release_points_hold))
+        .routes(routes!(post_referral_code))
+        .routes(routes!(get_referral_code))
+        .routes(routes!(get_referrals))
+        .routes(routes!(get_admin_members))
+        .routes(routes!(get_admin_member))
+        .routes(routes!(put_admin_reservation_count))
+        .routes(routes!(post_admin_points))
+        .routes(routes!(put_admin_tier_pin, delete_admin_tier_pin))
+        .routes(routes!(get_loyalty_stats))
+        .routes(routes!(get_top_members))
+        .routes(routes!(get_tier_migrations))
+        .routes(routes!(get_log_levels, put_log_levels))
+        .route_layer(axum::middleware::from_fn_with_state(
+            SignatureVerifier::from_env(),
+            signature::verify_signature,
+        ))
+        // проверки работоспособности не подписываются
+        .routes(routes!(check_health))
+        .routes(routes!(check_liveness))
+        .routes(routes!(check_readiness))
+        .routes(routes!(get_metrics))
         .with_state(state);
 
-    axum::Router::from(app).merge(swagger)
+    axum::Router::from(app)
+        .merge(swagger)
+        .layer(axum::middleware::from_fn(monitoring::track_request))
+        .layer(axum::middleware::from_fn(telemetry::trace_request))
+        .layer(axum::middleware::from_fn(request_id::propagate_request_id))
 }
 
 fn init_db(database_url: &str) {
@@ -0,0 +1,196 @@
+use std::{env, time::Duration};
+
+use chrono::DateTime;
+use diesel::prelude::*;
+use uuid::Uuid;
+
+use crate::{
+    dto::{LoyaltyEvent, LoyaltyEventKind, Referral, ReferralStatus},
+    events::record_event,
+    schema::{loyalty, loyalty_reservation, referral},
+};
+
+pub const DEFAULT_REFERRAL_BONUS: i32 = 500;
+pub const DEFAULT_REFERRAL_INTERVAL_SECS: u64 = 3600;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ReferralBonusKind {
+    Points,
+    // разовая скидка в процентах на следующее бронирование
+    Discount,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct ReferralBonus {
+    pub kind: ReferralBonusKind,
+    pub referrer: i32,
+    pub referee: i32,
+}
+
+impl ReferralBonus {
+    pub fn from_env() -> Self {
+        let kind = match env::var("REFERRAL_BONUS_KIND").as_deref() {
+            Ok("DISCOUNT") => ReferralBonusKind::Discount,
+            _ => ReferralBonusKind::Points,
+        };
+        let amount = |name: &str| {
+            env::var(name)
+                .ok()
+                .and_then(|v| v.parse::<i32>().ok())
+                .unwrap_or(DEFAULT_REFERRAL_BONUS)
+        };
+
+        Self {
+            kind,
+            referrer: amount("REFERRAL_REFERRER_BONUS"),
+            referee: amount("REFERRAL_REFEREE_BONUS"),
+        }
+    }
+
+    fn award(
+        &self,
+        conn: &mut PgConnection,
+        username: &str,
+        amount: i32,
+        reservation_uid: Uuid,
+    ) -> QueryResult<()> {
+        let event = LoyaltyEvent::new(
+            username,
+            LoyaltyEventKind::ReferralBonus,
+            Some(reservation_uid),
+        );
+
+        match self.kind {
+            ReferralBonusKind::Points => {
+                diesel::update(loyalty::table)
+                    .filter(loyalty::username.eq(username))
+                    .set(loyalty::points.eq(loyalty::points + amount))
+                    .execute(conn)?;
+                record_event(
+                    conn,
+                    &LoyaltyEvent {
+                        points: amount,
+                        ..event
+                    },
+                )
+            }
+            ReferralBonusKind::Discount => {
+                // неиспользованные скидки суммируются, но не дают больше 100%
+                let current = loyalty::table
+                    .filter(loyalty::username.eq(username))
+                    .select(loyalty::bonus_discount)
+                    .for_update()
+                    .get_result::<i32>(conn)?;
+                let total = (current + amount.max(0)).min(100);
+                let discount = total - current;
+                diesel::update(loyalty::table)
+                    .filter(loyalty::username.eq(username))
+                    .set(loyalty::bonus_discount.eq(total))
+                    .execute(conn)?;
+                record_event(
+                    conn,
+                    &LoyaltyEvent {
+                        discount: Some(discount),
+                        ..event
+                    },
+                )
+            }
+        }
+    }
+}
+
+// реферальный код доступен, пока у пользователя нет учтённых бронирований
+pub fn has_counted_bookings(conn: &mut PgConnection, username: &str) -> QueryResult<bool> {
+    Ok(loyalty::table
+        .filter(loyalty::username.eq(username))
+        .select(loyalty::reservation_count)
+        .get_result::<i32>(conn)
+        .optional()?
+        .is_some_and(|count| count > 0))
+}
+
+// бонус начисляется обоим участникам после завершения первого проживания приглашённого
+pub fn complete_referrals(conn: &mut PgConnection, bonus: &ReferralBonus) -> QueryResult<usize> {
+    let today = chrono::Local::now().date_naive();
+    let due = referral::table
+        .inner_join(
+            loyalty_reservation::table
+                .on(loyalty_reservation::reservation_uid.eq(referral::reservation_uid)),
+        )
+        .filter(referral::status.eq(ReferralStatus::Pending.to_string()))
+        .filter(
+            loyalty_reservation::stay_end_date
+                .le(today)
+                .or(loyalty_reservation::canceled_at.is_not_null()),
+        )
+        .select((Referral::as_select(), loyalty_reservation::canceled_at))
+        .load::<(Referral, Option<DateTime<chrono::Local>>)>(conn)?;
+
+    let mut completed = 0;
+    for (referral, canceled_at) in due {
+        conn.transaction(|conn| {
+            let status = if canceled_at.is_some() {
+                ReferralStatus::Canceled
+            } else {
+                bonus.award(
+                    conn,
+                    &referral.referrer,
+                    bonus.referrer,
+                    referral.reservation_uid,
+                )?;
+                bonus.award(
+                    conn,
+                    &referral.referee,
+                    bonus.referee,
+                    referral.reservation_uid,
+                )?;
+                completed += 1;
+                ReferralStatus::Completed
+            };
+
+            diesel::update(referral::table)
+                .filter(referral::referee.eq(&referral.referee))
+                .set((
+                    referral::status.eq(status.to_string()),
+                    referral::completed_at.eq(chrono::Local::now()),
+                ))
+                .execute(conn)
+        })?;
+    }
+
+    Ok(completed)
+}
+
+pub async fn run_referral_completion(database_url: String, bonus: ReferralBonus) {
+    let period = env::var("REFERRAL_INTERVAL_SECS")
+        .ok()
+        .and_then(|v| v.parse::<u64>().ok())
+        .filter(|&v| v > 0)
+        .unwrap_or(DEFAULT_REFERRAL_INTERVAL_SECS);
+    let mut interval = tokio::time::interval(Duration::from_secs(period));
+
+    loop {
+        interval.tick().await;
+
+        let database_url = database_url.clone();
+        let res = tokio::task::spawn_blocking(move || {
+            let conn = &mut match PgConnection::establish(database_url.as_str()) {
+                Ok(conn) => conn,
+                Err(e) => {
+                    tracing::error!("Failed to establish connection to database: {e}");
+                    return;
+                }
+            };
+            match complete_referrals(conn, &bonus) {
+                Ok(completed) => {
+                    tracing::info!("Referral check finished, {completed} referrals completed")
+                }
+                Err(e) => tracing::error!("Referral check failed: {e}"),
+            }
+        })
+        .await;
+        if let Err(e) = res {
+            tracing::error!("Referral check task failed: {e}");
+        }
+    }
+}
@@ -1,12 +1,33 @@
+use std::collections::BTreeMap;
+
 use axum::{
-    extract::State,
-    http::{HeaderMap, StatusCode},
+    extract::{Path, Query, State},
+    http::{header, HeaderMap, StatusCode},
     response::IntoResponse,
     Json,
 };
-use diesel::{prelude::*, result::Error as DieselError};
+use diesel::{
+    prelude::*,
+    result::{DatabaseErrorKind, Error as DieselError},
+};
+use uuid::Uuid;
 
-use crate::{dto::*, schema::loyalty, AppState};
+use crate::{
+    diesel_paginate::Paginate,
+    dto::*,
+    events::record_event,
+    health::{self, HealthReport},
+    logger::{self, LogLevels},
+    monitoring,
+    referrals::has_counted_bookings,
+    schema::{
+        loyalty, loyalty_events, loyalty_reservation, loyalty_tier, points_accrual, points_hold,
+        referral, referral_code,
+    },
+    stats::*,
+    tiers::*,
+    AppState, MAX_PAGE_SIZE,
+};
 
 #[utoipa::path(
     get,
@@ -19,6 +40,79 @@ pub async fn check_health() -> impl IntoResponse {
     StatusCode::OK
 }
 
+#[utoipa::path(
+    get,
+    path = "/manage/health/live",
+    responses(
+        (status = OK, body = HealthReport, description = "Процесс работает")
+    )
+)]
+pub async fn check_liveness() -> impl IntoResponse {
+    HealthReport::new(BTreeMap::new())
+}
+
+#[utoipa::path(
+    get,
+    path = "/manage/health/ready",
+    responses(
+        (status = OK, body = HealthReport, description = "Сервис готов принимать запросы"),
+        (
+            status = SERVICE_UNAVAILABLE,
+            body = HealthReport,
+            description = "Не пройдена одна из проверок: доступность базы данных и применённые миграции",
+        ),
+    )
+)]
+pub async fn check_readiness(State(state): State<AppState>) -> impl IntoResponse {
+    health::readiness(state.database_url).await
+}
+
+#[utoipa::path(
+    get,
+    path = "/manage/metrics",
+    responses(
+        (status = OK, body = String, content_type = "text/plain", description = "Метрики в формате Prometheus")
+    )
+)]
+pub async fn get_metrics() -> impl IntoResponse {
+    (
+        [(header::CONTENT_TYPE, "text/plain; version=0.0.4")],
+        monitoring::render(),
+    )
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/v1/admin/log-levels",
+    responses(
+        (status = OK, body = LogLevels, description = "Текущие уровни журнала")
+    )
+)]
+pub async fn get_log_levels() -> impl IntoResponse {
+    Json(logger::levels())
+}
+
+#[utoipa::path(
+    put,
+    path = "/api/v1/admin/log-levels",
+    request_body = LogLevels,
+    responses(
+        (status = OK, body = LogLevels, description = "Уровни журнала изменены"),
+        (status = BAD_REQUEST, description = "Неизвестный уровень журнала"),
+    )
+)]
+pub async fn put_log_levels(
+    Json(levels): Json<LogLevels>,
+) -> Result<impl IntoResponse, StatusCode> {
+    logger::set_levels(levels.clone()).map_err(|e| {
+        tracing::warn!("Rejected log levels: {e}");
+        StatusCode::BAD_REQUEST
+    })?;
+    tracing::info!("Log levels changed to {levels:?}");
+
+    Ok(Json(levels))
+}
+
 #[utoipa::path(
     get,
     path = "/api/v1/loyalty",
@@ -47,6 +141,16 @@ pub async fn get_loyalty(
     let conn = &mut PgConnection::establish(state.database_url.as_str())
         .expect("Failed to establish connection to database");
 
+    let res = load_loyalty(conn, username, &state.qualification)?;
+
+    Ok(Json(res))
+}
+
+fn load_loyalty(
+    conn: &mut PgConnection,
+    username: &str,
+    policy: &QualificationPolicy,
+) -> Result<LoyaltyResponse, StatusCode> {
     let res = loyalty::table
         .filter(loyalty::username.eq(username))
         .select(Loyalty::as_select())
@@ -56,9 +160,43 @@ pub async fn get_loyalty(
             _ => StatusCode::INTERNAL_SERVER_ERROR,
         })?;
 
-    let res = LoyaltyResponse::from(res);
+    let perks = loyalty_tier::table
+        .filter(loyalty_tier::name.eq(res.status))
+        .select(loyalty_tier::perks)
+        .get_result::<Vec<String>>(conn)
+        .optional()
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?
+        .unwrap_or_default();
+    let progress =
+        tier_progress(conn, username, policy).map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
 
-    Ok(Json(res))
+    Ok(LoyaltyResponse::new(res, perks, progress))
+}
+
+enum LoyaltyError {
+    Db(DieselError),
+    Rejected(StatusCode),
+}
+
+impl From<DieselError> for LoyaltyError {
+    fn from(value: DieselError) -> Self {
+        Self::Db(value)
+    }
+}
+
+fn loyalty_result<T>(res: Result<T, LoyaltyError>) -> Result<T, StatusCode> {
+    match res {
+        Ok(res) => Ok(res),
+        Err(LoyaltyError::Rejected(status)) => Err(status),
+        Err(LoyaltyError::Db(DieselError::NotFound)) => Err(StatusCode::NOT_FOUND),
+        Err(LoyaltyError::Db(DieselError::DatabaseError(DatabaseErrorKind::CheckViolation, _))) => {
+            Err(StatusCode::UNPROCESSABLE_ENTITY)
+        }
+        Err(LoyaltyError::Db(e)) => {
+            tracing::error!("Failed to update loyalty: {e}");
+            Err(StatusCode::INTERNAL_SERVER_ERROR)
+        }
+    }
 }
 
 #[utoipa::path(
@@ -67,17 +205,20 @@ pub async fn get_loyalty(
     responses(
         (
             status = NO_CONTENT,
-            description = "Бронирование вычтено из программы лояльности",
+            description = "Бронирование вычтено из программы лояльности, баллы по нему возвращены и списаны, разовая скидка возвращена",
             content_type = "application/json",
         ),
+        (status = NOT_FOUND, description = "Бронирование не было учтено"),
     ),
     params(
-        ("X-User-Name", Header, description = "Имя пользователя")
+        ("X-User-Name", Header, description = "Имя пользователя"),
+        ("reservationUid", Query, description = "UUID бронирования"),
     ),
 )]
 pub async fn delete_loyalty(
     State(state): State<AppState>,
     headers: HeaderMap,
+    Query(query): Query<LoyaltyChangeQuery>,
 ) -> Result<impl IntoResponse, StatusCode> {
     let username = headers
         .get("X-User-Name")
@@ -88,25 +229,55 @@ pub async fn delete_loyalty(
     let conn = &mut PgConnection::establish(state.database_url.as_str())
         .expect("Failed to establish connection to database");
 
-    let counter = diesel::update(loyalty::table)
-        .filter(loyalty::username.eq(username))
-        .set(loyalty::reservation_count.eq(loyalty::reservation_count - 1))
-        .returning(loyalty::reservation_count)
-        .get_result(conn)
-        .map_err(|e| match e {
-            DieselError::NotFound => StatusCode::NOT_FOUND,
-            _ => StatusCode::INTERNAL_SERVER_ERROR,
-        })?;
+    let res = conn.transaction::<_, LoyaltyError, _>(|conn| {
+        let reservation = loyalty_reservation::table
+            .filter(loyalty_reservation::reservation_uid.eq(query.reservation_uid))
+            .filter(loyalty_reservation::username.eq(username))
+            .select(LoyaltyReservation::as_select())
+            .for_update()
+            .get_result::<LoyaltyReservation>(conn)
+            .optional()?
+            .ok_or(LoyaltyError::Rejected(StatusCode::NOT_FOUND))?;
 
-    if counter == 9 || counter == 19 {
-        let (status, discount) = Loyalty::loyalty_from_counter(counter);
+        // повторная отмена ничего не меняет
+        if reservation.canceled_at.is_some() {
+            return Ok(());
+        }
+        reverse_reservation_points(conn, username, query.reservation_uid)?;
+        restore_reservation_bonus(conn, username, query.reservation_uid)?;
 
-        diesel::update(loyalty::table)
+        diesel::update(loyalty_reservation::table)
+            .filter(loyalty_reservation::reservation_uid.eq(query.reservation_uid))
+            .set(loyalty_reservation::canceled_at.eq(chrono::Local::now()))
+            .execute(conn)?;
+
+        let counter = diesel::update(loyalty::table)
             .filter(loyalty::username.eq(username))
-            .set((loyalty::status.eq(status), loyalty::discount.eq(discount)))
-            .execute(conn)
-            .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
-    }
+            .set(loyalty::reservation_count.eq(loyalty::reservation_count - 1))
+            .returning(loyalty::reservation_count)
+            .get_result(conn)?;
+
+        record_event(
+            conn,
+            &LoyaltyEvent {
+                reservation_count: Some(counter),
+                ..LoyaltyEvent::new(
+                    username,
+                    LoyaltyEventKind::ReservationRemoved,
+                    Some(query.reservation_uid),
+                )
+            },
+        )?;
+        update_member_tier(
+            conn,
+            username,
+            &state.qualification,
+            Some(query.reservation_uid),
+        )?;
+
+        Ok(())
+    });
+    loyalty_result(res)?;
 
     Ok(StatusCode::NO_CONTENT)
 }
@@ -115,15 +286,215 @@ pub async fn delete_loyalty(
     put,
     path = "/api/v1/loyalty",
     responses(
-        (status = NO_CONTENT, description = "Success")
+        (status = NO_CONTENT, description = "Success"),
+        (status = CONFLICT, description = "Бронирование уже отменено или учтено для другого пользователя"),
     ),
     params(
-        ("X-User-Name", Header, description = "Имя пользователя")
+        ("X-User-Name", Header, description = "Имя пользователя"),
+        ("reservationUid", Query, description = "UUID бронирования"),
     ),
 )]
 pub async fn put_loyalty(
     State(state): State<AppState>,
     headers: HeaderMap,
+    Query(query): Query<LoyaltyChangeQuery>,
+) -> Result<impl IntoResponse, StatusCode> {
+    let username = headers
+        .get("X-User-Name")
+        .ok_or(StatusCode::BAD_REQUEST)?
+        .to_str()
+        .map_err(|_| StatusCode::BAD_REQUEST)?;
+
+    let conn = &mut PgConnection::establish(state.database_url.as_str())
+        .expect("Failed to establish connection to database");
+
+    let base_tier = tier_for_counter(conn, 0).map_err(|e| {
+        tracing::error!("Failed to find base loyalty tier: {e}");
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
+
+    let res = conn.transaction::<_, LoyaltyError, _>(|conn| {
+        let inserted = diesel::insert_into(loyalty_reservation::table)
+            .values(&LoyaltyReservation::new(
+                query.reservation_uid,
+                username,
+                query.stay_end_date,
+            ))
+            .on_conflict(loyalty_reservation::reservation_uid)
+            .do_nothing()
+            .execute(conn)?;
+        if inserted == 0 {
+            let counted = loyalty_reservation::table
+                .filter(loyalty_reservation::reservation_uid.eq(query.reservation_uid))
+                .select(LoyaltyReservation::as_select())
+                .get_result::<LoyaltyReservation>(conn)?;
+            // повторный запрос по уже учтённому бронированию ничего не меняет
+            if counted.username == username && counted.canceled_at.is_none() {
+                return Ok(());
+            }
+            return Err(LoyaltyError::Rejected(StatusCode::CONFLICT));
+        }
+
+        // реферальный код принимается только при первом бронировании
+        if let Some(code) = &query.referral_code {
+            let is_member = has_counted_bookings(conn, username)?;
+            let referral_code = referral_code::table
+                .filter(referral_code::code.eq(code.to_uppercase()))
+                .select(ReferralCode::as_select())
+                .get_result::<ReferralCode>(conn)
+                .optional()?
+                .ok_or(LoyaltyError::Rejected(StatusCode::UNPROCESSABLE_ENTITY))?;
+            if is_member || referral_code.username == username {
+                return Err(LoyaltyError::Rejected(StatusCode::UNPROCESSABLE_ENTITY));
+            }
+
+            diesel::insert_into(referral::table)
+                .values(&Referral {
+                    code: referral_code.code,
+                    referrer: referral_code.username,
+                    referee: username.to_owned(),
+                    reservation_uid: query.reservation_uid,
+                    status: ReferralStatus::Pending.to_string(),
+                    created_at: chrono::Local::now(),
+                    completed_at: None,
+                })
+                .execute(conn)?;
+        }
+
+        let counter = diesel::insert_into(loyalty::table)
+            .values(&Loyalty::new(
+                username.to_owned(),
+                &base_tier,
+                chrono::Local::now() + state.qualification.window,
+            ))
+            .on_conflict(loyalty::username)
+            .do_update()
+            .set(loyalty::reservation_count.eq(loyalty::reservation_count + 1))
+            .returning(loyalty::reservation_count)
+            .get_result(conn)?;
+
+        record_event(
+            conn,
+            &LoyaltyEvent {
+                reservation_count: Some(counter),
+                ..LoyaltyEvent::new(
+                    username,
+                    LoyaltyEventKind::ReservationAdded,
+                    Some(query.reservation_uid),
+                )
+            },
+        )?;
+
+        // разовая скидка использована в этом бронировании
+        if query.consume_bonus {
+            let bonus_discount = loyalty::table
+                .filter(loyalty::username.eq(username))
+                .select(loyalty::bonus_discount)
+                .get_result::<i32>(conn)?;
+            if bonus_discount > 0 {
+                diesel::update(loyalty::table)
+                    .filter(loyalty::username.eq(username))
+                    .set(loyalty::bonus_discount.eq(0))
+                    .execute(conn)?;
+                record_event(
+                    conn,
+                    &LoyaltyEvent {
+                        discount: Some(bonus_discount),
+                        ..LoyaltyEvent::new(
+                            username,
+                            LoyaltyEventKind::BonusDiscountUsed,
+                            Some(query.reservation_uid),
+                        )
+                    },
+                )?;
+            }
+        }
+        update_member_tier(
+            conn,
+            username,
+            &state.qualification,
+            Some(query.reservation_uid),
+        )?;
+
+        Ok(())
+    });
+    loyalty_result(res)?;
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/v1/loyalty/history",
+    responses(
+        (
+            status = OK,
+            description = "История изменений программы лояльности",
+            body = LoyaltyHistory,
+            content_type = "application/json",
+        ),
+    ),
+    params(
+        ("X-User-Name", Header, description = "Имя пользователя"),
+        ("page", Query, description = "Номер страницы"),
+        ("size", Query, description = "Количество элементов страницы"),
+    ),
+)]
+pub async fn get_loyalty_history(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Query(query): Query<LoyaltyHistoryQuery>,
+) -> Result<impl IntoResponse, StatusCode> {
+    let username = headers
+        .get("X-User-Name")
+        .ok_or(StatusCode::BAD_REQUEST)?
+        .to_str()
+        .map_err(|_| StatusCode::BAD_REQUEST)?;
+
+    let page = query.page.unwrap_or(1).max(1);
+    let size = query.size.unwrap_or(10).clamp(1, MAX_PAGE_SIZE);
+
+    let conn = &mut PgConnection::establish(state.database_url.as_str())
+        .expect("Failed to establish connection to database");
+
+    let (items, _, total) = loyalty_events::table
+        .filter(loyalty_events::username.eq(username))
+        .order(loyalty_events::id.desc())
+        .select(LoyaltyEvent::as_select())
+        .paginate(page as i64)
+        .per_page(size as i64)
+        .load_and_count_pages::<LoyaltyEvent>(conn)
+        .map_err(|e| {
+            tracing::error!("Failed to load loyalty history: {e}");
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?;
+
+    Ok(Json(LoyaltyHistory {
+        page,
+        page_size: size,
+        total_elements: total as usize,
+        items,
+    }))
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/v1/loyalty/perks",
+    responses(
+        (
+            status = OK,
+            description = "Льготы уровня для следующего бронирования",
+            body = BookingPerks,
+            content_type = "application/json",
+        ),
+    ),
+    params(
+        ("X-User-Name", Header, description = "Имя пользователя")
+    ),
+)]
+pub async fn get_booking_perks(
+    State(state): State<AppState>,
+    headers: HeaderMap,
 ) -> Result<impl IntoResponse, StatusCode> {
     let username = headers
         .get("X-User-Name")
@@ -134,27 +505,1238 @@ pub async fn put_loyalty(
     let conn = &mut PgConnection::establish(state.database_url.as_str())
         .expect("Failed to establish connection to database");
 
-    let counter = diesel::insert_into(loyalty::table)
-        .values(&Loyalty::new(username.to_owned()))
-        .on_conflict(loyalty::username)
-        .do_update()
-        .set(loyalty::reservation_count.eq(loyalty::reservation_count + 1))
-        .returning(loyalty::reservation_count)
-        .get_result(conn)
+    // новый участник получает льготы начального уровня
+    let member = loyalty::table
+        .filter(loyalty::username.eq(username))
+        .select((loyalty::status, loyalty::reservation_count))
+        .get_result::<(LoyaltyStatus, i32)>(conn)
+        .optional()
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+    let (tier, reservation_count) = match member {
+        Some((status, reservation_count)) => (
+            loyalty_tier::table
+                .filter(loyalty_tier::name.eq(status))
+                .select(LoyaltyTier::as_select())
+                .get_result::<LoyaltyTier>(conn),
+            reservation_count,
+        ),
+        None => (tier_for_counter(conn, 0), 0),
+    };
+    let tier = tier.map_err(|e| {
+        tracing::error!("Failed to load loyalty tier for {username}: {e}");
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
+
+    Ok(Json(BookingPerks::new(tier, reservation_count)))
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/v1/loyalty/tiers",
+    responses(
+        (
+            status = OK,
+            description = "Уровни программы лояльности",
+            body = Vec<LoyaltyTier>,
+            content_type = "application/json",
+        ),
+    ),
+)]
+pub async fn get_tiers(State(state): State<AppState>) -> Result<impl IntoResponse, StatusCode> {
+    let conn = &mut PgConnection::establish(state.database_url.as_str())
+        .expect("Failed to establish connection to database");
+
+    let res = loyalty_tier::table
+        .order(loyalty_tier::threshold)
+        .select(LoyaltyTier::as_select())
+        .load::<LoyaltyTier>(conn)
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+    Ok(Json(res))
+}
+
+#[utoipa::path(
+    put,
+    path = "/api/v1/admin/loyalty/tiers",
+    request_body = Vec<LoyaltyTier>,
+    responses(
+        (
+            status = OK,
+            description = "Правила уровней обновлены, уровни участников пересчитаны",
+            body = Vec<LoyaltyTier>,
+            content_type = "application/json",
+        ),
+        (
+            status = UNPROCESSABLE_ENTITY,
+            description = "Некорректный набор уровней: нужны ровно BRONZE, SILVER и GOLD \
+                           с возрастающими порогами, начиная с 0, и неубывающими скидками",
+        ),
+    ),
+)]
+pub async fn put_tiers(
+    State(state): State<AppState>,
+    Json(mut tiers): Json<Vec<LoyaltyTier>>,
+) -> Result<impl IntoResponse, StatusCode> {
+    // добавить, переименовать или удалить уровень нельзя
+    tiers.sort_by_key(|t| t.threshold);
+    if tiers.iter().map(|t| t.name).ne(LoyaltyStatus::ALL)
+        || tiers.first().map(|t| t.threshold) != Some(0)
+        || tiers.iter().any(|t| !(0..=100).contains(&t.discount))
+        || tiers
+            .iter()
+            .any(|t| t.free_night_after.is_some_and(|n| n <= 0))
+        || !tiers
+            .windows(2)
+            .all(|w| w[0].threshold < w[1].threshold && w[0].discount <= w[1].discount)
+    {
+        return Err(StatusCode::UNPROCESSABLE_ENTITY);
+    }
+
+    let conn = &mut PgConnection::establish(state.database_url.as_str())
+        .expect("Failed to establish connection to database");
+
+    let updated = conn
+        .transaction::<_, DieselError, _>(|conn| {
+            diesel::delete(loyalty_tier::table).execute(conn)?;
+            diesel::insert_into(loyalty_tier::table)
+                .values(&tiers)
+                .execute(conn)?;
+            update_all_tiers(conn, &state.qualification)
+        })
         .map_err(|e| match e {
-            DieselError::NotFound => StatusCode::NOT_FOUND,
+            DieselError::DatabaseError(
+                DatabaseErrorKind::UniqueViolation | DatabaseErrorKind::CheckViolation,
+                _,
+            ) => StatusCode::UNPROCESSABLE_ENTITY,
             _ => StatusCode::INTERNAL_SERVER_ERROR,
         })?;
 
-    if counter == 10 || counter == 20 {
-        let (status, discount) = Loyalty::loyalty_from_counter(counter);
+    tracing::info!("Loyalty tiers updated, {updated} members changed tier");
 
-        diesel::update(loyalty::table)
+    Ok(Json(tiers))
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/v1/loyalty/points/accrue",
+    request_body = PointsAccrualRequest,
+    responses(
+        (
+            status = OK,
+            description = "Баллы начислены по ставке текущего уровня",
+            body = PointsAccrualResponse,
+            content_type = "application/json",
+        ),
+        (status = NOT_FOUND, description = "Участник программы лояльности не найден"),
+        (status = CONFLICT, description = "Баллы за бронирование начислены другому пользователю"),
+        (
+            status = UNPROCESSABLE_ENTITY,
+            description = "Сумма указана не в валюте баллов или слишком велика",
+        ),
+    ),
+    params(
+        ("X-User-Name", Header, description = "Имя пользователя")
+    ),
+)]
+pub async fn post_points_accrue(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Json(req): Json<PointsAccrualRequest>,
+) -> Result<impl IntoResponse, StatusCode> {
+    let username = headers
+        .get("X-User-Name")
+        .ok_or(StatusCode::BAD_REQUEST)?
+        .to_str()
+        .map_err(|_| StatusCode::BAD_REQUEST)?;
+
+    if req.amount < 0 {
+        return Err(StatusCode::BAD_REQUEST);
+    }
+    // баллы начисляются по единому курсу, сумму переводит вызывающая сторона
+    if !req.currency.eq_ignore_ascii_case(POINTS_CURRENCY) {
+        return Err(StatusCode::UNPROCESSABLE_ENTITY);
+    }
+
+    let conn = &mut PgConnection::establish(state.database_url.as_str())
+        .expect("Failed to establish connection to database");
+
+    let res = conn.transaction::<_, LoyaltyError, _>(|conn| {
+        let (points_rate, balance) = loyalty::table
+            .inner_join(loyalty_tier::table.on(loyalty_tier::name.eq(loyalty::status)))
+            .filter(loyalty::username.eq(username))
+            .select((loyalty_tier::points_rate, loyalty::points))
+            .get_result::<(i32, i32)>(conn)
+            .optional()?
+            .ok_or(LoyaltyError::Rejected(StatusCode::NOT_FOUND))?;
+
+        // за одно бронирование баллы начисляются один раз
+        let accrued = req
+            .points(points_rate)
+            .filter(|accrued| balance.checked_add(*accrued).is_some())
+            .ok_or(LoyaltyError::Rejected(StatusCode::UNPROCESSABLE_ENTITY))?;
+        let inserted = diesel::insert_into(points_accrual::table)
+            .values(&PointsAccrual {
+                reservation_uid: req.reservation_uid,
+                username: username.to_owned(),
+                kind: PointsAccrualKind::Accrued.to_string(),
+                points: accrued,
+            })
+            .on_conflict((points_accrual::reservation_uid, points_accrual::kind))
+            .do_nothing()
+            .execute(conn)?;
+        if inserted == 0 {
+            let accrual =
+                find_points_accrual(conn, req.reservation_uid, PointsAccrualKind::Accrued)?
+                    .ok_or(LoyaltyError::Db(DieselError::NotFound))?;
+            if accrual.username != username {
+                return Err(LoyaltyError::Rejected(StatusCode::CONFLICT));
+            }
+            return Ok(PointsAccrualResponse {
+                accrued: accrual.points,
+                balance,
+            });
+        }
+
+        let balance = diesel::update(loyalty::table)
             .filter(loyalty::username.eq(username))
-            .set((loyalty::status.eq(status), loyalty::discount.eq(discount)))
-            .execute(conn)
-            .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+            .set(loyalty::points.eq(loyalty::points + accrued))
+            .returning(loyalty::points)
+            .get_result(conn)?;
+
+        record_event(
+            conn,
+            &LoyaltyEvent {
+                points: accrued,
+                ..LoyaltyEvent::new(
+                    username,
+                    LoyaltyEventKind::PointsAccrued,
+                    Some(req.reservation_uid),
+                )
+            },
+        )?;
+
+        Ok(PointsAccrualResponse { accrued, balance })
+    });
+    let res = loyalty_result(res)?;
+
+    tracing::info!(
+        "Accrued {} points to {username} for reservation {}",
+        res.accrued,
+        req.reservation_uid
+    );
+
+    Ok(Json(res))
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/v1/loyalty/points/redeem",
+    request_body = PointsRedeemRequest,
+    responses(
+        (
+            status = CREATED,
+            description = "Баллы списаны с баланса и зарезервированы под бронирование",
+            body = PointsHoldResponse,
+            content_type = "application/json",
+        ),
+        (status = NOT_FOUND, description = "Участник программы лояльности не найден"),
+        (
+            status = UNPROCESSABLE_ENTITY,
+            description = "Недостаточно баллов или скидка указана не в валюте баллов",
+        ),
+    ),
+    params(
+        ("X-User-Name", Header, description = "Имя пользователя")
+    ),
+)]
+pub async fn post_points_redeem(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Json(req): Json<PointsRedeemRequest>,
+) -> Result<impl IntoResponse, StatusCode> {
+    let username = headers
+        .get("X-User-Name")
+        .ok_or(StatusCode::BAD_REQUEST)?
+        .to_str()
+        .map_err(|_| StatusCode::BAD_REQUEST)?;
+
+    // скидку в валюту баллов переводит вызывающая сторона, как и при начислении
+    if !req.currency.eq_ignore_ascii_case(POINTS_CURRENCY) {
+        return Err(StatusCode::UNPROCESSABLE_ENTITY);
     }
 
-    Ok(StatusCode::NO_CONTENT)
+    // списываем не больше, чем покрывает стоимость бронирования
+    let points = match req.max_discount {
+        Some(max_discount) => req.points.min(max_discount / POINT_VALUE),
+        None => req.points,
+    };
+    if points <= 0 {
+        return Err(StatusCode::BAD_REQUEST);
+    }
+
+    let conn = &mut PgConnection::establish(state.database_url.as_str())
+        .expect("Failed to establish connection to database");
+
+    let res = conn.transaction::<_, LoyaltyError, _>(|conn| {
+        let balance = loyalty::table
+            .filter(loyalty::username.eq(username))
+            .select(loyalty::points)
+            .for_update()
+            .get_result::<i32>(conn)
+            .optional()?
+            .ok_or(LoyaltyError::Rejected(StatusCode::NOT_FOUND))?;
+
+        if balance < points {
+            return Err(LoyaltyError::Rejected(StatusCode::UNPROCESSABLE_ENTITY));
+        }
+
+        diesel::update(loyalty::table)
+            .filter(loyalty::username.eq(username))
+            .set(loyalty::points.eq(loyalty::points - points))
+            .execute(conn)?;
+
+        let hold = PointsHold {
+            hold_uid: Uuid::new_v4(),
+            username: username.to_owned(),
+            points,
+            reservation_uid: req.reservation_uid,
+            status: PointsHoldStatus::Held.to_string(),
+        };
+        diesel::insert_into(points_hold::table)
+            .values(&hold)
+            .execute(conn)?;
+        record_event(
+            conn,
+            &LoyaltyEvent {
+                points: -points,
+                ..LoyaltyEvent::new(username, LoyaltyEventKind::PointsHeld, req.reservation_uid)
+            },
+        )?;
+
+        Ok(hold)
+    });
+    let hold = loyalty_result(res)?;
+
+    let res = PointsHoldResponse {
+        hold_uid: hold.hold_uid,
+        points: hold.points,
+        discount: hold.points * POINT_VALUE,
+        status: PointsHoldStatus::Held,
+    };
+
+    Ok((StatusCode::CREATED, Json(res)))
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/v1/loyalty/points/holds/{holdUid}/capture",
+    responses(
+        (
+            status = OK,
+            description = "Резерв баллов подтверждён",
+            body = PointsHoldResponse,
+            content_type = "application/json",
+        ),
+        (status = NOT_FOUND, description = "Резерв баллов не найден"),
+        (status = CONFLICT, description = "Резерв баллов уже освобождён"),
+    ),
+    params(
+        ("holdUid" = Uuid, Path, description = "UUID резерва баллов"),
+        ("X-User-Name", Header, description = "Имя пользователя")
+    ),
+)]
+pub async fn capture_points_hold(
+    State(state): State<AppState>,
+    Path(hold_uid): Path<Uuid>,
+    headers: HeaderMap,
+) -> Result<impl IntoResponse, StatusCode> {
+    let username = headers
+        .get("X-User-Name")
+        .ok_or(StatusCode::BAD_REQUEST)?
+        .to_str()
+        .map_err(|_| StatusCode::BAD_REQUEST)?;
+
+    let conn = &mut PgConnection::establish(state.database_url.as_str())
+        .expect("Failed to establish connection to database");
+
+    let res = conn.transaction::<_, LoyaltyError, _>(|conn| {
+        let hold = find_points_hold(conn, hold_uid, username)?;
+
+        match hold.status.parse() {
+            Ok(PointsHoldStatus::Held) => {
+                diesel::update(points_hold::table)
+                    .filter(points_hold::hold_uid.eq(hold_uid))
+                    .set(points_hold::status.eq(PointsHoldStatus::Captured.to_string()))
+                    .execute(conn)?;
+                record_event(
+                    conn,
+                    &LoyaltyEvent::new(
+                        username,
+                        LoyaltyEventKind::PointsCaptured,
+                        hold.reservation_uid,
+                    ),
+                )?;
+                Ok(hold)
+            }
+            Ok(PointsHoldStatus::Captured) => Ok(hold),
+            _ => Err(LoyaltyError::Rejected(StatusCode::CONFLICT)),
+        }
+    });
+    let hold = loyalty_result(res)?;
+
+    let res = PointsHoldResponse {
+        hold_uid: hold.hold_uid,
+        points: hold.points,
+        discount: hold.points * POINT_VALUE,
+        status: PointsHoldStatus::Captured,
+    };
+
+    Ok(Json(res))
+}
+
+#[utoipa::path(
+    delete,
+    path = "/api/v1/loyalty/points/holds/{holdUid}",
+    responses(
+        (
+            status = OK,
+            description = "Резерв баллов освобождён, баллы возвращены на баланс",
+            body = PointsHoldResponse,
+            content_type = "application/json",
+        ),
+        (status = NOT_FOUND, description = "Резерв баллов не найден"),
+        (status = CONFLICT, description = "Резерв баллов уже подтверждён"),
+    ),
+    params(
+        ("holdUid" = Uuid, Path, description = "UUID резерва баллов"),
+        ("X-User-Name", Header, description = "Имя пользователя")
+    ),
+)]
+pub async fn release_points_hold(
+    State(state): State<AppState>,
+    Path(hold_uid): Path<Uuid>,
+    headers: HeaderMap,
+) -> Result<impl IntoResponse, StatusCode> {
+    let username = headers
+        .get("X-User-Name")
+        .ok_or(StatusCode::BAD_REQUEST)?
+        .to_str()
+        .map_err(|_| StatusCode::BAD_REQUEST)?;
+
+    let conn = &mut PgConnection::establish(state.database_url.as_str())
+        .expect("Failed to establish connection to database");
+
+    let res = conn.transaction::<_, LoyaltyError, _>(|conn| {
+        let hold = find_points_hold(conn, hold_uid, username)?;
+
+        match hold.status.parse() {
+            Ok(PointsHoldStatus::Held) => {
+                diesel::update(points_hold::table)
+                    .filter(points_hold::hold_uid.eq(hold_uid))
+                    .set(points_hold::status.eq(PointsHoldStatus::Released.to_string()))
+                    .execute(conn)?;
+                diesel::update(loyalty::table)
+                    .filter(loyalty::username.eq(username))
+                    .set(loyalty::points.eq(loyalty::points + hold.points))
+                    .execute(conn)?;
+                record_event(
+                    conn,
+                    &LoyaltyEvent {
+                        points: hold.points,
+                        ..LoyaltyEvent::new(
+                            username,
+                            LoyaltyEventKind::PointsReleased,
+                            hold.reservation_uid,
+                        )
+                    },
+                )?;
+                Ok(hold)
+            }
+            Ok(PointsHoldStatus::Released) => Ok(hold),
+            _ => Err(LoyaltyError::Rejected(StatusCode::CONFLICT)),
+        }
+    });
+    let hold = loyalty_result(res)?;
+
+    let res = PointsHoldResponse {
+        hold_uid: hold.hold_uid,
+        points: hold.points,
+        discount: hold.points * POINT_VALUE,
+        status: PointsHoldStatus::Released,
+    };
+
+    Ok(Json(res))
+}
+
+fn find_points_hold(
+    conn: &mut PgConnection,
+    hold_uid: Uuid,
+    username: &str,
+) -> Result<PointsHold, LoyaltyError> {
+    points_hold::table
+        .filter(points_hold::hold_uid.eq(hold_uid))
+        .filter(points_hold::username.eq(username))
+        .select(PointsHold::as_select())
+        .for_update()
+        .get_result::<PointsHold>(conn)
+        .optional()?
+        .ok_or(LoyaltyError::Rejected(StatusCode::NOT_FOUND))
+}
+
+fn find_points_accrual(
+    conn: &mut PgConnection,
+    reservation_uid: Uuid,
+    kind: PointsAccrualKind,
+) -> QueryResult<Option<PointsAccrual>> {
+    points_accrual::table
+        .filter(points_accrual::reservation_uid.eq(reservation_uid))
+        .filter(points_accrual::kind.eq(kind.to_string()))
+        .select(PointsAccrual::as_select())
+        .get_result::<PointsAccrual>(conn)
+        .optional()
+}
+
+// при отмене бронирования потраченные баллы возвращаются, а начисленные списываются
+fn reverse_reservation_points(
+    conn: &mut PgConnection,
+    username: &str,
+    reservation_uid: Uuid,
+) -> QueryResult<()> {
+    let holds = points_hold::table
+        .filter(points_hold::reservation_uid.eq(reservation_uid))
+        .filter(points_hold::username.eq(username))
+        .filter(points_hold::status.ne(PointsHoldStatus::Released.to_string()))
+        .select(PointsHold::as_select())
+        .for_update()
+        .load::<PointsHold>(conn)?;
+    for hold in holds {
+        diesel::update(points_hold::table)
+            .filter(points_hold::hold_uid.eq(hold.hold_uid))
+            .set(points_hold::status.eq(PointsHoldStatus::Released.to_string()))
+            .execute(conn)?;
+        diesel::update(loyalty::table)
+            .filter(loyalty::username.eq(username))
+            .set(loyalty::points.eq(loyalty::points + hold.points))
+            .execute(conn)?;
+        record_event(
+            conn,
+            &LoyaltyEvent {
+                points: hold.points,
+                ..LoyaltyEvent::new(
+                    username,
+                    LoyaltyEventKind::PointsReleased,
+                    Some(reservation_uid),
+                )
+            },
+        )?;
+    }
+
+    let Some(accrual) = find_points_accrual(conn, reservation_uid, PointsAccrualKind::Accrued)?
+    else {
+        return Ok(());
+    };
+    if accrual.username != username {
+        return Ok(());
+    }
+    // уже потраченные баллы списать нельзя, баланс не уходит в минус
+    let balance = loyalty::table
+        .filter(loyalty::username.eq(username))
+        .select(loyalty::points)
+        .for_update()
+        .get_result::<i32>(conn)?;
+    let reversed = accrual.points.min(balance);
+    let inserted = diesel::insert_into(points_accrual::table)
+        .values(&PointsAccrual {
+            reservation_uid,
+            username: username.to_owned(),
+            kind: PointsAccrualKind::Reversed.to_string(),
+            points: reversed,
+        })
+        .on_conflict((points_accrual::reservation_uid, points_accrual::kind))
+        .do_nothing()
+        .execute(conn)?;
+    if inserted == 0 || reversed == 0 {
+        return Ok(());
+    }
+
+    diesel::update(loyalty::table)
+        .filter(loyalty::username.eq(username))
+        .set(loyalty::points.eq(loyalty::points - reversed))
+        .execute(conn)?;
+    record_event(
+        conn,
+        &LoyaltyEvent {
+            points: -reversed,
+            ..LoyaltyEvent::new(
+                username,
+                LoyaltyEventKind::PointsReversed,
+                Some(reservation_uid),
+            )
+        },
+    )
+}
+
+// при отмене бронирования возвращается использованная в нём разовая скидка,
+// а ещё не завершённое приглашение снимается, чтобы код можно было применить снова
+fn restore_reservation_bonus(
+    conn: &mut PgConnection,
+    username: &str,
+    reservation_uid: Uuid,
+) -> QueryResult<()> {
+    diesel::delete(referral::table)
+        .filter(referral::reservation_uid.eq(reservation_uid))
+        .filter(referral::referee.eq(username))
+        .filter(referral::status.eq(ReferralStatus::Pending.to_string()))
+        .execute(conn)?;
+
+    let used = loyalty_events::table
+        .filter(loyalty_events::username.eq(username))
+        .filter(loyalty_events::reservation_uid.eq(reservation_uid))
+        .filter(loyalty_events::kind.eq(LoyaltyEventKind::BonusDiscountUsed.to_string()))
+        .select(loyalty_events::discount)
+        .first::<Option<i32>>(conn)
+        .optional()?
+        .flatten()
+        .unwrap_or(0);
+    if used <= 0 {
+        return Ok(());
+    }
+
+    // неиспользованные скидки суммируются, но не дают больше 100%
+    let current = loyalty::table
+        .filter(loyalty::username.eq(username))
+        .select(loyalty::bonus_discount)
+        .for_update()
+        .get_result::<i32>(conn)?;
+    let total = (current + used).min(100);
+    diesel::update(loyalty::table)
+        .filter(loyalty::username.eq(username))
+        .set(loyalty::bonus_discount.eq(total))
+        .execute(conn)?;
+    record_event(
+        conn,
+        &LoyaltyEvent {
+            discount: Some(total - current),
+            ..LoyaltyEvent::new(
+                username,
+                LoyaltyEventKind::BonusDiscountRestored,
+                Some(reservation_uid),
+            )
+        },
+    )
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/v1/loyalty/referral-code",
+    responses(
+        (
+            status = OK,
+            description = "Реферальный код пользователя",
+            body = ReferralCode,
+            content_type = "application/json",
+        ),
+        (status = NOT_FOUND, description = "Участник программы лояльности не найден"),
+    ),
+    params(
+        ("X-User-Name", Header, description = "Имя пользователя")
+    ),
+)]
+pub async fn post_referral_code(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+) -> Result<impl IntoResponse, StatusCode> {
+    let username = headers
+        .get("X-User-Name")
+        .ok_or(StatusCode::BAD_REQUEST)?
+        .to_str()
+        .map_err(|_| StatusCode::BAD_REQUEST)?;
+
+    let conn = &mut PgConnection::establish(state.database_url.as_str())
+        .expect("Failed to establish connection to database");
+
+    let res = conn.transaction::<_, LoyaltyError, _>(|conn| {
+        loyalty::table
+            .filter(loyalty::username.eq(username))
+            .select(loyalty::username)
+            .get_result::<String>(conn)?;
+
+        diesel::insert_into(referral_code::table)
+            .values(&ReferralCode::new(username))
+            .on_conflict(referral_code::username)
+            .do_nothing()
+            .execute(conn)?;
+
+        Ok(referral_code::table
+            .filter(referral_code::username.eq(username))
+            .select(ReferralCode::as_select())
+            .get_result::<ReferralCode>(conn)?)
+    });
+
+    Ok(Json(loyalty_result(res)?))
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/v1/loyalty/referral-codes/{code}",
+    responses(
+        (
+            status = OK,
+            description = "Реферальный код существует",
+            body = ReferralCode,
+            content_type = "application/json",
+        ),
+        (status = NOT_FOUND, description = "Реферальный код не найден"),
+        (
+            status = UNPROCESSABLE_ENTITY,
+            description = "Пользователь не может воспользоваться этим реферальным кодом",
+        ),
+    ),
+    params(
+        ("code", Path, description = "Реферальный код"),
+        ("X-User-Name", Header, description = "Пользователь, применяющий код"),
+    ),
+)]
+pub async fn get_referral_code(
+    State(state): State<AppState>,
+    Path(code): Path<String>,
+    headers: HeaderMap,
+) -> Result<impl IntoResponse, StatusCode> {
+    let username = headers
+        .get("X-User-Name")
+        .map(|v| v.to_str().map_err(|_| StatusCode::BAD_REQUEST))
+        .transpose()?;
+
+    let conn = &mut PgConnection::establish(state.database_url.as_str())
+        .expect("Failed to establish connection to database");
+
+    let res = referral_code::table
+        .filter(referral_code::code.eq(code.to_uppercase()))
+        .select(ReferralCode::as_select())
+        .get_result::<ReferralCode>(conn)
+        .map_err(|e| match e {
+            DieselError::NotFound => StatusCode::NOT_FOUND,
+            _ => StatusCode::INTERNAL_SERVER_ERROR,
+        })?;
+
+    // те же условия, что и при учёте бронирования: только новый участник и не свой код
+    if let Some(username) = username {
+        let is_member =
+            has_counted_bookings(conn, username).map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+        if is_member || res.username == username {
+            return Err(StatusCode::UNPROCESSABLE_ENTITY);
+        }
+    }
+
+    Ok(Json(res))
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/v1/loyalty/referrals",
+    responses(
+        (
+            status = OK,
+            description = "Приглашённые пользователем участники",
+            body = Vec<Referral>,
+            content_type = "application/json",
+        ),
+    ),
+    params(
+        ("X-User-Name", Header, description = "Имя пользователя")
+    ),
+)]
+pub async fn get_referrals(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+) -> Result<impl IntoResponse, StatusCode> {
+    let username = headers
+        .get("X-User-Name")
+        .ok_or(StatusCode::BAD_REQUEST)?
+        .to_str()
+        .map_err(|_| StatusCode::BAD_REQUEST)?;
+
+    let conn = &mut PgConnection::establish(state.database_url.as_str())
+        .expect("Failed to establish connection to database");
+
+    let res = referral::table
+        .filter(referral::referrer.eq(username))
+        .order(referral::id.desc())
+        .select(Referral::as_select())
+        .load::<Referral>(conn)
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+    Ok(Json(res))
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/v1/admin/loyalty/members",
+    responses(
+        (
+            status = OK,
+            description = "Участники программы лояльности",
+            body = LoyaltyMemberList,
+            content_type = "application/json",
+        ),
+    ),
+    params(
+        ("status", Query, description = "Фильтр по уровню"),
+        ("page", Query, description = "Номер страницы"),
+        ("size", Query, description = "Количество элементов страницы"),
+    ),
+)]
+pub async fn get_admin_members(
+    State(state): State<AppState>,
+    Query(query): Query<LoyaltyMemberListQuery>,
+) -> Result<impl IntoResponse, StatusCode> {
+    let page = query.page.unwrap_or(1).max(1);
+    let size = query.size.unwrap_or(10).clamp(1, MAX_PAGE_SIZE);
+
+    let conn = &mut PgConnection::establish(state.database_url.as_str())
+        .expect("Failed to establish connection to database");
+
+    let mut select = loyalty::table
+        .order(loyalty::username)
+        .select(Loyalty::as_select())
+        .into_boxed();
+    if let Some(status) = query.status {
+        select = select.filter(loyalty::status.eq(status));
+    }
+
+    let (items, _, total) = select
+        .paginate(page as i64)
+        .per_page(size as i64)
+        .load_and_count_pages::<Loyalty>(conn)
+        .map_err(|e| {
+            tracing::error!("Failed to load loyalty members: {e}");
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?;
+
+    Ok(Json(LoyaltyMemberList {
+        page,
+        page_size: size,
+        total_elements: total as usize,
+        items,
+    }))
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/v1/admin/loyalty/members/{username}",
+    responses(
+        (
+            status = OK,
+            description = "Данные программы лояльности пользователя",
+            body = LoyaltyResponse,
+            content_type = "application/json",
+        ),
+        (status = NOT_FOUND, description = "Участник программы лояльности не найден"),
+    ),
+    params(
+        ("username", Path, description = "Имя пользователя")
+    ),
+)]
+pub async fn get_admin_member(
+    State(state): State<AppState>,
+    Path(username): Path<String>,
+) -> Result<impl IntoResponse, StatusCode> {
+    let conn = &mut PgConnection::establish(state.database_url.as_str())
+        .expect("Failed to establish connection to database");
+
+    let res = load_loyalty(conn, &username, &state.qualification)?;
+
+    Ok(Json(res))
+}
+
+#[utoipa::path(
+    put,
+    path = "/api/v1/admin/loyalty/members/{username}/reservation-count",
+    request_body = ReservationCountAdjustment,
+    responses(
+        (
+            status = OK,
+            description = "Счётчик бронирований изменён, поправка учтена при переаттестации уровня",
+            body = LoyaltyResponse,
+            content_type = "application/json",
+        ),
+        (status = BAD_REQUEST, description = "Нужно указать ровно одно из полей set или delta"),
+        (status = NOT_FOUND, description = "Участник программы лояльности не найден"),
+        (status = UNPROCESSABLE_ENTITY, description = "Счётчик не может быть отрицательным"),
+    ),
+    params(
+        ("username", Path, description = "Имя пользователя"),
+        ("X-User-Name", Header, description = "Имя администратора")
+    ),
+)]
+pub async fn put_admin_reservation_count(
+    State(state): State<AppState>,
+    Path(username): Path<String>,
+    headers: HeaderMap,
+    Json(req): Json<ReservationCountAdjustment>,
+) -> Result<impl IntoResponse, StatusCode> {
+    let actor = headers
+        .get("X-User-Name")
+        .ok_or(StatusCode::BAD_REQUEST)?
+        .to_str()
+        .map_err(|_| StatusCode::BAD_REQUEST)?;
+
+    let conn = &mut PgConnection::establish(state.database_url.as_str())
+        .expect("Failed to establish connection to database");
+
+    let res = conn.transaction::<_, LoyaltyError, _>(|conn| {
+        let current = loyalty::table
+            .filter(loyalty::username.eq(&username))
+            .select(loyalty::reservation_count)
+            .for_update()
+            .get_result::<i32>(conn)?;
+        let delta = match (req.set, req.delta) {
+            (Some(value), None) => value - current,
+            (None, Some(delta)) => delta,
+            _ => return Err(LoyaltyError::Rejected(StatusCode::BAD_REQUEST)),
+        };
+
+        // поправка сохраняется отдельно, чтобы переаттестация учитывала её поверх окна бронирований
+        let counter = diesel::update(loyalty::table)
+            .filter(loyalty::username.eq(&username))
+            .set((
+                loyalty::reservation_count.eq(loyalty::reservation_count + delta),
+                loyalty::qualifying_adjustment.eq(loyalty::qualifying_adjustment + delta),
+            ))
+            .returning(loyalty::reservation_count)
+            .get_result::<i32>(conn)?;
+
+        record_event(
+            conn,
+            &LoyaltyEvent {
+                reservation_count: Some(counter),
+                actor: Some(actor.to_owned()),
+                reason: Some(req.reason.clone()),
+                ..LoyaltyEvent::new(&username, LoyaltyEventKind::ReservationCountAdjusted, None)
+            },
+        )?;
+        update_member_tier(conn, &username, &state.qualification, None)?;
+
+        Ok(())
+    });
+    loyalty_result(res)?;
+
+    tracing::info!(
+        "{actor} adjusted reservation count of {username}: {}",
+        req.reason
+    );
+
+    Ok(Json(load_loyalty(conn, &username, &state.qualification)?))
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/v1/admin/loyalty/members/{username}/points",
+    request_body = PointsGrant,
+    responses(
+        (
+            status = OK,
+            description = "Баллы начислены или списаны",
+            body = LoyaltyResponse,
+            content_type = "application/json",
+        ),
+        (status = UNPROCESSABLE_ENTITY, description = "Баланс баллов не может быть отрицательным"),
+    ),
+    params(
+        ("username", Path, description = "Имя пользователя"),
+        ("X-User-Name", Header, description = "Имя администратора")
+    ),
+)]
+pub async fn post_admin_points(
+    State(state): State<AppState>,
+    Path(username): Path<String>,
+    headers: HeaderMap,
+    Json(req): Json<PointsGrant>,
+) -> Result<impl IntoResponse, StatusCode> {
+    let actor = headers
+        .get("X-User-Name")
+        .ok_or(StatusCode::BAD_REQUEST)?
+        .to_str()
+        .map_err(|_| StatusCode::BAD_REQUEST)?;
+
+    let conn = &mut PgConnection::establish(state.database_url.as_str())
+        .expect("Failed to establish connection to database");
+
+    let res = conn.transaction::<_, LoyaltyError, _>(|conn| {
+        diesel::update(loyalty::table)
+            .filter(loyalty::username.eq(&username))
+            .set(loyalty::points.eq(loyalty::points + req.points))
+            .returning(loyalty::points)
+            .get_result::<i32>(conn)?;
+
+        record_event(
+            conn,
+            &LoyaltyEvent {
+                points: req.points,
+                actor: Some(actor.to_owned()),
+                reason: Some(req.reason.clone()),
+                ..LoyaltyEvent::new(&username, LoyaltyEventKind::PointsGranted, None)
+            },
+        )?;
+
+        Ok(())
+    });
+    loyalty_result(res)?;
+
+    tracing::info!(
+        "{actor} granted {} points to {username}: {}",
+        req.points,
+        req.reason
+    );
+
+    Ok(Json(load_loyalty(conn, &username, &state.qualification)?))
+}
+
+#[utoipa::path(
+    put,
+    path = "/api/v1/admin/loyalty/members/{username}/tier-pin",
+    request_body = TierPin,
+    responses(
+        (
+            status = OK,
+            description = "Уровень закреплён до указанной даты",
+            body = LoyaltyResponse,
+            content_type = "application/json",
+        ),
+        (status = BAD_REQUEST, description = "Срок закрепления уже истёк"),
+    ),
+    params(
+        ("username", Path, description = "Имя пользователя"),
+        ("X-User-Name", Header, description = "Имя администратора")
+    ),
+)]
+pub async fn put_admin_tier_pin(
+    State(state): State<AppState>,
+    Path(username): Path<String>,
+    headers: HeaderMap,
+    Json(req): Json<TierPin>,
+) -> Result<impl IntoResponse, StatusCode> {
+    let actor = headers
+        .get("X-User-Name")
+        .ok_or(StatusCode::BAD_REQUEST)?
+        .to_str()
+        .map_err(|_| StatusCode::BAD_REQUEST)?;
+
+    if req.expires_at <= chrono::Local::now() {
+        return Err(StatusCode::BAD_REQUEST);
+    }
+
+    let conn = &mut PgConnection::establish(state.database_url.as_str())
+        .expect("Failed to establish connection to database");
+
+    let res = conn.transaction::<_, LoyaltyError, _>(|conn| {
+        // после окончания закрепления участник проходит обычную переаттестацию
+        diesel::update(loyalty::table)
+            .filter(loyalty::username.eq(&username))
+            .set((
+                loyalty::pinned_status.eq(req.status),
+                loyalty::pinned_until.eq(req.expires_at),
+                loyalty::requalify_at.eq(req.expires_at),
+                loyalty::downgrade_at.eq(None::<chrono::DateTime<chrono::Local>>),
+            ))
+            .returning(loyalty::username)
+            .get_result::<String>(conn)?;
+
+        record_event(
+            conn,
+            &LoyaltyEvent {
+                status_to: Some(req.status),
+                actor: Some(actor.to_owned()),
+                reason: Some(req.reason.clone()),
+                ..LoyaltyEvent::new(&username, LoyaltyEventKind::TierPinned, None)
+            },
+        )?;
+        update_member_tier(conn, &username, &state.qualification, None)?;
+
+        Ok(())
+    });
+    loyalty_result(res)?;
+
+    tracing::info!(
+        "{actor} pinned {username} to {} until {}: {}",
+        req.status,
+        req.expires_at,
+        req.reason
+    );
+
+    Ok(Json(load_loyalty(conn, &username, &state.qualification)?))
+}
+
+#[utoipa::path(
+    delete,
+    path = "/api/v1/admin/loyalty/members/{username}/tier-pin",
+    responses(
+        (
+            status = OK,
+            description = "Закрепление уровня снято",
+            body = LoyaltyResponse,
+            content_type = "application/json",
+        ),
+    ),
+    params(
+        ("username", Path, description = "Имя пользователя"),
+        ("reason", Query, description = "Причина"),
+        ("X-User-Name", Header, description = "Имя администратора")
+    ),
+)]
+pub async fn delete_admin_tier_pin(
+    State(state): State<AppState>,
+    Path(username): Path<String>,
+    headers: HeaderMap,
+    Query(query): Query<AdminReasonQuery>,
+) -> Result<impl IntoResponse, StatusCode> {
+    let actor = headers
+        .get("X-User-Name")
+        .ok_or(StatusCode::BAD_REQUEST)?
+        .to_str()
+        .map_err(|_| StatusCode::BAD_REQUEST)?;
+
+    let conn = &mut PgConnection::establish(state.database_url.as_str())
+        .expect("Failed to establish connection to database");
+
+    let res = conn.transaction::<_, LoyaltyError, _>(|conn| {
+        diesel::update(loyalty::table)
+            .filter(loyalty::username.eq(&username))
+            .set((
+                loyalty::pinned_status.eq(None::<LoyaltyStatus>),
+                loyalty::pinned_until.eq(None::<chrono::DateTime<chrono::Local>>),
+                loyalty::requalify_at.eq(chrono::Local::now()),
+            ))
+            .returning(loyalty::username)
+            .get_result::<String>(conn)?;
+
+        record_event(
+            conn,
+            &LoyaltyEvent {
+                actor: Some(actor.to_owned()),
+                reason: Some(query.reason.clone()),
+                ..LoyaltyEvent::new(&username, LoyaltyEventKind::TierUnpinned, None)
+            },
+        )?;
+        update_member_tier(conn, &username, &state.qualification, None)?;
+
+        Ok(())
+    });
+    loyalty_result(res)?;
+
+    tracing::info!("{actor} unpinned tier of {username}: {}", query.reason);
+
+    Ok(Json(load_loyalty(conn, &username, &state.qualification)?))
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/v1/admin/loyalty/stats",
+    responses(
+        (
+            status = OK,
+            description = "Количество участников и среднее число бронирований по уровням",
+            body = LoyaltyStats,
+            content_type = "application/json",
+        ),
+        (status = OK, description = "Статистика в формате CSV", content_type = "text/csv"),
+    ),
+    params(
+        ("format", Query, description = "Формат ответа: json или csv"),
+    ),
+)]
+pub async fn get_loyalty_stats(
+    State(state): State<AppState>,
+    Query(query): Query<StatsQuery>,
+) -> Result<impl IntoResponse, StatusCode> {
+    let conn = &mut PgConnection::establish(state.database_url.as_str())
+        .expect("Failed to establish connection to database");
+
+    let res = loyalty_stats(conn).map_err(|e| {
+        tracing::error!("Failed to compute loyalty stats: {e}");
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
+
+    Ok(render(query.format.unwrap_or_default(), res))
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/v1/admin/loyalty/stats/top",
+    responses(
+        (
+            status = OK,
+            description = "Участники с наибольшим числом бронирований или баллов",
+            body = Vec<TopMember>,
+            content_type = "application/json",
+        ),
+        (status = OK, description = "Рейтинг в формате CSV", content_type = "text/csv"),
+    ),
+    params(
+        ("by", Query, description = "Критерий: reservations или points"),
+        ("limit", Query, description = "Количество участников"),
+        ("format", Query, description = "Формат ответа: json или csv"),
+    ),
+)]
+pub async fn get_top_members(
+    State(state): State<AppState>,
+    Query(query): Query<TopMembersQuery>,
+) -> Result<impl IntoResponse, StatusCode> {
+    let conn = &mut PgConnection::establish(state.database_url.as_str())
+        .expect("Failed to establish connection to database");
+
+    let res = top_members(
+        conn,
+        query.by.unwrap_or_default(),
+        query.limit.unwrap_or(10),
+    )
+    .map_err(|e| {
+        tracing::error!("Failed to load top loyalty members: {e}");
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
+
+    Ok(render(query.format.unwrap_or_default(), res))
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/v1/admin/loyalty/stats/tier-migrations",
+    responses(
+        (
+            status = OK,
+            description = "Переходы между уровнями по периодам",
+            body = Vec<TierMigration>,
+            content_type = "application/json",
+        ),
+        (status = OK, description = "Переходы в формате CSV", content_type = "text/csv"),
+        (status = BAD_REQUEST, description = "Начало периода позже его окончания"),
+    ),
+    params(
+        ("from", Query, description = "Начало периода, по умолчанию год назад"),
+        ("to", Query, description = "Окончание периода включительно, по умолчанию сегодня"),
+        ("interval", Query, description = "Группировка: day, week или month"),
+        ("format", Query, description = "Формат ответа: json или csv"),
+    ),
+)]
+pub async fn get_tier_migrations(
+    State(state): State<AppState>,
+    Query(query): Query<TierMigrationQuery>,
+) -> Result<impl IntoResponse, StatusCode> {
+    let to = query
+        .to
+        .unwrap_or_else(|| chrono::Local::now().date_naive());
+    let from = query.from.unwrap_or(to - chrono::TimeDelta::days(365));
+    if from > to {
+        return Err(StatusCode::BAD_REQUEST);
+    }
+
+    let conn = &mut PgConnection::establish(state.database_url.as_str())
+        .expect("Failed to establish connection to database");
+
+    let res = tier_migrations(conn, from, to, query.interval.unwrap_or_default()).map_err(|e| {
+        tracing::error!("Failed to load tier migrations: {e}");
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
+
+    Ok(render(query.format.unwrap_or_default(), res))
 }
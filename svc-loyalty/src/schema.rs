@@ -9,5 +9,128 @@ diesel::table! {
         #[max_length = 80]
         status -> Varchar,
         discount -> Int4,
+        points -> Int4,
+        requalify_at -> Timestamptz,
+        downgrade_at -> Nullable<Timestamptz>,
+        bonus_discount -> Int4,
+        #[max_length = 80]
+        pinned_status -> Nullable<Varchar>,
+        pinned_until -> Nullable<Timestamptz>,
+        qualifying_adjustment -> Int4,
+    }
+}
+
+diesel::table! {
+    loyalty_events (id) {
+        id -> Int4,
+        #[max_length = 80]
+        username -> Varchar,
+        #[max_length = 40]
+        kind -> Varchar,
+        reservation_uid -> Nullable<Uuid>,
+        reservation_count -> Nullable<Int4>,
+        points -> Int4,
+        #[max_length = 80]
+        status_from -> Nullable<Varchar>,
+        #[max_length = 80]
+        status_to -> Nullable<Varchar>,
+        created_at -> Timestamptz,
+        discount -> Nullable<Int4>,
+        #[max_length = 80]
+        actor -> Nullable<Varchar>,
+        reason -> Nullable<Text>,
+    }
+}
+
+diesel::table! {
+    loyalty_reservation (id) {
+        id -> Int4,
+        reservation_uid -> Uuid,
+        #[max_length = 80]
+        username -> Varchar,
+        counted_at -> Timestamptz,
+        canceled_at -> Nullable<Timestamptz>,
+        stay_end_date -> Nullable<Date>,
+    }
+}
+
+diesel::table! {
+    loyalty_tier (id) {
+        id -> Int4,
+        #[max_length = 80]
+        name -> Varchar,
+        threshold -> Int4,
+        discount -> Int4,
+        perks -> Array<Text>,
+        points_rate -> Int4,
+        cancellation_extension_hours -> Int4,
+        late_checkout -> Bool,
+        free_night_after -> Nullable<Int4>,
+    }
+}
+
+diesel::table! {
+    points_accrual (id) {
+        id -> Int4,
+        reservation_uid -> Uuid,
+        #[max_length = 80]
+        username -> Varchar,
+        #[max_length = 20]
+        kind -> Varchar,
+        points -> Int4,
+        created_at -> Timestamptz,
+    }
+}
+
+diesel::table! {
+    points_hold (id) {
+        id -> Int4,
+        hold_uid -> Uuid,
+        #[max_length = 80]
+        username -> Varchar,
+        points -> Int4,
+        reservation_uid -> Nullable<Uuid>,
+        #[max_length = 20]
+        status -> Varchar,
+        created_at -> Timestamptz,
+    }
+}
+
+diesel::table! {
+    referral (id) {
+        id -> Int4,
+        #[max_length = 20]
+        code -> Varchar,
+        #[max_length = 80]
+        referrer -> Varchar,
+        #[max_length = 80]
+        referee -> Varchar,
+        reservation_uid -> Uuid,
+        #[max_length = 20]
+        status -> Varchar,
+        created_at -> Timestamptz,
+        completed_at -> Nullable<Timestamptz>,
+    }
+}
+
+diesel::table! {
+    referral_code (id) {
+        id -> Int4,
+        #[max_length = 20]
+        code -> Varchar,
+        #[max_length = 80]
+        username -> Varchar,
+        created_at -> Timestamptz,
     }
 }
+
+diesel::allow_tables_to_appear_in_same_query!(
+    loyalty,
+    loyalty_events,
+    loyalty_reservation,
+    loyalty_tier,
+    points_accrual,
+    points_hold,
+    referral,
+    referral_code,
+);
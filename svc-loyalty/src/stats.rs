@@ -0,0 +1,191 @@
+use axum::{
+    http::header,
+    response::{IntoResponse, Response},
+    Json,
+};
+use chrono::NaiveDate;
+use diesel::{
+    dsl::{count_star, sum},
+    prelude::*,
+    sql_types::{Date, Text},
+};
+use serde::Serialize;
+
+use crate::{
+    dto::{
+        LoyaltyEventKind, LoyaltyStats, LoyaltyStatus, StatsFormat, StatsInterval, TierMigration,
+        TierStats, TopMember, TopMembersOrder,
+    },
+    schema::{loyalty, loyalty_tier},
+};
+
+pub const MAX_TOP_MEMBERS: i64 = 100;
+
+pub fn loyalty_stats(conn: &mut PgConnection) -> QueryResult<LoyaltyStats> {
+    let rows = loyalty::table
+        .group_by(loyalty::status)
+        .select((
+            loyalty::status,
+            count_star(),
+            sum(loyalty::reservation_count),
+            sum(loyalty::points),
+        ))
+        .load::<(LoyaltyStatus, i64, Option<i64>, Option<i64>)>(conn)?;
+
+    // уровни без участников тоже попадают в отчёт
+    let statuses = loyalty_tier::table
+        .order(loyalty_tier::threshold)
+        .select(loyalty_tier::name)
+        .load::<LoyaltyStatus>(conn)?;
+
+    let tiers = statuses
+        .into_iter()
+        .map(|status| {
+            let (members, reservations, points) = rows
+                .iter()
+                .find(|row| row.0 == status)
+                .map_or((0, 0, 0), |row| {
+                    (row.1, row.2.unwrap_or(0), row.3.unwrap_or(0))
+                });
+            TierStats {
+                status,
+                members,
+                average_reservations: average(reservations, members),
+                points,
+            }
+        })
+        .collect::<Vec<_>>();
+
+    let total_members = rows.iter().map(|row| row.1).sum::<i64>();
+    let total_reservations = rows.iter().map(|row| row.2.unwrap_or(0)).sum::<i64>();
+
+    Ok(LoyaltyStats {
+        total_members,
+        average_reservations: average(total_reservations, total_members),
+        tiers,
+    })
+}
+
+pub fn top_members(
+    conn: &mut PgConnection,
+    order: TopMembersOrder,
+    limit: i64,
+) -> QueryResult<Vec<TopMember>> {
+    let query = loyalty::table
+        .select((
+            loyalty::username,
+            loyalty::status,
+            loyalty::reservation_count,
+            loyalty::points,
+        ))
+        .limit(limit.clamp(1, MAX_TOP_MEMBERS))
+        .into_boxed();
+    let query = match order {
+        TopMembersOrder::Reservations => {
+            query.order((loyalty::reservation_count.desc(), loyalty::username))
+        }
+        TopMembersOrder::Points => query.order((loyalty::points.desc(), loyalty::username)),
+    };
+
+    query.load::<TopMember>(conn)
+}
+
+// переходы между уровнями, сгруппированные по периодам
+pub fn tier_migrations(
+    conn: &mut PgConnection,
+    from: NaiveDate,
+    to: NaiveDate,
+    interval: StatsInterval,
+) -> QueryResult<Vec<TierMigration>> {
+    diesel::sql_query(format!(
+        "SELECT date_trunc('{interval}', created_at)::date AS period, \
+                status_from, status_to, count(*) AS count \
+         FROM loyalty_events \
+         WHERE kind = $1 AND created_at >= $2 AND created_at < $3 + 1 \
+         GROUP BY period, status_from, status_to \
+         ORDER BY period, status_from, status_to"
+    ))
+    .bind::<Text, _>(LoyaltyEventKind::TierChanged.to_string())
+    .bind::<Date, _>(from)
+    .bind::<Date, _>(to)
+    .load::<TierMigration>(conn)
+}
+
+fn average(total: i64, count: i64) -> f64 {
+    if count == 0 {
+        0.0
+    } else {
+        total as f64 / count as f64
+    }
+}
+
+pub trait ToCsv {
+    fn to_csv(&self) -> String;
+}
+
+impl ToCsv for LoyaltyStats {
+    fn to_csv(&self) -> String {
+        let mut csv = String::from("status,members,average_reservations,points\n");
+        for tier in &self.tiers {
+            csv.push_str(&format!(
+                "{},{},{:.2},{}\n",
+                tier.status, tier.members, tier.average_reservations, tier.points
+            ));
+        }
+        csv.push_str(&format!(
+            "TOTAL,{},{:.2},{}\n",
+            self.total_members,
+            self.average_reservations,
+            self.tiers.iter().map(|t| t.points).sum::<i64>()
+        ));
+        csv
+    }
+}
+
+impl ToCsv for Vec<TopMember> {
+    fn to_csv(&self) -> String {
+        let mut csv = String::from("username,status,reservation_count,points\n");
+        for member in self {
+            csv.push_str(&format!(
+                "{},{},{},{}\n",
+                escape_csv(&member.username),
+                member.status,
+                member.reservation_count,
+                member.points
+            ));
+        }
+        csv
+    }
+}
+
+impl ToCsv for Vec<TierMigration> {
+    fn to_csv(&self) -> String {
+        let mut csv = String::from("period,status_from,status_to,count\n");
+        for migration in self {
+            csv.push_str(&format!(
+                "{},{},{},{}\n",
+                migration.period, migration.status_from, migration.status_to, migration.count
+            ));
+        }
+        csv
+    }
+}
+
+pub fn render<T: Serialize + ToCsv>(format: StatsFormat, value: T) -> Response {
+    match format {
+        StatsFormat::Json => Json(value).into_response(),
+        StatsFormat::Csv => (
+            [(header::CONTENT_TYPE, "text/csv; charset=utf-8")],
+            value.to_csv(),
+        )
+            .into_response(),
+    }
+}
+
+pub fn escape_csv(s: &str) -> String {
+    if s.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", s.replace('"', "\"\""))
+    } else {
+        s.to_owned()
+    }
+}
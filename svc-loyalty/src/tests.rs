@@ -1 +1,143 @@
+use chrono::NaiveDate;
+use uuid::Uuid;
+
+use crate::{
+    dto::{
+        BookingPerks, LoyaltyStats, LoyaltyStatus, LoyaltyTier, PointsAccrualRequest,
+        TierMigration, TierStats, TopMember,
+    },
+    stats::{escape_csv, ToCsv},
+};
+
+#[test]
 fn hello_world() {}
+
+fn tier(free_night_after: Option<i32>) -> LoyaltyTier {
+    LoyaltyTier {
+        name: LoyaltyStatus::Silver,
+        threshold: 10,
+        discount: 7,
+        perks: vec![],
+        points_rate: 2,
+        cancellation_extension_hours: 24,
+        late_checkout: true,
+        free_night_after,
+    }
+}
+
+#[test]
+fn booking_perks_copy_tier_benefits() {
+    let perks = BookingPerks::new(tier(None), 3);
+
+    assert_eq!(perks.status, LoyaltyStatus::Silver);
+    assert_eq!(perks.cancellation_extension_hours, 24);
+    assert!(perks.late_checkout);
+    assert_eq!(perks.free_nights, 0);
+}
+
+#[test]
+fn booking_perks_free_night_on_every_nth_stay() {
+    // учитывается очередное, ещё не созданное бронирование
+    assert_eq!(BookingPerks::new(tier(Some(5)), 4).free_nights, 1);
+    assert_eq!(BookingPerks::new(tier(Some(5)), 9).free_nights, 1);
+    assert_eq!(BookingPerks::new(tier(Some(5)), 5).free_nights, 0);
+    assert_eq!(BookingPerks::new(tier(Some(1)), 0).free_nights, 1);
+}
+
+#[test]
+fn escape_csv_quotes_special_characters() {
+    assert_eq!(escape_csv("Test Max"), "Test Max");
+    assert_eq!(escape_csv("Max, Jr."), "\"Max, Jr.\"");
+    assert_eq!(escape_csv("say \"hi\""), "\"say \"\"hi\"\"\"");
+    assert_eq!(escape_csv("two\nlines"), "\"two\nlines\"");
+    assert_eq!(escape_csv(""), "");
+}
+
+#[test]
+fn loyalty_stats_to_csv() {
+    let stats = LoyaltyStats {
+        total_members: 3,
+        average_reservations: 4.0 / 3.0,
+        tiers: vec![
+            TierStats {
+                status: LoyaltyStatus::Bronze,
+                members: 2,
+                average_reservations: 1.5,
+                points: 100,
+            },
+            TierStats {
+                status: LoyaltyStatus::Gold,
+                members: 1,
+                average_reservations: 1.0,
+                points: 250,
+            },
+        ],
+    };
+
+    assert_eq!(
+        stats.to_csv(),
+        "status,members,average_reservations,points\n\
+         BRONZE,2,1.50,100\n\
+         GOLD,1,1.00,250\n\
+         TOTAL,3,1.33,350\n"
+    );
+}
+
+#[test]
+fn top_members_to_csv_escapes_usernames() {
+    let members = vec![
+        TopMember {
+            username: "Test Max".to_owned(),
+            status: LoyaltyStatus::Gold,
+            reservation_count: 25,
+            points: 300,
+        },
+        TopMember {
+            username: "Max, Jr.".to_owned(),
+            status: LoyaltyStatus::Bronze,
+            reservation_count: 1,
+            points: 0,
+        },
+    ];
+
+    assert_eq!(
+        members.to_csv(),
+        "username,status,reservation_count,points\n\
+         Test Max,GOLD,25,300\n\
+         \"Max, Jr.\",BRONZE,1,0\n"
+    );
+}
+
+#[test]
+fn tier_migrations_to_csv() {
+    let migrations = vec![TierMigration {
+        period: NaiveDate::from_ymd_opt(2025, 3, 1).unwrap(),
+        status_from: LoyaltyStatus::Bronze,
+        status_to: LoyaltyStatus::Silver,
+        count: 4,
+    }];
+
+    assert_eq!(
+        migrations.to_csv(),
+        "period,status_from,status_to,count\n2025-03-01,BRONZE,SILVER,4\n"
+    );
+    assert_eq!(
+        Vec::<TierMigration>::new().to_csv(),
+        "period,status_from,status_to,count\n"
+    );
+}
+
+#[test]
+fn points_accrual_does_not_overflow() {
+    let accrual = |amount: i32| PointsAccrualRequest {
+        amount,
+        currency: "RUB".to_owned(),
+        reservation_uid: Uuid::nil(),
+    };
+
+    assert_eq!(accrual(10_000).points(3), Some(300));
+    assert_eq!(accrual(i32::MAX).points(100), Some(i32::MAX));
+    // произведение суммы на ставку не помещается в i32
+    assert_eq!(accrual(i32::MAX).points(50), Some(i32::MAX / 2));
+    assert_eq!(accrual(i32::MAX).points(200), None);
+}
@@ -0,0 +1,318 @@
+use std::{env, time::Duration};
+
+use chrono::{DateTime, TimeDelta};
+use diesel::prelude::*;
+use uuid::Uuid;
+
+use crate::{
+    dto::{Loyalty, LoyaltyEvent, LoyaltyEventKind, LoyaltyTier, TierProgress},
+    events::record_event,
+    schema::{loyalty, loyalty_reservation, loyalty_tier},
+};
+
+pub const DEFAULT_QUALIFICATION_WINDOW_DAYS: i64 = 365;
+pub const DEFAULT_GRACE_PERIOD_DAYS: i64 = 30;
+pub const DEFAULT_REQUALIFICATION_INTERVAL_SECS: u64 = 3600;
+
+#[derive(Debug, Clone, Copy)]
+pub struct QualificationPolicy {
+    // за какой период учитываются бронирования
+    pub window: TimeDelta,
+    // сколько сохраняется уровень после неудачной переаттестации
+    pub grace_period: TimeDelta,
+}
+
+impl QualificationPolicy {
+    pub fn from_env() -> Self {
+        let days = |name: &str, default: i64| {
+            env::var(name)
+                .ok()
+                .and_then(|v| v.parse::<i64>().ok())
+                .unwrap_or(default)
+        };
+
+        Self {
+            window: TimeDelta::days(days(
+                "LOYALTY_QUALIFICATION_WINDOW_DAYS",
+                DEFAULT_QUALIFICATION_WINDOW_DAYS,
+            )),
+            grace_period: TimeDelta::days(days(
+                "LOYALTY_GRACE_PERIOD_DAYS",
+                DEFAULT_GRACE_PERIOD_DAYS,
+            )),
+        }
+    }
+}
+
+// самый высокий уровень, порог которого достигнут счётчиком бронирований
+pub fn tier_for_counter(conn: &mut PgConnection, counter: i32) -> QueryResult<LoyaltyTier> {
+    loyalty_tier::table
+        .filter(loyalty_tier::threshold.le(counter.max(0)))
+        .order(loyalty_tier::threshold.desc())
+        .select(LoyaltyTier::as_select())
+        .first(conn)
+}
+
+// проживания, завершённые за скользящее окно, с поправкой администратора;
+// у бронирований, учтённых до появления даты выезда, она не заполнена,
+// и для них берётся дата учёта
+pub fn qualifying_count(
+    conn: &mut PgConnection,
+    username: &str,
+    since: DateTime<chrono::Local>,
+) -> QueryResult<i32> {
+    let today = chrono::Local::now().date_naive();
+    let count = loyalty_reservation::table
+        .filter(loyalty_reservation::username.eq(username))
+        .filter(loyalty_reservation::canceled_at.is_null())
+        .filter(
+            loyalty_reservation::stay_end_date
+                .between(since.date_naive(), today)
+                .or(loyalty_reservation::stay_end_date
+                    .is_null()
+                    .and(loyalty_reservation::counted_at.ge(since))),
+        )
+        .count()
+        .get_result::<i64>(conn)?;
+    let adjustment = loyalty::table
+        .filter(loyalty::username.eq(username))
+        .select(loyalty::qualifying_adjustment)
+        .get_result::<i32>(conn)
+        .optional()?
+        .unwrap_or(0);
+
+    Ok((count as i32).saturating_add(adjustment).max(0))
+}
+
+pub fn tier_progress(
+    conn: &mut PgConnection,
+    username: &str,
+    policy: &QualificationPolicy,
+) -> QueryResult<TierProgress> {
+    let counter = qualifying_count(conn, username, chrono::Local::now() - policy.window)?;
+    let next_tier = loyalty_tier::table
+        .filter(loyalty_tier::threshold.gt(counter))
+        .order(loyalty_tier::threshold)
+        .select(LoyaltyTier::as_select())
+        .first(conn)
+        .optional()?;
+
+    Ok(TierProgress {
+        qualifying_reservations: counter,
+        reservations_to_next_tier: next_tier.as_ref().map(|t| t.threshold - counter),
+        next_tier: next_tier.map(|t| t.name),
+    })
+}
+
+// повышение применяется сразу, понижение — только после даты переаттестации и льготного периода;
+// возвращает true, если уровень участника изменился
+pub fn update_member_tier(
+    conn: &mut PgConnection,
+    username: &str,
+    policy: &QualificationPolicy,
+    reservation_uid: Option<Uuid>,
+) -> QueryResult<bool> {
+    evaluate_member_tier(conn, username, policy, reservation_uid, false)
+}
+
+// при пересчёте после изменения правил дата переаттестации не ждётся,
+// понижение назначается сразу с льготным периодом
+fn evaluate_member_tier(
+    conn: &mut PgConnection,
+    username: &str,
+    policy: &QualificationPolicy,
+    reservation_uid: Option<Uuid>,
+    recompute: bool,
+) -> QueryResult<bool> {
+    let now = chrono::Local::now();
+    let member = loyalty::table
+        .filter(loyalty::username.eq(username))
+        .select(Loyalty::as_select())
+        .for_update()
+        .get_result::<Loyalty>(conn)?;
+
+    let counter = qualifying_count(conn, username, now - policy.window)?;
+    // закреплённый администратором уровень действует до истечения срока
+    let pinned_tier = match member.pinned_status {
+        Some(status) if member.pinned_until.is_some_and(|until| until > now) => loyalty_tier::table
+            .filter(loyalty_tier::name.eq(status))
+            .select(LoyaltyTier::as_select())
+            .get_result::<LoyaltyTier>(conn)
+            .optional()?,
+        _ => None,
+    };
+    let is_pinned = pinned_tier.is_some();
+    let target = match pinned_tier {
+        Some(tier) => tier,
+        None => tier_for_counter(conn, counter)?,
+    };
+    let current_threshold = loyalty_tier::table
+        .filter(loyalty_tier::name.eq(member.status))
+        .select(loyalty_tier::threshold)
+        .get_result::<i32>(conn)
+        .optional()?
+        .unwrap_or(0);
+
+    if !is_pinned && target.threshold < current_threshold {
+        if !recompute && member.requalify_at > now {
+            return Ok(false);
+        }
+        match member.downgrade_at {
+            None => {
+                diesel::update(loyalty::table)
+                    .filter(loyalty::username.eq(username))
+                    .set(loyalty::downgrade_at.eq(now + policy.grace_period))
+                    .execute(conn)?;
+                record_event(
+                    conn,
+                    &LoyaltyEvent {
+                        reservation_count: Some(counter),
+                        status_from: Some(member.status),
+                        status_to: Some(target.name),
+                        ..LoyaltyEvent::new(
+                            username,
+                            LoyaltyEventKind::TierDowngradeScheduled,
+                            reservation_uid,
+                        )
+                    },
+                )?;
+                return Ok(false);
+            }
+            Some(downgrade_at) if downgrade_at > now => return Ok(false),
+            Some(_) => {}
+        }
+    }
+
+    let requalify_at =
+        if is_pinned || target.threshold == current_threshold && member.requalify_at > now {
+            member.requalify_at
+        } else {
+            now + policy.window
+        };
+
+    diesel::update(loyalty::table)
+        .filter(loyalty::username.eq(username))
+        .set((
+            loyalty::status.eq(target.name),
+            loyalty::discount.eq(target.discount),
+            loyalty::requalify_at.eq(requalify_at),
+            loyalty::downgrade_at.eq(None::<DateTime<chrono::Local>>),
+        ))
+        .execute(conn)?;
+
+    if member.status == target.name {
+        return Ok(false);
+    }
+    metrics::counter!(
+        "loyalty_tier_changes_total",
+        "from" => member.status.to_string(),
+        "to" => target.name.to_string()
+    )
+    .increment(1);
+
+    record_event(
+        conn,
+        &LoyaltyEvent {
+            reservation_count: Some(counter),
+            status_from: Some(member.status),
+            status_to: Some(target.name),
+            ..LoyaltyEvent::new(username, LoyaltyEventKind::TierChanged, reservation_uid)
+        },
+    )?;
+
+    Ok(true)
+}
+
+pub fn update_all_tiers(
+    conn: &mut PgConnection,
+    policy: &QualificationPolicy,
+) -> QueryResult<usize> {
+    let usernames = loyalty::table
+        .select(loyalty::username)
+        .load::<String>(conn)?;
+
+    let mut changed = 0;
+    for username in usernames {
+        if evaluate_member_tier(conn, &username, policy, None, true)? {
+            changed += 1;
+        }
+    }
+
+    Ok(changed)
+}
+
+// участники, у которых подошла дата переаттестации, истекает льготный период
+// или недавно завершилось проживание
+pub fn requalify_due_members(
+    conn: &mut PgConnection,
+    policy: &QualificationPolicy,
+) -> QueryResult<usize> {
+    let now = chrono::Local::now();
+    let today = now.date_naive();
+    let mut usernames = loyalty::table
+        .filter(
+            loyalty::requalify_at
+                .le(now)
+                .or(loyalty::downgrade_at.le(now)),
+        )
+        .select(loyalty::username)
+        .load::<String>(conn)?;
+    // повторная проверка безопасна, поэтому окно берётся с запасом в сутки
+    usernames.extend(
+        loyalty_reservation::table
+            .filter(loyalty_reservation::canceled_at.is_null())
+            .filter(loyalty_reservation::stay_end_date.between(today - TimeDelta::days(1), today))
+            .select(loyalty_reservation::username)
+            .distinct()
+            .load::<String>(conn)?,
+    );
+    usernames.sort();
+    usernames.dedup();
+
+    let mut changed = 0;
+    for username in usernames {
+        let updated = conn.transaction(|conn| update_member_tier(conn, &username, policy, None))?;
+        if updated {
+            changed += 1;
+        }
+    }
+
+    Ok(changed)
+}
+
+pub async fn run_requalification(database_url: String, policy: QualificationPolicy) {
+    let period = env::var("LOYALTY_REQUALIFICATION_INTERVAL_SECS")
+        .ok()
+        .and_then(|v| v.parse::<u64>().ok())
+        .filter(|&v| v > 0)
+        .unwrap_or(DEFAULT_REQUALIFICATION_INTERVAL_SECS);
+    let mut interval = tokio::time::interval(Duration::from_secs(period));
+
+    loop {
+        interval.tick().await;
+
+        let database_url = database_url.clone();
+        // diesel блокирует поток, поэтому проверка выполняется вне рабочих потоков tokio
+        let res = tokio::task::spawn_blocking(move || {
+            let conn = &mut match PgConnection::establish(database_url.as_str()) {
+                Ok(conn) => conn,
+                Err(e) => {
+                    tracing::error!("Failed to establish connection to database: {e}");
+                    return;
+                }
+            };
+            match requalify_due_members(conn, &policy) {
+                Ok(changed) => {
+                    tracing::info!(
+                        "Loyalty requalification finished, {changed} members changed tier"
+                    )
+                }
+                Err(e) => tracing::error!("Loyalty requalification failed: {e}"),
+            }
+        })
+        .await;
+        if let Err(e) = res {
+            tracing::error!("Loyalty requalification task failed: {e}");
+        }
+    }
+}
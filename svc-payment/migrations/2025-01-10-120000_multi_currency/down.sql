@@ -0,0 +1,5 @@
+ALTER TABLE payment
+    DROP COLUMN exchange_rate,
+    DROP COLUMN base_currency,
+    DROP COLUMN base_price,
+    DROP COLUMN currency;
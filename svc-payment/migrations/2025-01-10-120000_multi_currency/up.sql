@@ -0,0 +1,7 @@
+ALTER TABLE payment
+    ADD COLUMN currency      VARCHAR(3)       NOT NULL DEFAULT 'RUB',
+    ADD COLUMN base_price    INT              NOT NULL DEFAULT 0,
+    ADD COLUMN base_currency VARCHAR(3)       NOT NULL DEFAULT 'RUB',
+    ADD COLUMN exchange_rate DOUBLE PRECISION NOT NULL DEFAULT 1.0;
+
+UPDATE payment SET base_price = price;
@@ -0,0 +1,5 @@
+DROP INDEX IF EXISTS payment_username_idx;
+
+ALTER TABLE payment
+    DROP COLUMN reservation_uid,
+    DROP COLUMN username;
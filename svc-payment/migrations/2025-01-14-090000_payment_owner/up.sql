@@ -0,0 +1,5 @@
+ALTER TABLE payment
+    ADD COLUMN username        VARCHAR(80),
+    ADD COLUMN reservation_uid UUID;
+
+CREATE INDEX IF NOT EXISTS payment_username_idx ON payment (username);
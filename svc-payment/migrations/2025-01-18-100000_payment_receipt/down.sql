@@ -0,0 +1,10 @@
+ALTER TABLE payment
+    DROP COLUMN discount,
+    DROP COLUMN nightly_price,
+    DROP COLUMN end_date,
+    DROP COLUMN start_date,
+    DROP COLUMN hotel_name,
+    DROP COLUMN created_at,
+    DROP COLUMN invoice_number;
+
+DROP SEQUENCE IF EXISTS payment_invoice_number_seq;
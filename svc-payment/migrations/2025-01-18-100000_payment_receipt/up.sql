@@ -0,0 +1,12 @@
+CREATE SEQUENCE IF NOT EXISTS payment_invoice_number_seq;
+
+ALTER TABLE payment
+    ADD COLUMN invoice_number BIGINT       NOT NULL DEFAULT nextval('payment_invoice_number_seq') UNIQUE,
+    ADD COLUMN created_at     TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT now(),
+    ADD COLUMN hotel_name     VARCHAR(255),
+    ADD COLUMN start_date     DATE,
+    ADD COLUMN end_date       DATE,
+    ADD COLUMN nightly_price  INT,
+    ADD COLUMN discount       INT;
+
+ALTER SEQUENCE payment_invoice_number_seq OWNED BY payment.invoice_number;
@@ -0,0 +1,6 @@
+ALTER TABLE payment
+    DROP COLUMN promo_discount,
+    DROP COLUMN promo_code;
+
+DROP TABLE IF EXISTS promo_code_redemption;
+DROP TABLE IF EXISTS promo_code;
@@ -0,0 +1,33 @@
+CREATE TABLE IF NOT EXISTS promo_code
+(
+    id                SERIAL PRIMARY KEY,
+    code              VARCHAR(40) NOT NULL UNIQUE,
+    kind              VARCHAR(20) NOT NULL
+        CHECK (kind IN ('PERCENTAGE', 'FIXED')),
+    value             INT         NOT NULL CHECK (value > 0),
+    currency          VARCHAR(3),
+    valid_from        TIMESTAMP WITH TIME ZONE,
+    valid_until       TIMESTAMP WITH TIME ZONE,
+    max_uses          INT,
+    max_uses_per_user INT,
+    used_count        INT         NOT NULL DEFAULT 0,
+    stackable         BOOLEAN     NOT NULL DEFAULT FALSE,
+    created_at        TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT now()
+);
+
+CREATE TABLE IF NOT EXISTS promo_code_redemption
+(
+    id              SERIAL PRIMARY KEY,
+    promo_code_id   INT         NOT NULL REFERENCES promo_code (id),
+    username        VARCHAR(80) NOT NULL,
+    reservation_uid UUID,
+    discount        INT         NOT NULL,
+    created_at      TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT now()
+);
+
+CREATE INDEX IF NOT EXISTS promo_code_redemption_user_idx
+    ON promo_code_redemption (promo_code_id, username);
+
+ALTER TABLE payment
+    ADD COLUMN promo_code     VARCHAR(40),
+    ADD COLUMN promo_discount INT NOT NULL DEFAULT 0;
@@ -0,0 +1,9 @@
+CREATE TABLE IF NOT EXISTS payment_item
+(
+    id          SERIAL PRIMARY KEY,
+    payment_uid UUID         NOT NULL,
+    name        VARCHAR(255) NOT NULL,
+    amount      INT          NOT NULL
+);
+
+CREATE INDEX IF NOT EXISTS payment_item_payment_uid_idx ON payment_item (payment_uid);
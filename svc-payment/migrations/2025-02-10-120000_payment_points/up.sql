@@ -0,0 +1,2 @@
+ALTER TABLE payment
+    ADD COLUMN points_discount INT NOT NULL DEFAULT 0;
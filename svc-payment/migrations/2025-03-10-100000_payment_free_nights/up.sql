@@ -0,0 +1,2 @@
+ALTER TABLE payment
+    ADD COLUMN free_nights_discount INT NOT NULL DEFAULT 0;
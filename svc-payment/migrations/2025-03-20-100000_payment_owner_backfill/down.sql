@@ -0,0 +1,2 @@
+-- восстановленные владельцы оплат не сбрасываются
+SELECT 1;
@@ -0,0 +1,11 @@
+-- владелец восстанавливается по погашенным промокодам той же брони
+UPDATE payment p
+SET username = r.username
+FROM promo_code_redemption r
+WHERE p.username IS NULL
+  AND p.reservation_uid IS NOT NULL
+  AND r.reservation_uid = p.reservation_uid;
+
+-- остальные оплаты без владельца заполняются из базы reservations скриптом
+-- scripts/backfill_payment_owner.sh; до этого они никому не доступны,
+-- а новые оплаты без пользователя не создаются
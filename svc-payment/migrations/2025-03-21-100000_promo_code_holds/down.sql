@@ -0,0 +1,5 @@
+DROP INDEX IF EXISTS promo_code_redemption_reservation_idx;
+
+ALTER TABLE promo_code_redemption
+    DROP COLUMN status,
+    DROP COLUMN redemption_uid;
@@ -0,0 +1,26 @@
+-- применения до появления резерва считаются подтверждёнными
+ALTER TABLE promo_code_redemption
+    ADD COLUMN redemption_uid UUID        NOT NULL DEFAULT gen_random_uuid(),
+    ADD COLUMN status         VARCHAR(20) NOT NULL DEFAULT 'CAPTURED'
+        CHECK (status IN ('HELD', 'CAPTURED', 'RELEASED'));
+
+ALTER TABLE promo_code_redemption
+    ALTER COLUMN redemption_uid DROP DEFAULT,
+    ALTER COLUMN status DROP DEFAULT,
+    ADD CONSTRAINT promo_code_redemption_uid_key UNIQUE (redemption_uid);
+
+CREATE INDEX IF NOT EXISTS promo_code_redemption_reservation_idx
+    ON promo_code_redemption (reservation_uid);
+
+-- применения по уже отменённым оплатам возвращаются промокоду
+WITH released AS (
+    UPDATE promo_code_redemption r
+        SET status = 'RELEASED'
+        FROM payment p
+        WHERE p.reservation_uid = r.reservation_uid
+            AND p.status = 'CANCELED'
+        RETURNING r.promo_code_id)
+UPDATE promo_code c
+SET used_count = GREATEST(c.used_count - n.released, 0)
+FROM (SELECT promo_code_id, COUNT(*) AS released FROM released GROUP BY promo_code_id) n
+WHERE c.id = n.promo_code_id;
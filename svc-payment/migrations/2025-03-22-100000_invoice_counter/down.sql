@@ -0,0 +1,9 @@
+CREATE SEQUENCE IF NOT EXISTS payment_invoice_number_seq OWNED BY payment.invoice_number;
+
+SELECT setval('payment_invoice_number_seq', last_number + 1, false)
+FROM invoice_counter;
+
+ALTER TABLE payment
+    ALTER COLUMN invoice_number SET DEFAULT nextval('payment_invoice_number_seq');
+
+DROP TABLE IF EXISTS invoice_counter;
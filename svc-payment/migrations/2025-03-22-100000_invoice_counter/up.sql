@@ -0,0 +1,16 @@
+-- уже выданные номера счетов не меняются, счётчик продолжает с наибольшего из них
+CREATE TABLE IF NOT EXISTS invoice_counter
+(
+    id          INT PRIMARY KEY CHECK (id = 1),
+    last_number BIGINT NOT NULL
+);
+
+INSERT INTO invoice_counter (id, last_number)
+SELECT 1, COALESCE(MAX(invoice_number), 0)
+FROM payment;
+
+-- счётчик без пропусков: номер выделяется в транзакции создания платежа
+ALTER TABLE payment
+    ALTER COLUMN invoice_number DROP DEFAULT;
+
+DROP SEQUENCE IF EXISTS payment_invoice_number_seq;
@@ -1,14 +1,34 @@
-use std::fmt::Display;
+use std::{fmt::Display, str::FromStr};
 
+use chrono::{DateTime, NaiveDate};
 use diesel::prelude::*;
 use serde::{Deserialize, Serialize};
 use utoipa::ToSchema;
 use uuid::Uuid;
 
+use crate::{exchange_rates::DEFAULT_CURRENCY, pricing::PriceItem};
+
 #[derive(Deserialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
 pub struct PaymentRequest {
     pub status: PaymentStatus,
     pub price: i32,
+    pub currency: Option<String>,
+    pub base_price: Option<i32>,
+    pub base_currency: Option<String>,
+    pub exchange_rate: Option<f64>,
+    pub reservation_uid: Option<Uuid>,
+    pub hotel_name: Option<String>,
+    pub start_date: Option<NaiveDate>,
+    pub end_date: Option<NaiveDate>,
+    pub nightly_price: Option<i32>,
+    pub discount: Option<i32>,
+    pub promo_code: Option<String>,
+    pub promo_discount: Option<i32>,
+    pub points_discount: Option<i32>,
+    pub free_nights_discount: Option<i32>,
+    #[serde(default)]
+    pub items: Vec<PriceItem>,
 }
 
 #[derive(Serialize, Queryable, Selectable, Insertable, ToSchema)]
@@ -19,14 +39,308 @@ pub struct Payment {
     pub payment_uid: Uuid,
     pub status: String,
     pub price: i32,
+    pub currency: String,
+    pub base_price: i32,
+    pub base_currency: String,
+    pub exchange_rate: f64,
+    // у оплат, созданных до появления владельца, он может быть не заполнен
+    pub username: Option<String>,
+    pub reservation_uid: Option<Uuid>,
+    // выделяется из invoice_counter при создании платежа
+    pub invoice_number: i64,
+    #[diesel(skip_insertion)]
+    pub created_at: DateTime<chrono::Local>,
+    pub hotel_name: Option<String>,
+    pub start_date: Option<NaiveDate>,
+    pub end_date: Option<NaiveDate>,
+    pub nightly_price: Option<i32>,
+    pub discount: Option<i32>,
+    pub promo_code: Option<String>,
+    pub promo_discount: i32,
+    pub points_discount: i32,
+    pub free_nights_discount: i32,
+}
+
+impl PaymentRequest {
+    pub fn into_payment(self, username: String) -> (Payment, Vec<PaymentItem>) {
+        let currency = self
+            .currency
+            .unwrap_or_else(|| DEFAULT_CURRENCY.to_owned())
+            .to_uppercase();
+
+        let payment_uid = Uuid::new_v4();
+        let items = self
+            .items
+            .into_iter()
+            .map(|item| PaymentItem {
+                payment_uid,
+                name: item.name,
+                amount: item.amount,
+            })
+            .collect();
+
+        let payment = Payment {
+            payment_uid,
+            status: self.status.to_string(),
+            price: self.price,
+            base_price: self.base_price.unwrap_or(self.price),
+            base_currency: self
+                .base_currency
+                .map(|c| c.to_uppercase())
+                .unwrap_or_else(|| currency.clone()),
+            currency,
+            exchange_rate: self.exchange_rate.unwrap_or(1.0),
+            username: Some(username),
+            reservation_uid: self.reservation_uid,
+            invoice_number: 0,
+            created_at: chrono::Local::now(),
+            hotel_name: self.hotel_name,
+            start_date: self.start_date,
+            end_date: self.end_date,
+            nightly_price: self.nightly_price,
+            discount: self.discount,
+            promo_code: self.promo_code,
+            promo_discount: self.promo_discount.unwrap_or(0),
+            points_discount: self.points_discount.unwrap_or(0),
+            free_nights_discount: self.free_nights_discount.unwrap_or(0),
+        };
+
+        (payment, items)
+    }
+}
+
+#[derive(Queryable, Selectable, Insertable)]
+#[diesel(table_name = crate::schema::payment_item)]
+#[diesel(check_for_backend(diesel::pg::Pg))]
+pub struct PaymentItem {
+    pub payment_uid: Uuid,
+    pub name: String,
+    pub amount: i32,
 }
 
-impl From<PaymentRequest> for Payment {
-    fn from(value: PaymentRequest) -> Self {
+impl From<PaymentItem> for PriceItem {
+    fn from(value: PaymentItem) -> Self {
         Self {
-            payment_uid: Uuid::new_v4(),
-            status: value.status.to_string(),
-            price: value.price,
+            name: value.name,
+            amount: value.amount,
+        }
+    }
+}
+
+#[derive(Deserialize)]
+pub struct PaymentListQuery {
+    pub status: Option<PaymentStatus>,
+    pub page: Option<usize>,
+    pub size: Option<usize>,
+}
+
+#[derive(Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct PaymentList {
+    pub page: usize,
+    pub page_size: usize,
+    pub total_elements: usize,
+    pub items: Vec<Payment>,
+}
+
+#[derive(Deserialize)]
+pub struct ExchangeRateQuery {
+    pub from: String,
+    pub to: String,
+    pub date: Option<NaiveDate>,
+}
+
+#[derive(Deserialize)]
+pub struct ReceiptQuery {
+    pub format: Option<ReceiptFormat>,
+}
+
+#[derive(Clone, Copy, Default, Deserialize, ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum ReceiptFormat {
+    #[default]
+    Html,
+    Pdf,
+}
+
+#[derive(Deserialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct PromoCodeRequest {
+    pub code: String,
+    pub kind: PromoCodeKind,
+    pub value: i32,
+    pub currency: Option<String>,
+    pub valid_from: Option<DateTime<chrono::Local>>,
+    pub valid_until: Option<DateTime<chrono::Local>>,
+    pub max_uses: Option<i32>,
+    pub max_uses_per_user: Option<i32>,
+    #[serde(default)]
+    pub stackable: bool,
+}
+
+#[derive(Insertable)]
+#[diesel(table_name = crate::schema::promo_code)]
+#[diesel(check_for_backend(diesel::pg::Pg))]
+pub struct NewPromoCode {
+    pub code: String,
+    pub kind: String,
+    pub value: i32,
+    pub currency: Option<String>,
+    pub valid_from: Option<DateTime<chrono::Local>>,
+    pub valid_until: Option<DateTime<chrono::Local>>,
+    pub max_uses: Option<i32>,
+    pub max_uses_per_user: Option<i32>,
+    pub stackable: bool,
+}
+
+impl From<PromoCodeRequest> for NewPromoCode {
+    fn from(value: PromoCodeRequest) -> Self {
+        Self {
+            code: value.code.to_uppercase(),
+            kind: value.kind.to_string(),
+            value: value.value,
+            currency: value.currency.map(|c| c.to_uppercase()),
+            valid_from: value.valid_from,
+            valid_until: value.valid_until,
+            max_uses: value.max_uses,
+            max_uses_per_user: value.max_uses_per_user,
+            stackable: value.stackable,
+        }
+    }
+}
+
+#[derive(Serialize, Queryable, Selectable, ToSchema)]
+#[diesel(table_name = crate::schema::promo_code)]
+#[diesel(check_for_backend(diesel::pg::Pg))]
+#[serde(rename_all = "camelCase")]
+pub struct PromoCode {
+    #[serde(skip)]
+    pub id: i32,
+    pub code: String,
+    pub kind: String,
+    pub value: i32,
+    pub currency: Option<String>,
+    pub valid_from: Option<DateTime<chrono::Local>>,
+    pub valid_until: Option<DateTime<chrono::Local>>,
+    pub max_uses: Option<i32>,
+    pub max_uses_per_user: Option<i32>,
+    pub used_count: i32,
+    pub stackable: bool,
+    pub created_at: DateTime<chrono::Local>,
+}
+
+impl PromoCode {
+    pub fn is_active(&self, now: DateTime<chrono::Local>) -> bool {
+        self.valid_from.is_none_or(|from| from <= now)
+            && self.valid_until.is_none_or(|until| now < until)
+            && self.max_uses.is_none_or(|max| self.used_count < max)
+    }
+
+    // размер скидки по коду для указанной суммы
+    pub fn discount_for(&self, amount: i32) -> i32 {
+        let discount = if self.kind == PromoCodeKind::Percentage.to_string() {
+            amount * self.value.min(100) / 100
+        } else {
+            self.value
+        };
+        discount.clamp(0, amount)
+    }
+}
+
+#[derive(Deserialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct PromoCodeRedeemRequest {
+    pub amount: i32,
+    pub currency: String,
+    pub loyalty_discount: i32,
+    pub reservation_uid: Option<Uuid>,
+}
+
+#[derive(Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct PromoCodeRedeemResponse {
+    pub redemption_uid: Uuid,
+    pub code: String,
+    pub stackable: bool,
+    pub loyalty_discount: i32,
+    pub promo_discount: i32,
+    pub total: i32,
+}
+
+#[derive(Insertable)]
+#[diesel(table_name = crate::schema::promo_code_redemption)]
+#[diesel(check_for_backend(diesel::pg::Pg))]
+pub struct NewPromoCodeRedemption {
+    pub promo_code_id: i32,
+    pub username: String,
+    pub redemption_uid: Uuid,
+    pub reservation_uid: Option<Uuid>,
+    pub discount: i32,
+    pub status: String,
+}
+
+#[derive(Queryable, Selectable)]
+#[diesel(table_name = crate::schema::promo_code_redemption)]
+#[diesel(check_for_backend(diesel::pg::Pg))]
+pub struct PromoCodeRedemption {
+    pub id: i32,
+    pub promo_code_id: i32,
+    pub redemption_uid: Uuid,
+    pub discount: i32,
+    pub status: String,
+}
+
+#[derive(Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct PromoCodeRedemptionResponse {
+    pub redemption_uid: Uuid,
+    pub promo_discount: i32,
+    pub status: PromoCodeRedemptionStatus,
+}
+
+#[derive(Serialize, Deserialize, ToSchema, PartialEq)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum PromoCodeRedemptionStatus {
+    Held,
+    Captured,
+    Released,
+}
+
+impl Display for PromoCodeRedemptionStatus {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Held => f.write_str("HELD"),
+            Self::Captured => f.write_str("CAPTURED"),
+            Self::Released => f.write_str("RELEASED"),
+        }
+    }
+}
+
+impl FromStr for PromoCodeRedemptionStatus {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "HELD" => Ok(Self::Held),
+            "CAPTURED" => Ok(Self::Captured),
+            "RELEASED" => Ok(Self::Released),
+            _ => Err(()),
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum PromoCodeKind {
+    Percentage,
+    Fixed,
+}
+
+impl Display for PromoCodeKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Percentage => f.write_str("PERCENTAGE"),
+            Self::Fixed => f.write_str("FIXED"),
         }
     }
 }
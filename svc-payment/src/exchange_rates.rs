@@ -0,0 +1,90 @@
+use std::{collections::HashMap, fs, path::Path};
+
+use chrono::NaiveDate;
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+pub const DEFAULT_CURRENCY: &str = "RUB";
+
+#[derive(Clone, Deserialize, Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct ExchangeRate {
+    pub from: String,
+    pub to: String,
+    pub rate: f64,
+    pub effective_from: NaiveDate,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct ExchangeRates {
+    // (from, to) -> список (дата начала действия, курс), отсортированный по дате
+    rates: HashMap<(String, String), Vec<(NaiveDate, f64)>>,
+}
+
+impl ExchangeRates {
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, String> {
+        let path = path.as_ref();
+        let data = fs::read_to_string(path)
+            .map_err(|e| format!("Failed to read {}: {e}", path.display()))?;
+        let rates = serde_json::from_str::<Vec<ExchangeRate>>(&data)
+            .map_err(|e| format!("Failed to parse {}: {e}", path.display()))?;
+
+        Ok(Self::from_rates(rates))
+    }
+
+    pub fn from_rates(rates: Vec<ExchangeRate>) -> Self {
+        let mut res = Self::default();
+        for rate in rates {
+            res.rates
+                .entry((rate.from.to_uppercase(), rate.to.to_uppercase()))
+                .or_default()
+                .push((rate.effective_from, rate.rate));
+        }
+        for list in res.rates.values_mut() {
+            list.sort_by_key(|(date, _)| *date);
+        }
+        res
+    }
+
+    // курс, действующий на указанную дату; обратная пара используется, если прямой нет
+    pub fn find(&self, from: &str, to: &str, date: NaiveDate) -> Option<ExchangeRate> {
+        let from = from.to_uppercase();
+        let to = to.to_uppercase();
+
+        if from == to {
+            return Some(ExchangeRate {
+                from,
+                to,
+                rate: 1.0,
+                effective_from: NaiveDate::MIN,
+            });
+        }
+
+        if let Some((effective_from, rate)) = self.effective(&from, &to, date) {
+            return Some(ExchangeRate {
+                from,
+                to,
+                rate,
+                effective_from,
+            });
+        }
+
+        self.effective(&to, &from, date)
+            .filter(|(_, rate)| *rate != 0.0)
+            .map(|(effective_from, rate)| ExchangeRate {
+                from,
+                to,
+                rate: 1.0 / rate,
+                effective_from,
+            })
+    }
+
+    fn effective(&self, from: &str, to: &str, date: NaiveDate) -> Option<(NaiveDate, f64)> {
+        self.rates
+            .get(&(from.to_owned(), to.to_owned()))?
+            .iter()
+            .rev()
+            .find(|(effective_from, _)| *effective_from <= date)
+            .copied()
+    }
+}
@@ -0,0 +1,128 @@
+use std::{
+    collections::BTreeMap,
+    time::{Duration, Instant},
+};
+
+use axum::{
+    http::StatusCode,
+    response::{IntoResponse, Response},
+    Json,
+};
+use diesel::prelude::*;
+use diesel_migrations::MigrationHarness;
+use serde::Serialize;
+use utoipa::ToSchema;
+
+use crate::MIGRATIONS;
+
+pub const CHECK_TIMEOUT: Duration = Duration::from_secs(2);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, ToSchema)]
+#[serde(rename_all = "UPPERCASE")]
+pub enum HealthStatus {
+    Up,
+    Down,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct DependencyHealth {
+    pub status: HealthStatus,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub latency_ms: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub detail: Option<String>,
+}
+
+impl DependencyHealth {
+    fn up(latency: Option<Duration>) -> Self {
+        Self {
+            status: HealthStatus::Up,
+            latency_ms: latency.map(|l| l.as_millis() as u64),
+            detail: None,
+        }
+    }
+
+    fn down(detail: impl Into<String>) -> Self {
+        Self {
+            status: HealthStatus::Down,
+            latency_ms: None,
+            detail: Some(detail.into()),
+        }
+    }
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct HealthReport {
+    pub status: HealthStatus,
+    pub checks: BTreeMap<String, DependencyHealth>,
+}
+
+impl HealthReport {
+    pub fn new(checks: BTreeMap<String, DependencyHealth>) -> Self {
+        let status = if checks.values().all(|c| c.status == HealthStatus::Up) {
+            HealthStatus::Up
+        } else {
+            HealthStatus::Down
+        };
+
+        Self { status, checks }
+    }
+}
+
+impl IntoResponse for HealthReport {
+    fn into_response(self) -> Response {
+        let status = match self.status {
+            HealthStatus::Up => StatusCode::OK,
+            HealthStatus::Down => StatusCode::SERVICE_UNAVAILABLE,
+        };
+
+        (status, Json(self)).into_response()
+    }
+}
+
+// сервис готов принимать запросы, если база доступна и все миграции применены
+pub async fn readiness(database_url: String) -> HealthReport {
+    let check = tokio::task::spawn_blocking(move || {
+        let started_at = Instant::now();
+        let conn = &mut PgConnection::establish(&database_url).map_err(|e| e.to_string())?;
+        diesel::sql_query("SELECT 1")
+            .execute(conn)
+            .map_err(|e| e.to_string())?;
+        let latency = started_at.elapsed();
+        let pending = conn.pending_migrations(MIGRATIONS).map(|m| m.len());
+
+        Ok::<_, String>((latency, pending))
+    });
+
+    let (database, migrations) = match tokio::time::timeout(CHECK_TIMEOUT, check).await {
+        Ok(Ok(Ok((latency, pending)))) => (
+            DependencyHealth::up(Some(latency)),
+            match pending {
+                Ok(0) => DependencyHealth::up(None),
+                Ok(n) => DependencyHealth::down(format!("{n} pending migrations")),
+                Err(e) => DependencyHealth::down(e.to_string()),
+            },
+        ),
+        Ok(Ok(Err(e))) => (
+            DependencyHealth::down(e),
+            DependencyHealth::down("database is unavailable"),
+        ),
+        Ok(Err(e)) => (
+            DependencyHealth::down(e.to_string()),
+            DependencyHealth::down("database is unavailable"),
+        ),
+        Err(_) => (
+            DependencyHealth::down("timed out"),
+            DependencyHealth::down("database is unavailable"),
+        ),
+    };
+    if database.status == HealthStatus::Down {
+        tracing::warn!("Database is not ready: {:?}", database.detail);
+    }
+
+    HealthReport::new(BTreeMap::from([
+        ("database".to_owned(), database),
+        ("migrations".to_owned(), migrations),
+    ]))
+}
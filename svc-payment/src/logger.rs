@@ -1,18 +1,206 @@
-use log::LevelFilter;
+use std::{
+    collections::BTreeMap,
+    env,
+    str::FromStr,
+    sync::{Mutex, OnceLock},
+};
+
+use log::{LevelFilter, Record};
 use log4rs::{
     append::console::ConsoleAppender,
     config::{Appender, Logger, Root},
+    encode::{self, Encode},
     Config, Handle,
 };
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+use crate::request_id;
+
+pub const DEFAULT_LOG_LEVEL: &str = "debug";
+
+// уровни журнала: общий и для отдельных модулей (по префиксу target)
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct LogLevels {
+    #[schema(example = "info")]
+    pub root: String,
+    #[serde(default)]
+    pub modules: BTreeMap<String, String>,
+}
+
+impl LogLevels {
+    // формат как у RUST_LOG: "info,diesel=warn,bmstu_rsoi_lab2_gateway::routes=debug"
+    pub fn parse(directives: &str) -> Result<Self, String> {
+        let mut levels = Self {
+            root: DEFAULT_LOG_LEVEL.to_owned(),
+            modules: BTreeMap::new(),
+        };
+        for directive in directives
+            .split(',')
+            .map(str::trim)
+            .filter(|d| !d.is_empty())
+        {
+            match directive.split_once('=') {
+                Some((module, level)) => {
+                    levels
+                        .modules
+                        .insert(module.trim().to_owned(), level.trim().to_owned());
+                }
+                None => levels.root = directive.to_owned(),
+            }
+        }
+        levels.validate()?;
+
+        Ok(levels)
+    }
+
+    pub fn validate(&self) -> Result<(), String> {
+        for level in std::iter::once(&self.root).chain(self.modules.values()) {
+            LevelFilter::from_str(level).map_err(|_| format!("unknown log level {level}"))?;
+        }
+
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+enum LogFormat {
+    Json,
+    Text,
+}
+
+struct LoggerState {
+    handle: Handle,
+    service: &'static str,
+    format: LogFormat,
+    levels: LogLevels,
+}
+
+static LOGGER: OnceLock<Mutex<LoggerState>> = OnceLock::new();
+
+// одна запись — один JSON-объект в строке
+#[derive(Debug)]
+struct JsonEncoder {
+    service: &'static str,
+}
 
-pub fn init() -> Handle {
-    let stdout = ConsoleAppender::builder().build();
+impl Encode for JsonEncoder {
+    fn encode(&self, w: &mut dyn encode::Write, record: &Record) -> anyhow::Result<()> {
+        let line = serde_json::json!({
+            "timestamp": chrono::Local::now().to_rfc3339(),
+            "service": self.service,
+            "level": record.level().as_str(),
+            "target": record.target(),
+            "message": record.args().to_string(),
+            "request_id": request_id::current(),
+            "user": request_id::current_user(),
+        });
+        writeln!(w, "{line}")?;
 
-    let config = Config::builder()
+        Ok(())
+    }
+}
+
+// к каждой записи добавляется идентификатор запроса, в рамках которого она сделана
+#[derive(Debug)]
+struct TextEncoder;
+
+impl Encode for TextEncoder {
+    fn encode(&self, w: &mut dyn encode::Write, record: &Record) -> anyhow::Result<()> {
+        writeln!(
+            w,
+            "{} {} {} [{}] - {}",
+            chrono::Local::now().to_rfc3339(),
+            record.level(),
+            record.target(),
+            request_id::current().as_deref().unwrap_or("-"),
+            record.args()
+        )?;
+
+        Ok(())
+    }
+}
+
+fn config(service: &'static str, format: LogFormat, levels: &LogLevels) -> Config {
+    let encoder: Box<dyn Encode> = match format {
+        LogFormat::Json => Box::new(JsonEncoder { service }),
+        LogFormat::Text => Box::new(TextEncoder),
+    };
+    let stdout = ConsoleAppender::builder().encoder(encoder).build();
+    let level = |level: &str| LevelFilter::from_str(level).unwrap_or(LevelFilter::Debug);
+
+    // спаны запросов экспортируются отдельно и не дублируются в журнале
+    let mut modules = BTreeMap::from([(
+        concat!(env!("CARGO_CRATE_NAME"), "::telemetry").to_owned(),
+        "warn".to_owned(),
+    )]);
+    modules.extend(levels.modules.clone());
+
+    Config::builder()
         .appender(Appender::builder().build("stdout", Box::new(stdout)))
-        .logger(Logger::builder().build("app", LevelFilter::Debug))
-        .build(Root::builder().appender("stdout").build(LevelFilter::Debug))
+        .loggers(
+            modules
+                .iter()
+                .map(|(module, l)| Logger::builder().build(module, level(l))),
+        )
+        .build(
+            Root::builder()
+                .appender("stdout")
+                .build(level(&levels.root)),
+        )
+        .unwrap()
+}
+
+// LOG_FORMAT: json (по умолчанию) или text; LOG_LEVEL: уровни в формате RUST_LOG
+pub fn init(service: &'static str) {
+    let format = match env::var("LOG_FORMAT").as_deref() {
+        Ok("text") => LogFormat::Text,
+        _ => LogFormat::Json,
+    };
+    let directives = env::var("LOG_LEVEL").unwrap_or_else(|_| DEFAULT_LOG_LEVEL.to_owned());
+    let (levels, error) = match LogLevels::parse(&directives) {
+        Ok(levels) => (levels, None),
+        Err(e) => (LogLevels::parse(DEFAULT_LOG_LEVEL).unwrap(), Some(e)),
+    };
+
+    let handle = log4rs::init_config(config(service, format, &levels)).unwrap();
+    if let Some(e) = error {
+        log::warn!("Invalid LOG_LEVEL, using {DEFAULT_LOG_LEVEL}: {e}");
+    }
+
+    LOGGER.get_or_init(|| {
+        Mutex::new(LoggerState {
+            handle,
+            service,
+            format,
+            levels,
+        })
+    });
+}
+
+pub fn levels() -> LogLevels {
+    LOGGER
+        .get()
+        .expect("Logger is not initialized")
+        .lock()
+        .unwrap()
+        .levels
+        .clone()
+}
+
+// новые уровни применяются сразу, без перезапуска сервиса
+pub fn set_levels(levels: LogLevels) -> Result<(), String> {
+    levels.validate()?;
+
+    let mut state = LOGGER
+        .get()
+        .expect("Logger is not initialized")
+        .lock()
         .unwrap();
+    state
+        .handle
+        .set_config(config(state.service, state.format, &levels));
+    state.levels = levels;
 
-    log4rs::init_config(config).unwrap()
+    Ok(())
 }
@@ -1,35 +1,88 @@
-use std::env;
+use std::{env, sync::Arc};
 
 use diesel::prelude::*;
 use diesel_migrations::{embed_migrations, EmbeddedMigrations, MigrationHarness};
 use dto::*;
+use exchange_rates::{ExchangeRate, ExchangeRates};
+use pricing::{PriceItem, PricingQuoteRequest, PricingQuoteResponse, PricingRules};
 use routes::*;
+use signature::SignatureVerifier;
 use tokio::net::TcpListener;
 use utoipa::OpenApi;
 use utoipa_axum::{router::OpenApiRouter, routes};
 use utoipa_swagger_ui::SwaggerUi;
 
+mod diesel_paginate;
 mod dto;
+mod exchange_rates;
+mod health;
 mod logger;
+mod monitoring;
+mod pricing;
+mod receipt;
+mod request_id;
 mod routes;
 mod schema;
+mod telemetry;
 
 #[cfg(test)]
 mod tests;
 
 #[derive(utoipa::OpenApi)]
 #[openapi(
-    paths(post_payment, delete_payment, get_payment),
-    components(schemas(PaymentStatus, Payment, PaymentRequest))
+    paths(
+        post_payment,
+        delete_payment,
+        refund_payment,
+        get_payment,
+        get_payments,
+        get_receipt,
+        get_exchange_rate,
+        post_pricing_quote,
+        post_promo_code,
+        get_promo_codes,
+        redeem_promo_code,
+        capture_promo_redemption,
+        release_promo_redemption,
+        routes::get_log_levels,
+        routes::put_log_levels
+    ),
+    components(schemas(
+        PaymentStatus,
+        Payment,
+        PaymentList,
+        PaymentRequest,
+        ExchangeRate,
+        PromoCode,
+        PromoCodeKind,
+        PromoCodeRequest,
+        PromoCodeRedeemRequest,
+        PromoCodeRedeemResponse,
+        PromoCodeRedemptionResponse,
+        PromoCodeRedemptionStatus,
+        PriceItem,
+        PricingQuoteRequest,
+        PricingQuoteResponse,
+        logger::LogLevels,
+        health::HealthStatus,
+        health::DependencyHealth,
+        health::HealthReport
+    ))
 )]
 struct ApiDoc;
 
 pub const MIGRATIONS: EmbeddedMigrations = embed_migrations!();
 pub const SERVICE_ENDPOINT: &str = "0.0.0.0:8060";
+pub const DEFAULT_EXCHANGE_RATES_PATH: &str = "exchange_rates.json";
+pub const DEFAULT_PRICING_RULES_PATH: &str = "pricing_rules.json";
+// наибольший размер страницы истории оплат
+pub const MAX_PAGE_SIZE: usize = 100;
 
 #[derive(Debug, Clone)]
 struct AppState {
     database_url: String,
+    exchange_rates: Arc<ExchangeRates>,
+    pricing_rules: Arc<PricingRules>,
 }
 
 #[tokio::main(flavor = "multi_thread", worker_threads = 4)]
@@ -37,12 +90,14 @@ async fn main() {
     let database_url =
         env::var("DATABASE_URL").expect("DATABASE_URL environment variable was not specified");
 
-    let _logger_handler = logger::init();
-    log::debug!("Logger initialized. Hello, world!");
+    logger::init("payment");
+    let _tracer_provider = telemetry::init("payment");
+    monitoring::init();
+    tracing::debug!("Logger initialized. Hello, world!");
 
     let app = app(database_url).await;
 
-    log::info!("Listening on {}", SERVICE_ENDPOINT);
+    tracing::info!("Listening on {}", SERVICE_ENDPOINT);
     let listener = TcpListener::bind(SERVICE_ENDPOINT).await.unwrap();
     axum::serve(listener, app.into_make_service())
         .await
@@ -52,15 +107,50 @@ async fn main() {
 async fn app(database_url: String) -> axum::Router {
     init_db(database_url.as_str());
 
+    let exchange_rates_path =
+        env::var("EXCHANGE_RATES_PATH").unwrap_or_else(|_| DEFAULT_EXCHANGE_RATES_PATH.to_owned());
+    let exchange_rates = ExchangeRates::load(&exchange_rates_path)
+        .unwrap_or_else(|e| panic!("Failed to load exchange rates: {e}"));
+    let pricing_rules_path =
+        env::var("PRICING_RULES_PATH").unwrap_or_else(|_| DEFAULT_PRICING_RULES_PATH.to_owned());
+    let pricing_rules = PricingRules::load(&pricing_rules_path)
+        .unwrap_or_else(|e| panic!("Failed to load pricing rules: {e}"));
+
     let swagger = SwaggerUi::new("/swagger-ui").url("/openapi.json", ApiDoc::openapi());
-    let state = AppState { database_url };
+    let state = AppState {
+        database_url,
+        exchange_rates: Arc::new(exchange_rates),
+        pricing_rules: Arc::new(pricing_rules),
+    };
     let app = OpenApiRouter::with_openapi(ApiDoc::openapi())
-        .routes(routes!(routes::check_health))
         .routes(routes!(routes::post_payment))
+        .routes(routes!(routes::get_payments))
+        .routes(routes!(routes::get_receipt))
         .routes(routes!(routes::get_payment, routes::delete_payment))
+        .routes(routes!(routes::refund_payment))
+        .routes(routes!(routes::get_exchange_rate))
+        .routes(routes!(routes::post_pricing_quote))
+        .routes(routes!(routes::post_promo_code, routes::get_promo_codes))
+        .routes(routes!(routes::redeem_promo_code))
+        .routes(routes!(routes::capture_promo_redemption))
+        .routes(routes!(routes::release_promo_redemption))
+        .routes(routes!(routes::get_log_levels, routes::put_log_levels))
+        .route_layer(axum::middleware::from_fn_with_state(
+            SignatureVerifier::from_env(),
+            signature::verify_signature,
+        ))
+        // проверки работоспособности не подписываются
+        .routes(routes!(routes::check_health))
+        .routes(routes!(routes::check_liveness))
+        .routes(routes!(routes::check_readiness))
+        .routes(routes!(routes::get_metrics))
         .with_state(state);
 
-    axum::Router::from(app).merge(swagger)
+    axum::Router::from(app)
+        .merge(swagger)
+        .layer(axum::middleware::from_fn(monitoring::track_request))
+        .layer(axum::middleware::from_fn(telemetry::trace_request))
+        .layer(axum::middleware::from_fn(request_id::propagate_request_id))
 }
 
 fn init_db(database_url: &str) {
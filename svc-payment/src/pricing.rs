@@ -0,0 +1,120 @@
+use std::{fs, path::Path};
+
+use chrono::NaiveDate;
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+use crate::exchange_rates::ExchangeRates;
+
+#[derive(Clone, Debug, Deserialize, Serialize, ToSchema)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum PricingRuleKind {
+    Percentage,
+    Fixed,
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct PricingRule {
+    pub name: String,
+    pub country: Option<String>,
+    pub city: Option<String>,
+    pub kind: PricingRuleKind,
+    pub value: f64,
+    pub currency: Option<String>,
+    #[serde(default)]
+    pub per_night: bool,
+}
+
+impl PricingRule {
+    fn matches(&self, country: &str, city: &str) -> bool {
+        let eq = |rule: &Option<String>, value: &str| {
+            rule.as_ref()
+                .is_none_or(|r| r.to_lowercase() == value.to_lowercase())
+        };
+        eq(&self.country, country) && eq(&self.city, city)
+    }
+}
+
+#[derive(Clone, Deserialize, Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct PriceItem {
+    pub name: String,
+    pub amount: i32,
+}
+
+#[derive(Deserialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct PricingQuoteRequest {
+    pub country: String,
+    pub city: String,
+    pub amount: i32,
+    pub nights: i32,
+    pub currency: String,
+}
+
+#[derive(Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct PricingQuoteResponse {
+    pub items: Vec<PriceItem>,
+    pub total: i32,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct PricingRules {
+    rules: Vec<PricingRule>,
+}
+
+impl PricingRules {
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, String> {
+        let path = path.as_ref();
+        let data = fs::read_to_string(path)
+            .map_err(|e| format!("Failed to read {}: {e}", path.display()))?;
+        let rules = serde_json::from_str::<Vec<PricingRule>>(&data)
+            .map_err(|e| format!("Failed to parse {}: {e}", path.display()))?;
+
+        Ok(Self::from_rules(rules))
+    }
+
+    pub fn from_rules(rules: Vec<PricingRule>) -> Self {
+        Self { rules }
+    }
+
+    // налоги и сборы для проживания; фиксированные суммы переводятся в валюту брони
+    pub fn quote(
+        &self,
+        rates: &ExchangeRates,
+        stay: &PricingQuoteRequest,
+        date: NaiveDate,
+    ) -> Result<Vec<PriceItem>, String> {
+        self.rules
+            .iter()
+            .filter(|rule| rule.matches(&stay.country, &stay.city))
+            .map(|rule| {
+                let value = match rule.kind {
+                    PricingRuleKind::Percentage => stay.amount as f64 * rule.value / 100.0,
+                    PricingRuleKind::Fixed => {
+                        let rate = match &rule.currency {
+                            Some(from) => {
+                                rates
+                                    .find(from, &stay.currency, date)
+                                    .ok_or_else(|| {
+                                        format!("No exchange rate from {from} to {}", stay.currency)
+                                    })?
+                                    .rate
+                            }
+                            None => 1.0,
+                        };
+                        let nights = if rule.per_night { stay.nights } else { 1 };
+                        rule.value * rate * nights as f64
+                    }
+                };
+
+                Ok(PriceItem {
+                    name: rule.name.clone(),
+                    amount: value.round() as i32,
+                })
+            })
+            .collect()
+    }
+}
@@ -0,0 +1,230 @@
+use chrono::{DateTime, NaiveDate};
+use printpdf::{BuiltinFont, Mm, PdfDocument};
+use uuid::Uuid;
+
+use crate::dto::{Payment, PaymentItem, PaymentStatus};
+
+pub struct ReceiptLine {
+    pub title: String,
+    pub amount: String,
+}
+
+pub struct Receipt {
+    pub invoice_number: String,
+    pub issued_at: DateTime<chrono::Local>,
+    pub payment_uid: Uuid,
+    pub reservation_uid: Option<Uuid>,
+    pub hotel_name: Option<String>,
+    pub start_date: Option<NaiveDate>,
+    pub end_date: Option<NaiveDate>,
+    pub lines: Vec<ReceiptLine>,
+}
+
+impl Receipt {
+    pub fn from_payment(payment: &Payment, items: &[PaymentItem]) -> Self {
+        let mut lines = Vec::new();
+        let taxes = items.iter().map(|item| item.amount as i64).sum::<i64>();
+
+        let nights = match (payment.start_date, payment.end_date) {
+            (Some(start), Some(end)) => Some((end - start).num_days()),
+            _ => None,
+        };
+        if let (Some(nights), Some(nightly_price)) = (nights, payment.nightly_price) {
+            let subtotal = nights * nightly_price as i64;
+            lines.push(ReceiptLine {
+                title: format!(
+                    "Accommodation: {nights} night(s) x {nightly_price} {}",
+                    payment.base_currency
+                ),
+                amount: format!("{subtotal} {}", payment.base_currency),
+            });
+
+            if payment.free_nights_discount > 0 {
+                lines.push(ReceiptLine {
+                    title: "Free night(s)".to_owned(),
+                    amount: format!(
+                        "-{} {}",
+                        payment.free_nights_discount, payment.base_currency
+                    ),
+                });
+            }
+
+            if let Some(discount) = payment.discount.filter(|d| *d > 0) {
+                lines.push(ReceiptLine {
+                    title: format!("Loyalty discount {discount}%"),
+                    amount: format!(
+                        "-{} {}",
+                        subtotal
+                            - (payment.base_price as i64 - taxes)
+                            - payment.promo_discount as i64
+                            - payment.points_discount as i64
+                            - payment.free_nights_discount as i64,
+                        payment.base_currency
+                    ),
+                });
+            }
+        }
+
+        if let Some(promo_code) = &payment.promo_code {
+            lines.push(ReceiptLine {
+                title: format!("Promo code {promo_code}"),
+                amount: format!("-{} {}", payment.promo_discount, payment.base_currency),
+            });
+        }
+
+        if payment.points_discount > 0 {
+            lines.push(ReceiptLine {
+                title: "Loyalty points".to_owned(),
+                amount: format!("-{} {}", payment.points_discount, payment.base_currency),
+            });
+        }
+
+        for item in items {
+            lines.push(ReceiptLine {
+                title: item.name.clone(),
+                amount: format!("{} {}", item.amount, payment.base_currency),
+            });
+        }
+
+        lines.push(ReceiptLine {
+            title: "Total".to_owned(),
+            amount: format!("{} {}", payment.base_price, payment.base_currency),
+        });
+
+        if payment.currency != payment.base_currency {
+            lines.push(ReceiptLine {
+                title: format!(
+                    "Exchange rate {}/{}",
+                    payment.base_currency, payment.currency
+                ),
+                amount: format!("{:.4}", payment.exchange_rate),
+            });
+            lines.push(ReceiptLine {
+                title: "Charged".to_owned(),
+                amount: format!("{} {}", payment.price, payment.currency),
+            });
+        }
+
+        if payment.status == PaymentStatus::Canceled.to_string() {
+            lines.push(ReceiptLine {
+                title: "Refund".to_owned(),
+                amount: format!("-{} {}", payment.price, payment.currency),
+            });
+        }
+
+        Self {
+            invoice_number: format!("INV-{:06}", payment.invoice_number),
+            issued_at: payment.created_at,
+            payment_uid: payment.payment_uid,
+            reservation_uid: payment.reservation_uid,
+            hotel_name: payment.hotel_name.clone(),
+            start_date: payment.start_date,
+            end_date: payment.end_date,
+            lines,
+        }
+    }
+
+    fn header(&self) -> Vec<(&'static str, String)> {
+        let mut header = vec![
+            ("Invoice", self.invoice_number.clone()),
+            (
+                "Issued",
+                self.issued_at.format("%Y-%m-%d %H:%M").to_string(),
+            ),
+            ("Payment", self.payment_uid.to_string()),
+        ];
+        if let Some(reservation_uid) = self.reservation_uid {
+            header.push(("Reservation", reservation_uid.to_string()));
+        }
+        if let Some(hotel_name) = &self.hotel_name {
+            header.push(("Hotel", hotel_name.clone()));
+        }
+        if let (Some(start), Some(end)) = (self.start_date, self.end_date) {
+            header.push(("Dates", format!("{start} - {end}")));
+        }
+        header
+    }
+
+    pub fn to_html(&self) -> String {
+        let header = self
+            .header()
+            .into_iter()
+            .map(|(k, v)| format!("<tr><th>{k}</th><td>{}</td></tr>", escape_html(&v)))
+            .collect::<String>();
+        let lines = self
+            .lines
+            .iter()
+            .map(|l| {
+                format!(
+                    "<tr><td>{}</td><td class=\"amount\">{}</td></tr>",
+                    escape_html(&l.title),
+                    escape_html(&l.amount)
+                )
+            })
+            .collect::<String>();
+
+        format!(
+            "<!DOCTYPE html>\n\
+             <html><head><meta charset=\"utf-8\"><title>{0}</title>\
+             <style>body{{font-family:sans-serif}}th{{text-align:left}}.amount{{text-align:right}}</style>\
+             </head><body><h1>Receipt {0}</h1>\
+             <table>{1}</table><hr><table>{2}</table></body></html>\n",
+            self.invoice_number, header, lines
+        )
+    }
+
+    pub fn to_pdf(&self) -> Result<Vec<u8>, String> {
+        let (doc, page, layer) = PdfDocument::new(
+            format!("Receipt {}", self.invoice_number),
+            Mm(210.0),
+            Mm(297.0),
+            "Receipt",
+        );
+        let font = doc
+            .add_builtin_font(BuiltinFont::Helvetica)
+            .map_err(|e| e.to_string())?;
+        let bold = doc
+            .add_builtin_font(BuiltinFont::HelveticaBold)
+            .map_err(|e| e.to_string())?;
+        let layer = doc.get_page(page).get_layer(layer);
+
+        let mut y = 270.0;
+        layer.use_text(
+            format!("Receipt {}", self.invoice_number),
+            18.0,
+            Mm(20.0),
+            Mm(y),
+            &bold,
+        );
+        y -= 14.0;
+
+        for (k, v) in self.header() {
+            layer.use_text(k, 11.0, Mm(20.0), Mm(y), &bold);
+            layer.use_text(latin1(&v), 11.0, Mm(60.0), Mm(y), &font);
+            y -= 7.0;
+        }
+        y -= 7.0;
+
+        for line in &self.lines {
+            layer.use_text(latin1(&line.title), 11.0, Mm(20.0), Mm(y), &font);
+            layer.use_text(latin1(&line.amount), 11.0, Mm(150.0), Mm(y), &font);
+            y -= 7.0;
+        }
+
+        doc.save_to_bytes().map_err(|e| e.to_string())
+    }
+}
+
+fn escape_html(s: &str) -> String {
+    s.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+// встроенные шрифты PDF не содержат кириллицы
+fn latin1(s: &str) -> String {
+    s.chars()
+        .map(|c| if (c as u32) < 0x100 { c } else { '?' })
+        .collect()
+}
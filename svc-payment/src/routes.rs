@@ -1,13 +1,29 @@
+use std::collections::BTreeMap;
+
 use axum::{
-    extract::{Path, State},
-    http::StatusCode,
+    extract::{Path, Query, State},
+    http::{header, HeaderMap, StatusCode},
     response::IntoResponse,
     Json,
 };
-use diesel::{prelude::*, result::Error as DieselError};
+use diesel::{
+    prelude::*,
+    result::{DatabaseErrorKind, Error as DieselError},
+};
 use uuid::Uuid;
 
-use crate::{dto::*, schema::payment, AppState};
+use crate::{
+    diesel_paginate::*,
+    dto::*,
+    exchange_rates::ExchangeRate,
+    health::{self, HealthReport},
+    logger::{self, LogLevels},
+    monitoring,
+    pricing::{PricingQuoteRequest, PricingQuoteResponse},
+    receipt::Receipt,
+    schema::{invoice_counter, payment, payment_item, promo_code, promo_code_redemption},
+    AppState, MAX_PAGE_SIZE,
+};
 
 #[utoipa::path(
     get,
@@ -20,6 +36,79 @@ pub async fn check_health() -> impl IntoResponse {
     StatusCode::OK
 }
 
+#[utoipa::path(
+    get,
+    path = "/manage/health/live",
+    responses(
+        (status = OK, body = HealthReport, description = "Процесс работает")
+    )
+)]
+pub async fn check_liveness() -> impl IntoResponse {
+    HealthReport::new(BTreeMap::new())
+}
+
+#[utoipa::path(
+    get,
+    path = "/manage/health/ready",
+    responses(
+        (status = OK, body = HealthReport, description = "Сервис готов принимать запросы"),
+        (
+            status = SERVICE_UNAVAILABLE,
+            body = HealthReport,
+            description = "Не пройдена одна из проверок: доступность базы данных и применённые миграции",
+        ),
+    )
+)]
+pub async fn check_readiness(State(state): State<AppState>) -> impl IntoResponse {
+    health::readiness(state.database_url).await
+}
+
+#[utoipa::path(
+    get,
+    path = "/manage/metrics",
+    responses(
+        (status = OK, body = String, content_type = "text/plain", description = "Метрики в формате Prometheus")
+    )
+)]
+pub async fn get_metrics() -> impl IntoResponse {
+    (
+        [(header::CONTENT_TYPE, "text/plain; version=0.0.4")],
+        monitoring::render(),
+    )
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/v1/admin/log-levels",
+    responses(
+        (status = OK, body = LogLevels, description = "Текущие уровни журнала")
+    )
+)]
+pub async fn get_log_levels() -> impl IntoResponse {
+    Json(logger::levels())
+}
+
+#[utoipa::path(
+    put,
+    path = "/api/v1/admin/log-levels",
+    request_body = LogLevels,
+    responses(
+        (status = OK, body = LogLevels, description = "Уровни журнала изменены"),
+        (status = BAD_REQUEST, description = "Неизвестный уровень журнала"),
+    )
+)]
+pub async fn put_log_levels(
+    Json(levels): Json<LogLevels>,
+) -> Result<impl IntoResponse, StatusCode> {
+    logger::set_levels(levels.clone()).map_err(|e| {
+        tracing::warn!("Rejected log levels: {e}");
+        StatusCode::BAD_REQUEST
+    })?;
+    tracing::info!("Log levels changed to {levels:?}");
+
+    Ok(Json(levels))
+}
+
 #[utoipa::path(
     get,
     path = "/api/v1/payment/{paymentUid}",
@@ -32,28 +121,88 @@ pub async fn check_health() -> impl IntoResponse {
         ),
     ),
     params(
-        ("paymentUid", Path, description = "Идентификатор оплаты")
+        ("paymentUid", Path, description = "Идентификатор оплаты"),
+        ("X-User-Name", Header, description = "Владелец оплаты"),
     ),
 )]
 pub async fn get_payment(
     State(state): State<AppState>,
     Path(uid): Path<Uuid>,
+    headers: HeaderMap,
 ) -> Result<impl IntoResponse, StatusCode> {
+    let username = headers
+        .get("X-User-Name")
+        .ok_or(StatusCode::BAD_REQUEST)?
+        .to_str()
+        .map_err(|_| StatusCode::BAD_REQUEST)?;
+
     let conn = &mut PgConnection::establish(state.database_url.as_str())
         .expect("Failed to establish connection to database");
 
-    let res = payment::table
-        .filter(payment::payment_uid.eq(uid))
-        .select(Payment::as_select())
-        .get_result::<Payment>(conn)
-        .map_err(|e| match e {
-            DieselError::NotFound => StatusCode::NOT_FOUND,
-            _ => StatusCode::INTERNAL_SERVER_ERROR,
-        })?;
+    let res = owned_payment(conn, uid, username).map_err(|e| match e {
+        DieselError::NotFound => StatusCode::NOT_FOUND,
+        _ => StatusCode::INTERNAL_SERVER_ERROR,
+    })?;
 
     Ok(Json(res))
 }
 
+#[utoipa::path(
+    get,
+    path = "/api/v1/payment/{paymentUid}/receipt",
+    responses(
+        (status = OK, description = "Квитанция об оплате", content_type = "text/html"),
+        (status = OK, description = "Квитанция об оплате", content_type = "application/pdf"),
+    ),
+    params(
+        ("paymentUid", Path, description = "Идентификатор оплаты"),
+        ("format", Query, description = "Формат квитанции: html или pdf"),
+        ("X-User-Name", Header, description = "Владелец оплаты"),
+    ),
+)]
+pub async fn get_receipt(
+    State(state): State<AppState>,
+    Path(uid): Path<Uuid>,
+    Query(query): Query<ReceiptQuery>,
+    headers: HeaderMap,
+) -> Result<impl IntoResponse, StatusCode> {
+    let username = headers
+        .get("X-User-Name")
+        .ok_or(StatusCode::BAD_REQUEST)?
+        .to_str()
+        .map_err(|_| StatusCode::BAD_REQUEST)?;
+
+    let conn = &mut PgConnection::establish(state.database_url.as_str())
+        .expect("Failed to establish connection to database");
+
+    let res = owned_payment(conn, uid, username).map_err(|e| match e {
+        DieselError::NotFound => StatusCode::NOT_FOUND,
+        _ => StatusCode::INTERNAL_SERVER_ERROR,
+    })?;
+
+    let items = payment_item::table
+        .filter(payment_item::payment_uid.eq(uid))
+        .order(payment_item::id)
+        .select(PaymentItem::as_select())
+        .load::<PaymentItem>(conn)
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+    let receipt = Receipt::from_payment(&res, &items);
+    match query.format.unwrap_or_default() {
+        ReceiptFormat::Html => Ok((
+            [(header::CONTENT_TYPE, "text/html; charset=utf-8")],
+            receipt.to_html().into_bytes(),
+        )),
+        ReceiptFormat::Pdf => {
+            let pdf = receipt.to_pdf().map_err(|e| {
+                tracing::error!("Failed to render receipt {}: {e}", receipt.invoice_number);
+                StatusCode::INTERNAL_SERVER_ERROR
+            })?;
+            Ok(([(header::CONTENT_TYPE, "application/pdf")], pdf))
+        }
+    }
+}
+
 #[utoipa::path(
     delete,
     path = "/api/v1/payment/{paymentUid}",
@@ -63,52 +212,639 @@ pub async fn get_payment(
             description = "Оплата отменена",
             content_type = "application/json",
         ),
+        (status = NOT_FOUND, description = "Оплата не найдена"),
     ),
     params(
-        ("paymentUid", Path, description = "Идентификатор оплаты")
+        ("paymentUid", Path, description = "Идентификатор оплаты"),
+        ("X-User-Name", Header, description = "Владелец оплаты"),
     ),
 )]
 pub async fn delete_payment(
     State(state): State<AppState>,
     Path(uid): Path<Uuid>,
+    headers: HeaderMap,
+) -> Result<impl IntoResponse, StatusCode> {
+    let username = headers
+        .get("X-User-Name")
+        .ok_or(StatusCode::BAD_REQUEST)?
+        .to_str()
+        .map_err(|_| StatusCode::BAD_REQUEST)?;
+
+    let conn = &mut PgConnection::establish(state.database_url.as_str())
+        .expect("Failed to establish connection to database");
+
+    owned_payment(conn, uid, username).map_err(|e| match e {
+        DieselError::NotFound => StatusCode::NOT_FOUND,
+        _ => StatusCode::INTERNAL_SERVER_ERROR,
+    })?;
+    if cancel_payment(conn, uid).map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)? > 0 {
+        metrics::counter!("payment_cancellations_total", "reason" => "user").increment(1);
+    }
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/v1/admin/payments/{paymentUid}/refund",
+    responses(
+        (status = NO_CONTENT, description = "Оплата возвращена"),
+        (status = NOT_FOUND, description = "Оплата не найдена"),
+    ),
+    params(
+        ("paymentUid", Path, description = "Идентификатор оплаты"),
+        ("X-User-Name", Header, description = "Имя сотрудника"),
+    ),
+)]
+pub async fn refund_payment(
+    State(state): State<AppState>,
+    Path(uid): Path<Uuid>,
+    headers: HeaderMap,
 ) -> Result<impl IntoResponse, StatusCode> {
+    let actor = headers
+        .get("X-User-Name")
+        .ok_or(StatusCode::BAD_REQUEST)?
+        .to_str()
+        .map_err(|_| StatusCode::BAD_REQUEST)?;
+
     let conn = &mut PgConnection::establish(state.database_url.as_str())
         .expect("Failed to establish connection to database");
 
-    diesel::update(payment::table)
-        .filter(payment::payment_uid.eq(uid))
-        .set(payment::status.eq(PaymentStatus::Canceled.to_string()))
-        .execute(conn)
-        .map_err(|e| match e {
-            DieselError::NotFound => StatusCode::NOT_FOUND,
-            _ => StatusCode::INTERNAL_SERVER_ERROR,
-        })?;
+    if cancel_payment(conn, uid).map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)? == 0 {
+        // повторный возврат уже отменённой оплаты ничего не меняет
+        let exists = diesel::select(diesel::dsl::exists(
+            payment::table.filter(payment::payment_uid.eq(uid)),
+        ))
+        .get_result::<bool>(conn)
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+        if !exists {
+            return Err(StatusCode::NOT_FOUND);
+        }
+        return Ok(StatusCode::NO_CONTENT);
+    }
+    tracing::info!("Payment {uid} refunded by {actor}");
+    metrics::counter!("payment_cancellations_total", "reason" => "refund").increment(1);
 
     Ok(StatusCode::NO_CONTENT)
 }
 
+// оплата без записанного владельца никому не выдаётся
+fn owned_payment(conn: &mut PgConnection, uid: Uuid, username: &str) -> QueryResult<Payment> {
+    payment::table
+        .filter(payment::payment_uid.eq(uid))
+        .filter(payment::username.eq(username))
+        .select(Payment::as_select())
+        .get_result::<Payment>(conn)
+}
+
+// вместе с оплатой отменяется применение промокода по той же брони;
+// возвращает число оплат, отменённых этим вызовом
+fn cancel_payment(conn: &mut PgConnection, uid: Uuid) -> QueryResult<usize> {
+    conn.transaction(|conn| {
+        let canceled = diesel::update(payment::table)
+            .filter(payment::payment_uid.eq(uid))
+            .filter(payment::status.ne(PaymentStatus::Canceled.to_string()))
+            .set(payment::status.eq(PaymentStatus::Canceled.to_string()))
+            .returning(payment::reservation_uid)
+            .get_results::<Option<Uuid>>(conn)?;
+
+        for reservation_uid in canceled.iter().flatten() {
+            let redemptions = promo_code_redemption::table
+                .filter(promo_code_redemption::reservation_uid.eq(reservation_uid))
+                .select(PromoCodeRedemption::as_select())
+                .for_update()
+                .load::<PromoCodeRedemption>(conn)?;
+            for redemption in &redemptions {
+                release_redemption(conn, redemption)?;
+            }
+        }
+
+        Ok(canceled.len())
+    })
+}
+
 #[utoipa::path(
     post,
     path = "/api/v1/payment",
     responses(
-        (status = CREATED, body = Payment, description = "Success")
+        (status = CREATED, body = Payment, description = "Success"),
+        (status = BAD_REQUEST, description = "Не указан пользователь, совершивший оплату"),
+    ),
+    params(
+        ("X-User-Name", Header, description = "Имя пользователя, совершившего оплату")
     ),
 )]
 pub async fn post_payment(
     State(state): State<AppState>,
+    headers: HeaderMap,
     Json(payment): Json<PaymentRequest>,
 ) -> Result<impl IntoResponse, StatusCode> {
+    let username = headers
+        .get("X-User-Name")
+        .ok_or(StatusCode::BAD_REQUEST)?
+        .to_str()
+        .map_err(|_| StatusCode::BAD_REQUEST)?;
+
     let conn = &mut PgConnection::establish(state.database_url.as_str())
         .expect("Failed to establish connection to database");
 
-    let payment = Payment::from(payment);
-    let created = diesel::insert_into(payment::table)
-        .values(&payment)
-        .returning(Payment::as_returning())
-        .get_result(conn)
+    let (mut payment, items) = payment.into_payment(username.to_owned());
+    let created = conn
+        .transaction::<_, DieselError, _>(|conn| {
+            // строка счётчика блокируется до конца транзакции, поэтому номера идут без пропусков
+            payment.invoice_number = diesel::update(invoice_counter::table)
+                .set(invoice_counter::last_number.eq(invoice_counter::last_number + 1))
+                .returning(invoice_counter::last_number)
+                .get_result(conn)?;
+            let created = diesel::insert_into(payment::table)
+                .values(&payment)
+                .returning(Payment::as_returning())
+                .get_result(conn)?;
+            diesel::insert_into(payment_item::table)
+                .values(&items)
+                .execute(conn)?;
+            Ok(created)
+        })
         .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
 
-    log::debug!("Created payment: {}", created.payment_uid);
+    tracing::debug!("Created payment: {}", created.payment_uid);
+    metrics::counter!("payments_total", "currency" => created.currency.clone()).increment(1);
+    metrics::counter!("payment_revenue_total", "currency" => created.currency.clone())
+        .increment(created.price.max(0) as u64);
 
     Ok((StatusCode::CREATED, Json(created)))
 }
+
+#[utoipa::path(
+    get,
+    path = "/api/v1/payments",
+    responses(
+        (
+            status = OK,
+            description = "История оплат пользователя",
+            body = PaymentList,
+            content_type = "application/json",
+        ),
+    ),
+    params(
+        ("status", Query, description = "Фильтр по статусу оплаты"),
+        ("page", Query, description = "Номер страницы"),
+        ("size", Query, description = "Количество элементов страницы"),
+        ("X-User-Name", Header, description = "Владелец оплат"),
+    ),
+)]
+pub async fn get_payments(
+    State(state): State<AppState>,
+    Query(query): Query<PaymentListQuery>,
+    headers: HeaderMap,
+) -> Result<impl IntoResponse, StatusCode> {
+    let username = headers
+        .get("X-User-Name")
+        .ok_or(StatusCode::BAD_REQUEST)?
+        .to_str()
+        .map_err(|_| StatusCode::BAD_REQUEST)?;
+
+    let page = query.page.unwrap_or(1).max(1);
+    let size = query.size.unwrap_or(10).clamp(1, MAX_PAGE_SIZE);
+
+    let conn = &mut PgConnection::establish(state.database_url.as_str())
+        .expect("Failed to establish connection to database");
+
+    let mut select = payment::table
+        .filter(payment::username.eq(username))
+        .order(payment::id.desc())
+        .select(Payment::as_select())
+        .into_boxed();
+    if let Some(status) = query.status {
+        select = select.filter(payment::status.eq(status.to_string()));
+    }
+
+    let (items, _, total) = select
+        .paginate(page as i64)
+        .per_page(size as i64)
+        .load_and_count_pages::<Payment>(conn)
+        .map_err(|e| {
+            tracing::error!("Failed to load payments: {e}");
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?;
+
+    Ok(Json(PaymentList {
+        page,
+        page_size: size,
+        total_elements: total as usize,
+        items,
+    }))
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/v1/pricing/quote",
+    responses(
+        (
+            status = OK,
+            description = "Налоги и сборы за проживание",
+            body = PricingQuoteResponse,
+            content_type = "application/json",
+        ),
+        (status = UNPROCESSABLE_ENTITY, description = "Не найден курс для пересчёта сбора"),
+    ),
+)]
+pub async fn post_pricing_quote(
+    State(state): State<AppState>,
+    Json(req): Json<PricingQuoteRequest>,
+) -> Result<impl IntoResponse, StatusCode> {
+    let items = state
+        .pricing_rules
+        .quote(
+            &state.exchange_rates,
+            &req,
+            chrono::Local::now().date_naive(),
+        )
+        .map_err(|e| {
+            tracing::warn!("Failed to calculate taxes: {e}");
+            StatusCode::UNPROCESSABLE_ENTITY
+        })?;
+    let total = items.iter().map(|item| item.amount).sum();
+
+    Ok(Json(PricingQuoteResponse { items, total }))
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/v1/exchange-rate",
+    responses(
+        (
+            status = OK,
+            description = "Курс обмена валют, действующий на указанную дату",
+            body = ExchangeRate,
+            content_type = "application/json",
+        ),
+        (status = NOT_FOUND, description = "Курс для пары валют не найден"),
+    ),
+    params(
+        ("from", Query, description = "Исходная валюта"),
+        ("to", Query, description = "Целевая валюта"),
+        ("date", Query, description = "Дата, на которую нужен курс (по умолчанию сегодня)"),
+    ),
+)]
+pub async fn get_exchange_rate(
+    State(state): State<AppState>,
+    Query(query): Query<ExchangeRateQuery>,
+) -> Result<impl IntoResponse, StatusCode> {
+    let date = query
+        .date
+        .unwrap_or_else(|| chrono::Local::now().date_naive());
+
+    let rate = state
+        .exchange_rates
+        .find(&query.from, &query.to, date)
+        .ok_or(StatusCode::NOT_FOUND)?;
+
+    Ok(Json(rate))
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/v1/admin/promo-codes",
+    responses(
+        (status = CREATED, body = PromoCode, description = "Промокод создан"),
+        (status = CONFLICT, description = "Промокод с таким кодом уже существует"),
+    ),
+)]
+pub async fn post_promo_code(
+    State(state): State<AppState>,
+    Json(req): Json<PromoCodeRequest>,
+) -> Result<impl IntoResponse, StatusCode> {
+    // код передаётся в пути запроса, поэтому допускаются только буквы, цифры, '-' и '_'
+    let code_is_valid = !req.code.is_empty()
+        && req
+            .code
+            .bytes()
+            .all(|b| b.is_ascii_alphanumeric() || b == b'-' || b == b'_');
+    if !code_is_valid || req.value <= 0 {
+        return Err(StatusCode::BAD_REQUEST);
+    }
+    if matches!(req.kind, PromoCodeKind::Fixed) && req.currency.is_none() {
+        return Err(StatusCode::BAD_REQUEST);
+    }
+
+    let conn = &mut PgConnection::establish(state.database_url.as_str())
+        .expect("Failed to establish connection to database");
+
+    let created = diesel::insert_into(promo_code::table)
+        .values(&NewPromoCode::from(req))
+        .returning(PromoCode::as_returning())
+        .get_result(conn)
+        .map_err(|e| match e {
+            DieselError::DatabaseError(DatabaseErrorKind::UniqueViolation, _) => {
+                StatusCode::CONFLICT
+            }
+            _ => StatusCode::INTERNAL_SERVER_ERROR,
+        })?;
+
+    tracing::info!("Created promo code {}", created.code);
+
+    Ok((StatusCode::CREATED, Json(created)))
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/v1/admin/promo-codes",
+    responses(
+        (
+            status = OK,
+            description = "Список промокодов",
+            body = Vec<PromoCode>,
+            content_type = "application/json",
+        ),
+    ),
+)]
+pub async fn get_promo_codes(
+    State(state): State<AppState>,
+) -> Result<impl IntoResponse, StatusCode> {
+    let conn = &mut PgConnection::establish(state.database_url.as_str())
+        .expect("Failed to establish connection to database");
+
+    let res = promo_code::table
+        .order(promo_code::created_at.desc())
+        .select(PromoCode::as_select())
+        .load::<PromoCode>(conn)
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+    Ok(Json(res))
+}
+
+enum RedeemError {
+    Db(DieselError),
+    Rejected(StatusCode),
+}
+
+impl From<DieselError> for RedeemError {
+    fn from(value: DieselError) -> Self {
+        Self::Db(value)
+    }
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/v1/promo-codes/{code}/redeem",
+    responses(
+        (
+            status = OK,
+            description = "Промокод применён и зарезервирован под бронирование",
+            body = PromoCodeRedeemResponse,
+            content_type = "application/json",
+        ),
+        (status = NOT_FOUND, description = "Промокод не найден"),
+        (status = UNPROCESSABLE_ENTITY, description = "Промокод не может быть применён"),
+    ),
+    params(
+        ("code", Path, description = "Промокод"),
+        ("X-User-Name", Header, description = "Имя пользователя"),
+    ),
+)]
+pub async fn redeem_promo_code(
+    State(state): State<AppState>,
+    Path(code): Path<String>,
+    headers: HeaderMap,
+    Json(req): Json<PromoCodeRedeemRequest>,
+) -> Result<impl IntoResponse, StatusCode> {
+    let username = headers
+        .get("X-User-Name")
+        .ok_or(StatusCode::BAD_REQUEST)?
+        .to_str()
+        .map_err(|_| StatusCode::BAD_REQUEST)?;
+
+    let conn = &mut PgConnection::establish(state.database_url.as_str())
+        .expect("Failed to establish connection to database");
+
+    let res = conn.transaction::<_, RedeemError, _>(|conn| {
+        let promo = promo_code::table
+            .filter(promo_code::code.eq(code.to_uppercase()))
+            .select(PromoCode::as_select())
+            .for_update()
+            .get_result::<PromoCode>(conn)
+            .optional()?
+            .ok_or(RedeemError::Rejected(StatusCode::NOT_FOUND))?;
+
+        if !promo.is_active(chrono::Local::now()) {
+            return Err(RedeemError::Rejected(StatusCode::UNPROCESSABLE_ENTITY));
+        }
+        if promo
+            .currency
+            .as_ref()
+            .is_some_and(|c| !c.eq_ignore_ascii_case(&req.currency))
+        {
+            return Err(RedeemError::Rejected(StatusCode::UNPROCESSABLE_ENTITY));
+        }
+        if let Some(max_uses_per_user) = promo.max_uses_per_user {
+            let used = promo_code_redemption::table
+                .filter(promo_code_redemption::promo_code_id.eq(promo.id))
+                .filter(promo_code_redemption::username.eq(username))
+                .filter(
+                    promo_code_redemption::status
+                        .ne(PromoCodeRedemptionStatus::Released.to_string()),
+                )
+                .count()
+                .get_result::<i64>(conn)?;
+            if used >= max_uses_per_user as i64 {
+                return Err(RedeemError::Rejected(StatusCode::UNPROCESSABLE_ENTITY));
+            }
+        }
+
+        // нестекируемый промокод заменяет скидку по программе лояльности
+        let loyalty_discount = if promo.stackable {
+            req.amount * req.loyalty_discount / 100
+        } else {
+            0
+        };
+        let promo_discount = promo.discount_for(req.amount - loyalty_discount);
+
+        diesel::update(promo_code::table)
+            .filter(promo_code::id.eq(promo.id))
+            .set(promo_code::used_count.eq(promo_code::used_count + 1))
+            .execute(conn)?;
+        // применение резервируется до подтверждения бронирования
+        let redemption_uid = Uuid::new_v4();
+        diesel::insert_into(promo_code_redemption::table)
+            .values(&NewPromoCodeRedemption {
+                promo_code_id: promo.id,
+                username: username.to_owned(),
+                redemption_uid,
+                reservation_uid: req.reservation_uid,
+                discount: promo_discount,
+                status: PromoCodeRedemptionStatus::Held.to_string(),
+            })
+            .execute(conn)?;
+
+        Ok(PromoCodeRedeemResponse {
+            redemption_uid,
+            code: promo.code,
+            stackable: promo.stackable,
+            loyalty_discount,
+            promo_discount,
+            total: req.amount - loyalty_discount - promo_discount,
+        })
+    });
+
+    match res {
+        Ok(res) => {
+            tracing::debug!("User {username} redeemed promo code {}", res.code);
+            Ok(Json(res))
+        }
+        Err(RedeemError::Rejected(status)) => Err(status),
+        Err(RedeemError::Db(e)) => {
+            tracing::error!("Failed to redeem promo code: {e}");
+            Err(StatusCode::INTERNAL_SERVER_ERROR)
+        }
+    }
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/v1/promo-redemptions/{redemptionUid}/capture",
+    responses(
+        (
+            status = OK,
+            description = "Применение промокода подтверждено",
+            body = PromoCodeRedemptionResponse,
+            content_type = "application/json",
+        ),
+        (status = NOT_FOUND, description = "Применение промокода не найдено"),
+        (status = CONFLICT, description = "Применение промокода уже отменено"),
+    ),
+    params(
+        ("redemptionUid" = Uuid, Path, description = "UUID применения промокода"),
+        ("X-User-Name", Header, description = "Имя пользователя"),
+    ),
+)]
+pub async fn capture_promo_redemption(
+    State(state): State<AppState>,
+    Path(redemption_uid): Path<Uuid>,
+    headers: HeaderMap,
+) -> Result<impl IntoResponse, StatusCode> {
+    let username = headers
+        .get("X-User-Name")
+        .ok_or(StatusCode::BAD_REQUEST)?
+        .to_str()
+        .map_err(|_| StatusCode::BAD_REQUEST)?;
+
+    let conn = &mut PgConnection::establish(state.database_url.as_str())
+        .expect("Failed to establish connection to database");
+
+    let res = conn.transaction::<_, RedeemError, _>(|conn| {
+        let redemption = find_promo_redemption(conn, redemption_uid, username)?;
+
+        match redemption.status.parse() {
+            Ok(PromoCodeRedemptionStatus::Held) => {
+                diesel::update(promo_code_redemption::table)
+                    .filter(promo_code_redemption::id.eq(redemption.id))
+                    .set(
+                        promo_code_redemption::status
+                            .eq(PromoCodeRedemptionStatus::Captured.to_string()),
+                    )
+                    .execute(conn)?;
+                Ok(redemption)
+            }
+            Ok(PromoCodeRedemptionStatus::Captured) => Ok(redemption),
+            _ => Err(RedeemError::Rejected(StatusCode::CONFLICT)),
+        }
+    });
+    let redemption = redeem_result(res)?;
+
+    Ok(Json(PromoCodeRedemptionResponse {
+        redemption_uid: redemption.redemption_uid,
+        promo_discount: redemption.discount,
+        status: PromoCodeRedemptionStatus::Captured,
+    }))
+}
+
+#[utoipa::path(
+    delete,
+    path = "/api/v1/promo-redemptions/{redemptionUid}",
+    responses(
+        (
+            status = OK,
+            description = "Применение промокода отменено, использование возвращено",
+            body = PromoCodeRedemptionResponse,
+            content_type = "application/json",
+        ),
+        (status = NOT_FOUND, description = "Применение промокода не найдено"),
+    ),
+    params(
+        ("redemptionUid" = Uuid, Path, description = "UUID применения промокода"),
+        ("X-User-Name", Header, description = "Имя пользователя"),
+    ),
+)]
+pub async fn release_promo_redemption(
+    State(state): State<AppState>,
+    Path(redemption_uid): Path<Uuid>,
+    headers: HeaderMap,
+) -> Result<impl IntoResponse, StatusCode> {
+    let username = headers
+        .get("X-User-Name")
+        .ok_or(StatusCode::BAD_REQUEST)?
+        .to_str()
+        .map_err(|_| StatusCode::BAD_REQUEST)?;
+
+    let conn = &mut PgConnection::establish(state.database_url.as_str())
+        .expect("Failed to establish connection to database");
+
+    let res = conn.transaction::<_, RedeemError, _>(|conn| {
+        let redemption = find_promo_redemption(conn, redemption_uid, username)?;
+        release_redemption(conn, &redemption)?;
+        Ok(redemption)
+    });
+    let redemption = redeem_result(res)?;
+
+    Ok(Json(PromoCodeRedemptionResponse {
+        redemption_uid: redemption.redemption_uid,
+        promo_discount: redemption.discount,
+        status: PromoCodeRedemptionStatus::Released,
+    }))
+}
+
+fn redeem_result<T>(res: Result<T, RedeemError>) -> Result<T, StatusCode> {
+    res.map_err(|e| match e {
+        RedeemError::Rejected(status) => status,
+        RedeemError::Db(e) => {
+            tracing::error!("Failed to update promo code redemption: {e}");
+            StatusCode::INTERNAL_SERVER_ERROR
+        }
+    })
+}
+
+fn find_promo_redemption(
+    conn: &mut PgConnection,
+    redemption_uid: Uuid,
+    username: &str,
+) -> Result<PromoCodeRedemption, RedeemError> {
+    promo_code_redemption::table
+        .filter(promo_code_redemption::redemption_uid.eq(redemption_uid))
+        .filter(promo_code_redemption::username.eq(username))
+        .select(PromoCodeRedemption::as_select())
+        .for_update()
+        .get_result::<PromoCodeRedemption>(conn)
+        .optional()?
+        .ok_or(RedeemError::Rejected(StatusCode::NOT_FOUND))
+}
+
+// отменённое применение снова доступно в пределах лимитов промокода
+fn release_redemption(
+    conn: &mut PgConnection,
+    redemption: &PromoCodeRedemption,
+) -> QueryResult<()> {
+    if redemption.status == PromoCodeRedemptionStatus::Released.to_string() {
+        return Ok(());
+    }
+    diesel::update(promo_code_redemption::table)
+        .filter(promo_code_redemption::id.eq(redemption.id))
+        .set(promo_code_redemption::status.eq(PromoCodeRedemptionStatus::Released.to_string()))
+        .execute(conn)?;
+    diesel::update(promo_code::table)
+        .filter(promo_code::id.eq(redemption.promo_code_id))
+        .filter(promo_code::used_count.gt(0))
+        .set(promo_code::used_count.eq(promo_code::used_count - 1))
+        .execute(conn)?;
+    Ok(())
+}
@@ -1,5 +1,12 @@
 // @generated automatically by Diesel CLI.
 
+diesel::table! {
+    invoice_counter (id) {
+        id -> Int4,
+        last_number -> Int8,
+    }
+}
+
 diesel::table! {
     payment (id) {
         id -> Int4,
@@ -7,5 +14,82 @@ diesel::table! {
         #[max_length = 20]
         status -> Varchar,
         price -> Int4,
+        #[max_length = 3]
+        currency -> Varchar,
+        base_price -> Int4,
+        #[max_length = 3]
+        base_currency -> Varchar,
+        exchange_rate -> Float8,
+        #[max_length = 80]
+        username -> Nullable<Varchar>,
+        reservation_uid -> Nullable<Uuid>,
+        invoice_number -> Int8,
+        created_at -> Timestamptz,
+        #[max_length = 255]
+        hotel_name -> Nullable<Varchar>,
+        start_date -> Nullable<Date>,
+        end_date -> Nullable<Date>,
+        nightly_price -> Nullable<Int4>,
+        discount -> Nullable<Int4>,
+        #[max_length = 40]
+        promo_code -> Nullable<Varchar>,
+        promo_discount -> Int4,
+        points_discount -> Int4,
+        free_nights_discount -> Int4,
+    }
+}
+
+diesel::table! {
+    promo_code (id) {
+        id -> Int4,
+        #[max_length = 40]
+        code -> Varchar,
+        #[max_length = 20]
+        kind -> Varchar,
+        value -> Int4,
+        #[max_length = 3]
+        currency -> Nullable<Varchar>,
+        valid_from -> Nullable<Timestamptz>,
+        valid_until -> Nullable<Timestamptz>,
+        max_uses -> Nullable<Int4>,
+        max_uses_per_user -> Nullable<Int4>,
+        used_count -> Int4,
+        stackable -> Bool,
+        created_at -> Timestamptz,
+    }
+}
+
+diesel::table! {
+    promo_code_redemption (id) {
+        id -> Int4,
+        promo_code_id -> Int4,
+        #[max_length = 80]
+        username -> Varchar,
+        reservation_uid -> Nullable<Uuid>,
+        discount -> Int4,
+        created_at -> Timestamptz,
+        redemption_uid -> Uuid,
+        #[max_length = 20]
+        status -> Varchar,
     }
 }
+
+diesel::table! {
+    payment_item (id) {
+        id -> Int4,
+        payment_uid -> Uuid,
+        #[max_length = 255]
+        name -> Varchar,
+        amount -> Int4,
+    }
+}
+
+diesel::joinable!(promo_code_redemption -> promo_code (promo_code_id));
+
+diesel::allow_tables_to_appear_in_same_query!(
+    invoice_counter,
+    payment,
+    payment_item,
+    promo_code,
+    promo_code_redemption,
+);
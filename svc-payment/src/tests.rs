@@ -1,2 +1,140 @@
+use chrono::NaiveDate;
+
+use crate::{
+    exchange_rates::{ExchangeRate, ExchangeRates},
+    pricing::{PricingQuoteRequest, PricingRule, PricingRuleKind, PricingRules},
+};
+
 #[test]
 fn hello_world() {}
+
+fn date(y: i32, m: u32, d: u32) -> NaiveDate {
+    NaiveDate::from_ymd_opt(y, m, d).unwrap()
+}
+
+fn rates() -> ExchangeRates {
+    let rate = |from: &str, to: &str, rate: f64, effective_from: NaiveDate| ExchangeRate {
+        from: from.to_owned(),
+        to: to.to_owned(),
+        rate,
+        effective_from,
+    };
+
+    ExchangeRates::from_rates(vec![
+        rate("usd", "rub", 95.0, date(2025, 3, 1)),
+        rate("USD", "RUB", 90.0, date(2025, 1, 1)),
+        rate("EUR", "RUB", 0.0, date(2025, 1, 1)),
+    ])
+}
+
+#[test]
+fn exchange_rate_same_currency() {
+    let rate = rates().find("rub", "RUB", date(2020, 1, 1)).unwrap();
+
+    assert_eq!(rate.rate, 1.0);
+    assert_eq!(rate.from, "RUB");
+    assert_eq!(rate.to, "RUB");
+}
+
+#[test]
+fn exchange_rate_effective_on_date() {
+    let rates = rates();
+
+    assert_eq!(
+        rates.find("USD", "RUB", date(2025, 2, 15)).unwrap().rate,
+        90.0
+    );
+    // курс действует с даты начала включительно
+    let rate = rates.find("USD", "RUB", date(2025, 3, 1)).unwrap();
+    assert_eq!(rate.rate, 95.0);
+    assert_eq!(rate.effective_from, date(2025, 3, 1));
+    assert!(rates.find("USD", "RUB", date(2024, 12, 31)).is_none());
+}
+
+#[test]
+fn exchange_rate_inverse_pair() {
+    let rates = rates();
+
+    let rate = rates.find("RUB", "USD", date(2025, 2, 1)).unwrap();
+    assert!((rate.rate - 1.0 / 90.0).abs() < 1e-12);
+    assert_eq!(rate.from, "RUB");
+    assert_eq!(rate.to, "USD");
+    assert_eq!(rate.effective_from, date(2025, 1, 1));
+
+    // нулевой курс не обращается
+    assert!(rates.find("RUB", "EUR", date(2025, 2, 1)).is_none());
+    assert!(rates.find("RUB", "GBP", date(2025, 2, 1)).is_none());
+}
+
+fn rule(name: &str, city: Option<&str>, kind: PricingRuleKind, value: f64) -> PricingRule {
+    PricingRule {
+        name: name.to_owned(),
+        country: Some("Россия".to_owned()),
+        city: city.map(str::to_owned),
+        kind,
+        value,
+        currency: None,
+        per_night: false,
+    }
+}
+
+fn stay(city: &str, currency: &str) -> PricingQuoteRequest {
+    PricingQuoteRequest {
+        country: "россия".to_owned(),
+        city: city.to_owned(),
+        amount: 10_000,
+        nights: 3,
+        currency: currency.to_owned(),
+    }
+}
+
+#[test]
+fn pricing_quote_applies_matching_rules() {
+    let rules = PricingRules::from_rules(vec![
+        rule("НДС", None, PricingRuleKind::Percentage, 20.0),
+        rule(
+            "Курортный сбор",
+            Some("Сочи"),
+            PricingRuleKind::Fixed,
+            100.0,
+        ),
+        PricingRule {
+            per_night: true,
+            ..rule(
+                "Туристический налог",
+                Some("Москва"),
+                PricingRuleKind::Fixed,
+                50.0,
+            )
+        },
+    ]);
+
+    let items = rules
+        .quote(&rates(), &stay("МОСКВА", "RUB"), date(2025, 2, 1))
+        .unwrap();
+
+    let items = items
+        .iter()
+        .map(|item| (item.name.as_str(), item.amount))
+        .collect::<Vec<_>>();
+    assert_eq!(items, [("НДС", 2000), ("Туристический налог", 150)]);
+}
+
+#[test]
+fn pricing_quote_converts_fixed_amounts() {
+    let rules = PricingRules::from_rules(vec![PricingRule {
+        currency: Some("USD".to_owned()),
+        per_night: true,
+        ..rule("Сбор", None, PricingRuleKind::Fixed, 2.0)
+    }]);
+
+    let items = rules
+        .quote(&rates(), &stay("Москва", "RUB"), date(2025, 3, 10))
+        .unwrap();
+    assert_eq!(items[0].amount, 570);
+
+    let err = rules
+        .quote(&rates(), &stay("Москва", "GBP"), date(2025, 3, 10))
+        .err();
+    assert_eq!(err.as_deref(), Some("No exchange rate from USD to GBP"));
+}
@@ -0,0 +1,2 @@
+ALTER TABLE hotels
+    ADD COLUMN currency VARCHAR(3) NOT NULL DEFAULT 'RUB';
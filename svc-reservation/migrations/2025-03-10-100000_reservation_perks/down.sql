@@ -0,0 +1,4 @@
+ALTER TABLE reservation
+    DROP COLUMN free_nights,
+    DROP COLUMN late_checkout,
+    DROP COLUMN cancellation_extension_hours;
@@ -0,0 +1,4 @@
+ALTER TABLE reservation
+    ADD COLUMN cancellation_extension_hours INT     NOT NULL DEFAULT 0,
+    ADD COLUMN late_checkout                BOOLEAN NOT NULL DEFAULT FALSE,
+    ADD COLUMN free_nights                  INT     NOT NULL DEFAULT 0;
@@ -1,7 +1,7 @@
 use diesel::prelude::*;
 use uuid::Uuid;
 
-#[derive(Queryable, Selectable)]
+#[derive(Queryable, Selectable, Insertable, AsChangeset)]
 #[diesel(table_name = crate::schema::hotels)]
 #[diesel(check_for_backend(diesel::pg::Pg))]
 pub struct Hotel {
@@ -12,6 +12,7 @@ pub struct Hotel {
     pub address: String,
     pub stars: Option<i32>,
     pub price: i32,
+    pub currency: String,
 }
 
 #[derive(Queryable, Selectable, Insertable)]
@@ -25,4 +26,7 @@ pub struct Reservation {
     pub status: String,
     pub start_date: Option<chrono::DateTime<chrono::Local>>,
     pub end_date: Option<chrono::DateTime<chrono::Local>>,
+    pub cancellation_extension_hours: i32,
+    pub late_checkout: bool,
+    pub free_nights: i32,
 }
@@ -14,13 +14,18 @@ impl<T> Paginate for T {
             query: self,
             per_page: DEFAULT_PER_PAGE,
             page,
-            offset: (page - 1) * DEFAULT_PER_PAGE,
+            offset: offset(page, DEFAULT_PER_PAGE),
         }
     }
 }
 
 const DEFAULT_PER_PAGE: i64 = 10;
 
+// большие номера страниц дают пустую выборку вместо переполнения
+fn offset(page: i64, per_page: i64) -> i64 {
+    page.saturating_sub(1).max(0).saturating_mul(per_page)
+}
+
 #[derive(Debug, Clone, Copy, QueryId)]
 pub struct Paginated<T> {
     query: T,
@@ -33,12 +38,15 @@ impl<T> Paginated<T> {
     pub fn per_page(self, per_page: i64) -> Self {
         Paginated {
             per_page,
-            offset: (self.page - 1) * per_page,
+            offset: offset(self.page, per_page),
             ..self
         }
     }
 
-    pub fn load_and_count_pages<'a, U>(self, conn: &mut PgConnection) -> QueryResult<(Vec<U>, i64)>
+    pub fn load_and_count_pages<'a, U>(
+        self,
+        conn: &mut PgConnection,
+    ) -> QueryResult<(Vec<U>, i64, i64)>
     where
         Self: LoadQuery<'a, PgConnection, (U, i64)>,
     {
@@ -47,7 +55,7 @@ impl<T> Paginated<T> {
         let total = results.first().map(|x| x.1).unwrap_or(0);
         let records = results.into_iter().map(|x| x.0).collect();
         let total_pages = (total as f64 / per_page as f64).ceil() as i64;
-        Ok((records, total_pages))
+        Ok((records, total_pages, total))
     }
 }
 
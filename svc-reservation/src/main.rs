@@ -2,6 +2,7 @@ use std::env;
 
 use diesel::prelude::*;
 use diesel_migrations::{embed_migrations, EmbeddedMigrations, MigrationHarness};
+use signature::SignatureVerifier;
 use tokio::net::TcpListener;
 use utoipa::OpenApi;
 use utoipa_axum::{router::OpenApiRouter, routes};
@@ -9,11 +10,15 @@ use utoipa_swagger_ui::SwaggerUi;
 
 mod db_dto;
 mod diesel_paginate;
+mod health;
 mod logger;
+mod monitoring;
 mod request_dto;
+mod request_id;
 mod response_dto;
 mod routes;
 mod schema;
+mod telemetry;
 
 #[cfg(test)]
 mod tests;
@@ -22,12 +27,19 @@ mod tests;
 #[openapi(
     paths(
         routes::check_health,
+        routes::check_liveness,
+        routes::check_readiness,
+        routes::get_metrics,
         routes::get_hotels,
         routes::get_hotel,
+        routes::post_hotel,
+        routes::put_hotel,
         routes::get_reservations,
         routes::post_reservation,
         routes::get_reservation,
         routes::delete_reservation,
+        routes::get_log_levels,
+        routes::put_log_levels
     ),
     components(schemas(
         response_dto::Hotel,
@@ -36,8 +48,14 @@ mod tests;
         response_dto::Reservation,
         response_dto::ReservationStatus,
         response_dto::ReservationWithHotel,
+        response_dto::ReservationPerks,
         request_dto::ReservationPath,
         request_dto::ReservationRequest,
+        request_dto::HotelRequest,
+        logger::LogLevels,
+        health::HealthStatus,
+        health::DependencyHealth,
+        health::HealthReport
     ))
 )]
 struct ApiDoc;
@@ -55,12 +73,14 @@ async fn main() {
     let database_url =
         env::var("DATABASE_URL").expect("DATABASE_URL environment variable was not specified");
 
-    let _logger_handler = logger::init();
-    log::debug!("Logger initialized. Hello, world!");
+    logger::init("reservation");
+    let _tracer_provider = telemetry::init("reservation");
+    monitoring::init();
+    tracing::debug!("Logger initialized. Hello, world!");
 
     let app = app(database_url).await;
 
-    log::info!("Listening on {}", SERVICE_ENDPOINT);
+    tracing::info!("Listening on {}", SERVICE_ENDPOINT);
     let listener = TcpListener::bind(SERVICE_ENDPOINT).await.unwrap();
     axum::serve(listener, app.into_make_service())
         .await
@@ -73,14 +93,27 @@ async fn app(database_url: String) -> axum::Router {
     let swagger = SwaggerUi::new("/swagger-ui").url("/openapi.json", ApiDoc::openapi());
     let state = AppState { database_url };
     let app = OpenApiRouter::with_openapi(ApiDoc::openapi())
-        .routes(routes!(routes::check_health))
-        .routes(routes!(routes::get_hotels))
-        .routes(routes!(routes::get_hotel))
+        .routes(routes!(routes::get_hotels, routes::post_hotel))
+        .routes(routes!(routes::get_hotel, routes::put_hotel))
         .routes(routes!(routes::post_reservation, routes::get_reservations))
         .routes(routes!(routes::get_reservation, routes::delete_reservation))
+        .routes(routes!(routes::get_log_levels, routes::put_log_levels))
+        .route_layer(axum::middleware::from_fn_with_state(
+            SignatureVerifier::from_env(),
+            signature::verify_signature,
+        ))
+        // проверки работоспособности не подписываются
+        .routes(routes!(routes::check_health))
+        .routes(routes!(routes::check_liveness))
+        .routes(routes!(routes::check_readiness))
+        .routes(routes!(routes::get_metrics))
         .with_state(state);
 
-    axum::Router::from(app).merge(swagger)
+    axum::Router::from(app)
+        .merge(swagger)
+        .layer(axum::middleware::from_fn(monitoring::track_request))
+        .layer(axum::middleware::from_fn(telemetry::trace_request))
+        .layer(axum::middleware::from_fn(request_id::propagate_request_id))
 }
 
 fn init_db(database_url: &str) {
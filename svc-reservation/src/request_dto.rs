@@ -3,7 +3,10 @@ use serde::Deserialize;
 use utoipa::ToSchema;
 use uuid::Uuid;
 
-use crate::{db_dto, response_dto::ReservationStatus};
+use crate::{
+    db_dto,
+    response_dto::{ReservationPerks, ReservationStatus},
+};
 
 #[derive(Deserialize)]
 pub struct Pagination {
@@ -14,22 +17,57 @@ pub struct Pagination {
 #[derive(Deserialize, ToSchema)]
 #[serde(rename_all = "camelCase")]
 pub struct ReservationRequest {
+    pub reservation_uid: Option<Uuid>,
     pub hotel_uid: Uuid,
     pub payment_uid: Uuid,
     pub start_date: Option<DateTime<chrono::Local>>,
     pub end_date: Option<DateTime<chrono::Local>>,
+    #[serde(default)]
+    pub perks: ReservationPerks,
 }
 
 impl ReservationRequest {
     pub fn into_db_dto(self, username: String, hotel_id: Option<i32>) -> db_dto::Reservation {
         db_dto::Reservation {
-            reservation_uid: Uuid::new_v4(),
+            reservation_uid: self.reservation_uid.unwrap_or_else(Uuid::new_v4),
             username,
             payment_uid: self.payment_uid,
             hotel_id,
             status: ReservationStatus::Paid.to_string(),
             start_date: self.start_date,
             end_date: self.end_date,
+            cancellation_extension_hours: self.perks.cancellation_extension_hours,
+            late_checkout: self.perks.late_checkout,
+            free_nights: self.perks.free_nights,
+        }
+    }
+}
+
+#[derive(Deserialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct HotelRequest {
+    pub name: String,
+    pub country: String,
+    pub city: String,
+    pub address: String,
+    pub stars: Option<i32>,
+    pub price: i32,
+    pub currency: Option<String>,
+}
+
+impl HotelRequest {
+    pub fn into_db_dto(self, hotel_uid: Uuid) -> db_dto::Hotel {
+        db_dto::Hotel {
+            hotel_uid,
+            name: self.name,
+            country: self.country,
+            city: self.city,
+            address: self.address,
+            stars: self.stars,
+            price: self.price,
+            currency: self
+                .currency
+                .map_or_else(|| "RUB".to_owned(), |c| c.to_uppercase()),
         }
     }
 }
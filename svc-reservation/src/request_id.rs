@@ -0,0 +1,53 @@
+use axum::{extract::Request, http::HeaderValue, middleware::Next, response::Response};
+use uuid::Uuid;
+
+pub const REQUEST_ID_HEADER: &str = "X-Request-Id";
+pub const MAX_REQUEST_ID_LEN: usize = 128;
+
+struct RequestContext {
+    id: String,
+    user: Option<String>,
+}
+
+tokio::task_local! {
+    static REQUEST: RequestContext;
+}
+
+// идентификатор запроса, который сейчас обрабатывается в этой задаче
+pub fn current() -> Option<String> {
+    REQUEST.try_with(|r| r.id.clone()).ok()
+}
+
+pub fn current_user() -> Option<String> {
+    REQUEST.try_with(|r| r.user.clone()).ok().flatten()
+}
+
+// принимает X-Request-Id от клиента или шлюза, иначе генерирует новый
+pub async fn propagate_request_id(mut req: Request, next: Next) -> Response {
+    let request_id = req
+        .headers()
+        .get(REQUEST_ID_HEADER)
+        .and_then(|v| v.to_str().ok())
+        .filter(|id| {
+            !id.is_empty()
+                && id.len() <= MAX_REQUEST_ID_LEN
+                && id.bytes().all(|b| b.is_ascii_graphic())
+        })
+        .map(str::to_owned)
+        .unwrap_or_else(|| Uuid::new_v4().to_string());
+    let value = HeaderValue::from_str(&request_id).unwrap();
+    req.headers_mut().insert(REQUEST_ID_HEADER, value.clone());
+    let user = req
+        .headers()
+        .get("X-User-Name")
+        .and_then(|v| v.to_str().ok())
+        .map(str::to_owned);
+
+    let context = RequestContext {
+        id: request_id,
+        user,
+    };
+    let mut resp = REQUEST.scope(context, next.run(req)).await;
+    resp.headers_mut().insert(REQUEST_ID_HEADER, value);
+    resp
+}
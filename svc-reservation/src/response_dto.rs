@@ -17,6 +17,7 @@ pub struct Hotel {
     pub address: String,
     pub stars: Option<i32>,
     pub price: i32,
+    pub currency: String,
 }
 
 impl From<crate::db_dto::Hotel> for Hotel {
@@ -29,6 +30,7 @@ impl From<crate::db_dto::Hotel> for Hotel {
             address: value.address,
             stars: value.stars,
             price: value.price,
+            currency: value.currency,
         }
     }
 }
@@ -82,6 +84,7 @@ pub struct Reservation {
     pub status: ReservationStatus,
     pub start_date: Option<DateTime<chrono::Local>>,
     pub end_date: Option<DateTime<chrono::Local>>,
+    pub perks: ReservationPerks,
 }
 
 impl Reservation {
@@ -91,6 +94,7 @@ impl Reservation {
             payment_uid: value.payment_uid,
             hotel_uid,
             status: ReservationStatus::from_str(value.status.as_str()).unwrap(),
+            perks: ReservationPerks::from(&value),
             start_date: value.start_date,
             end_date: value.end_date,
         }
@@ -106,6 +110,7 @@ pub struct ReservationWithHotel {
     pub start_date: Option<DateTime<chrono::Local>>,
     pub end_date: Option<DateTime<chrono::Local>>,
     pub hotel: HotelShort,
+    pub perks: ReservationPerks,
 }
 
 impl ReservationWithHotel {
@@ -115,12 +120,32 @@ impl ReservationWithHotel {
             payment_uid: value.payment_uid,
             hotel: hotel.into(),
             status: ReservationStatus::from_str(value.status.as_str()).unwrap(),
+            perks: ReservationPerks::from(&value),
             start_date: value.start_date,
             end_date: value.end_date,
         }
     }
 }
 
+// льготы уровня лояльности, действовавшие на момент бронирования
+#[derive(Default, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct ReservationPerks {
+    pub cancellation_extension_hours: i32,
+    pub late_checkout: bool,
+    pub free_nights: i32,
+}
+
+impl From<&db_dto::Reservation> for ReservationPerks {
+    fn from(value: &db_dto::Reservation) -> Self {
+        Self {
+            cancellation_extension_hours: value.cancellation_extension_hours,
+            late_checkout: value.late_checkout,
+            free_nights: value.free_nights,
+        }
+    }
+}
+
 #[derive(Serialize, Deserialize, ToSchema)]
 #[serde(rename_all = "SCREAMING_SNAKE_CASE")]
 pub enum ReservationStatus {
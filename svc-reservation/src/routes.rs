@@ -1,6 +1,8 @@
+use std::collections::BTreeMap;
+
 use axum::{
     extract::{Path, Query, State},
-    http::{HeaderMap, StatusCode},
+    http::{header, HeaderMap, StatusCode},
     response::IntoResponse,
     Json,
 };
@@ -10,7 +12,9 @@ use uuid::Uuid;
 use crate::{
     db_dto,
     diesel_paginate::*,
-    request_dto, response_dto,
+    health::{self, HealthReport},
+    logger::{self, LogLevels},
+    monitoring, request_dto, response_dto,
     schema::{hotels, reservation},
     AppState,
 };
@@ -26,6 +30,79 @@ pub async fn check_health() -> impl IntoResponse {
     StatusCode::OK
 }
 
+#[utoipa::path(
+    get,
+    path = "/manage/health/live",
+    responses(
+        (status = OK, body = HealthReport, description = "Процесс работает")
+    )
+)]
+pub async fn check_liveness() -> impl IntoResponse {
+    HealthReport::new(BTreeMap::new())
+}
+
+#[utoipa::path(
+    get,
+    path = "/manage/health/ready",
+    responses(
+        (status = OK, body = HealthReport, description = "Сервис готов принимать запросы"),
+        (
+            status = SERVICE_UNAVAILABLE,
+            body = HealthReport,
+            description = "Не пройдена одна из проверок: доступность базы данных и применённые миграции",
+        ),
+    )
+)]
+pub async fn check_readiness(State(state): State<AppState>) -> impl IntoResponse {
+    health::readiness(state.database_url).await
+}
+
+#[utoipa::path(
+    get,
+    path = "/manage/metrics",
+    responses(
+        (status = OK, body = String, content_type = "text/plain", description = "Метрики в формате Prometheus")
+    )
+)]
+pub async fn get_metrics() -> impl IntoResponse {
+    (
+        [(header::CONTENT_TYPE, "text/plain; version=0.0.4")],
+        monitoring::render(),
+    )
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/v1/admin/log-levels",
+    responses(
+        (status = OK, body = LogLevels, description = "Текущие уровни журнала")
+    )
+)]
+pub async fn get_log_levels() -> impl IntoResponse {
+    Json(logger::levels())
+}
+
+#[utoipa::path(
+    put,
+    path = "/api/v1/admin/log-levels",
+    request_body = LogLevels,
+    responses(
+        (status = OK, body = LogLevels, description = "Уровни журнала изменены"),
+        (status = BAD_REQUEST, description = "Неизвестный уровень журнала"),
+    )
+)]
+pub async fn put_log_levels(
+    Json(levels): Json<LogLevels>,
+) -> Result<impl IntoResponse, StatusCode> {
+    logger::set_levels(levels.clone()).map_err(|e| {
+        tracing::warn!("Rejected log levels: {e}");
+        StatusCode::BAD_REQUEST
+    })?;
+    tracing::info!("Log levels changed to {levels:?}");
+
+    Ok(Json(levels))
+}
+
 #[utoipa::path(
     get,
     path = "/api/v1/hotels",
@@ -56,7 +133,7 @@ pub async fn get_hotels(
         .load_and_count_pages(conn);
 
     match res {
-        Ok((hotels, count)) => (
+        Ok((hotels, count, _)) => (
             StatusCode::OK,
             Json(response_dto::HotelList {
                 page: pagination.page,
@@ -103,6 +180,84 @@ pub async fn get_hotel(
     Ok(Json(response_dto::Hotel::from(res)))
 }
 
+#[utoipa::path(
+    post,
+    path = "/api/v1/hotels",
+    request_body = request_dto::HotelRequest,
+    responses(
+        (
+            status = CREATED,
+            description = "Отель добавлен",
+            body = response_dto::Hotel,
+            content_type = "application/json",
+        ),
+        (status = BAD_REQUEST, description = "Некорректные данные отеля"),
+    ),
+)]
+pub async fn post_hotel(
+    State(state): State<AppState>,
+    Json(hotel): Json<request_dto::HotelRequest>,
+) -> Result<impl IntoResponse, StatusCode> {
+    if hotel.price < 0 || hotel.stars.is_some_and(|s| !(1..=5).contains(&s)) {
+        return Err(StatusCode::BAD_REQUEST);
+    }
+
+    let conn = &mut PgConnection::establish(state.database_url.as_str())
+        .expect("Failed to establish connection to database");
+    let created_hotel = diesel::insert_into(hotels::table)
+        .values(hotel.into_db_dto(Uuid::new_v4()))
+        .returning(db_dto::Hotel::as_returning())
+        .get_result(conn)
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+    Ok((
+        StatusCode::CREATED,
+        Json(response_dto::Hotel::from(created_hotel)),
+    ))
+}
+
+#[utoipa::path(
+    put,
+    path = "/api/v1/hotel/{hotelId}",
+    request_body = request_dto::HotelRequest,
+    responses(
+        (
+            status = OK,
+            description = "Данные отеля обновлены",
+            body = response_dto::Hotel,
+            content_type = "application/json",
+        ),
+        (status = BAD_REQUEST, description = "Некорректные данные отеля"),
+        (status = NOT_FOUND, description = "Отель не найден"),
+    ),
+    params(
+        ("hotelid", Path, description="ID отеля"),
+    ),
+)]
+pub async fn put_hotel(
+    State(state): State<AppState>,
+    Path(uid): Path<Uuid>,
+    Json(hotel): Json<request_dto::HotelRequest>,
+) -> Result<impl IntoResponse, StatusCode> {
+    if hotel.price < 0 || hotel.stars.is_some_and(|s| !(1..=5).contains(&s)) {
+        return Err(StatusCode::BAD_REQUEST);
+    }
+
+    let conn = &mut PgConnection::establish(state.database_url.as_str())
+        .expect("Failed to establish connection to database");
+    let updated_hotel = diesel::update(hotels::table)
+        .filter(hotels::hotel_uid.eq(uid))
+        .set(hotel.into_db_dto(uid))
+        .returning(db_dto::Hotel::as_returning())
+        .get_result(conn)
+        .map_err(|e| match e {
+            DieselError::NotFound => StatusCode::NOT_FOUND,
+            _ => StatusCode::INTERNAL_SERVER_ERROR,
+        })?;
+
+    Ok(Json(response_dto::Hotel::from(updated_hotel)))
+}
+
 #[utoipa::path(
     get,
     path = "/api/v1/reservations",
@@ -228,7 +383,7 @@ pub async fn delete_reservation(
 
     let conn = &mut PgConnection::establish(state.database_url.as_str())
         .expect("Failed to establish connection to database");
-    diesel::update(reservation::table)
+    let canceled = diesel::update(reservation::table)
         .filter(reservation::username.eq(username))
         .filter(reservation::reservation_uid.eq(path.reservation_uid))
         .set(reservation::status.eq(response_dto::ReservationStatus::Canceled.to_string()))
@@ -237,6 +392,9 @@ pub async fn delete_reservation(
             DieselError::NotFound => StatusCode::NOT_FOUND,
             _ => StatusCode::INTERNAL_SERVER_ERROR,
         })?;
+    if canceled > 0 {
+        metrics::counter!("reservation_cancellations_total").increment(1);
+    }
 
     Ok(StatusCode::NO_CONTENT)
 }
@@ -283,6 +441,7 @@ pub async fn post_reservation(
         .get_result(conn)
         .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
 
+    metrics::counter!("reservation_bookings_total").increment(1);
     let response_reservation =
         response_dto::Reservation::from_db_dto(created_reservation, hotel_uid);
 
@@ -14,6 +14,8 @@ diesel::table! {
         address -> Varchar,
         stars -> Nullable<Int4>,
         price -> Int4,
+        #[max_length = 3]
+        currency -> Varchar,
     }
 }
 
@@ -29,6 +31,9 @@ diesel::table! {
         status -> Varchar,
         start_date -> Nullable<Timestamptz>,
         end_date -> Nullable<Timestamptz>,
+        cancellation_extension_hours -> Int4,
+        late_checkout -> Bool,
+        free_nights -> Int4,
     }
 }
 
@@ -0,0 +1,196 @@
+use std::{env, time::Instant};
+
+use axum::{
+    extract::{MatchedPath, Request},
+    middleware::Next,
+    response::Response,
+};
+use diesel::connection::{set_default_instrumentation, Instrumentation, InstrumentationEvent};
+use opentelemetry::{global, trace::TracerProvider as _};
+use opentelemetry_http::HeaderExtractor;
+use opentelemetry_sdk::{
+    error::OTelSdkResult,
+    propagation::TraceContextPropagator,
+    trace::{SdkTracerProvider, SpanData, SpanExporter},
+    Resource,
+};
+use tracing::{field, Instrument, Level};
+use tracing_opentelemetry::OpenTelemetrySpanExt;
+use tracing_subscriber::{filter::Targets, layer::SubscriberExt, util::SubscriberInitExt};
+
+use crate::request_id;
+
+// куда отправлять спаны: otlp (адрес из OTEL_EXPORTER_OTLP_ENDPOINT), stdout или none
+pub fn init(service_name: &str) -> SdkTracerProvider {
+    global::set_text_map_propagator(TraceContextPropagator::new());
+
+    let exporter = env::var("OTEL_TRACES_EXPORTER").unwrap_or_else(|_| {
+        match env::var("OTEL_EXPORTER_OTLP_ENDPOINT") {
+            Ok(_) => "otlp".to_owned(),
+            Err(_) => "none".to_owned(),
+        }
+    });
+    let resource = Resource::builder()
+        .with_service_name(
+            env::var("OTEL_SERVICE_NAME").unwrap_or_else(|_| service_name.to_owned()),
+        )
+        .build();
+    let builder = SdkTracerProvider::builder().with_resource(resource);
+    let provider = match exporter.as_str() {
+        "otlp" => match opentelemetry_otlp::SpanExporter::builder()
+            .with_http()
+            .build()
+        {
+            Ok(exporter) => builder.with_batch_exporter(exporter),
+            Err(e) => {
+                tracing::error!("Failed to create OTLP exporter: {e}");
+                builder
+            }
+        },
+        "stdout" => builder.with_simple_exporter(StdoutExporter),
+        "none" => builder,
+        other => {
+            tracing::warn!("Unknown traces exporter {other}, spans are not exported");
+            builder
+        }
+    }
+    .build();
+    global::set_tracer_provider(provider.clone());
+
+    // события по-прежнему пишутся в журнал через log4rs, подписчик нужен только для спанов
+    tracing_subscriber::registry()
+        .with(tracing_opentelemetry::layer().with_tracer(provider.tracer(service_name.to_owned())))
+        .with(
+            Targets::new()
+                .with_target(env!("CARGO_CRATE_NAME"), Level::DEBUG)
+                .with_default(Level::INFO),
+        )
+        .init();
+    if let Err(e) = set_default_instrumentation(|| Some(Box::new(ConnectionTelemetry::default()))) {
+        tracing::error!("Failed to set up query tracing: {e}");
+    }
+
+    provider
+}
+
+// серверный спан на каждый запрос, родительский контекст берётся из заголовка traceparent
+pub async fn trace_request(req: Request, next: Next) -> Response {
+    let route = req
+        .extensions()
+        .get::<MatchedPath>()
+        .map_or(req.uri().path(), MatchedPath::as_str)
+        .to_owned();
+    let span = tracing::info_span!(
+        "request",
+        otel.name = format!("{} {route}", req.method()),
+        otel.kind = "server",
+        otel.status_code = field::Empty,
+        http.request.method = %req.method(),
+        http.route = route,
+        http.response.status_code = field::Empty,
+        request_id = request_id::current(),
+    );
+    let parent = global::get_text_map_propagator(|p| p.extract(&HeaderExtractor(req.headers())));
+    let _ = span.set_parent(parent);
+
+    let resp = next.run(req).instrument(span.clone()).await;
+    span.record("http.response.status_code", resp.status().as_u16());
+    if resp.status().is_server_error() {
+        span.record("otel.status_code", "ERROR");
+    }
+    resp
+}
+
+// для локального запуска: одна строка на завершённый спан
+#[derive(Debug)]
+struct StdoutExporter;
+
+impl SpanExporter for StdoutExporter {
+    async fn export(&self, batch: Vec<SpanData>) -> OTelSdkResult {
+        for span in batch {
+            let duration = span
+                .end_time
+                .duration_since(span.start_time)
+                .unwrap_or_default();
+            let attributes = span
+                .attributes
+                .iter()
+                .map(|kv| format!("{}={}", kv.key, kv.value))
+                .collect::<Vec<_>>()
+                .join(" ");
+            println!(
+                "span trace_id={} span_id={} parent_id={} name=\"{}\" duration={:.3}ms {attributes}",
+                span.span_context.trace_id(),
+                span.span_context.span_id(),
+                span.parent_span_id,
+                span.name,
+                duration.as_secs_f64() * 1000.0,
+            );
+        }
+
+        Ok(())
+    }
+}
+
+// спан и метрики на каждый запрос к базе, параметры запроса не записываются;
+// пула нет, поэтому вместо его заполненности учитываются открытые соединения
+#[derive(Default)]
+struct ConnectionTelemetry {
+    span: Option<tracing::Span>,
+    started_at: Option<Instant>,
+    connected: bool,
+}
+
+impl Instrumentation for ConnectionTelemetry {
+    fn on_connection_event(&mut self, event: InstrumentationEvent<'_>) {
+        match event {
+            InstrumentationEvent::StartEstablishConnection { .. } => {
+                self.started_at = Some(Instant::now());
+            }
+            InstrumentationEvent::FinishEstablishConnection { error, .. } => {
+                let outcome = if error.is_some() { "error" } else { "ok" };
+                if let Some(started_at) = self.started_at.take() {
+                    metrics::histogram!("db_connection_duration_seconds", "outcome" => outcome)
+                        .record(started_at.elapsed());
+                }
+                if error.is_none() {
+                    self.connected = true;
+                    metrics::gauge!("db_connections_active").increment(1);
+                }
+            }
+            InstrumentationEvent::StartQuery { query, .. } => {
+                let query = query.to_string();
+                let statement = query.split(" -- binds:").next().unwrap_or_default();
+                self.span = Some(tracing::info_span!(
+                    "query",
+                    otel.kind = "client",
+                    otel.status_code = field::Empty,
+                    db.system = "postgresql",
+                    db.query.text = statement,
+                    error.message = field::Empty,
+                ));
+                self.started_at = Some(Instant::now());
+            }
+            InstrumentationEvent::FinishQuery { error, .. } => {
+                let outcome = if error.is_some() { "error" } else { "ok" };
+                if let Some(started_at) = self.started_at.take() {
+                    metrics::histogram!("db_query_duration_seconds", "outcome" => outcome)
+                        .record(started_at.elapsed());
+                }
+                if let (Some(span), Some(e)) = (self.span.take(), error) {
+                    span.record("otel.status_code", "ERROR");
+                    span.record("error.message", e.to_string());
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+impl Drop for ConnectionTelemetry {
+    fn drop(&mut self) {
+        if self.connected {
+            metrics::gauge!("db_connections_active").decrement(1);
+        }
+    }
+}
@@ -0,0 +1,148 @@
+use std::{
+    collections::HashMap,
+    env,
+    sync::{Arc, Mutex},
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+use axum::{
+    body::{self, Body},
+    extract::{Request, State},
+    http::StatusCode,
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+use hmac::{Hmac, Mac};
+use sha2::{Digest, Sha256};
+
+#[cfg(test)]
+mod tests;
+
+pub const DEFAULT_MAX_AGE_SECS: u64 = 300;
+pub const MAX_SIGNED_BODY_BYTES: usize = 10 * 1024 * 1024;
+
+// запросы от шлюза подписываются HMAC-SHA256 от метода, пути, пользователя, времени, nonce и тела
+pub struct SignatureVerifier {
+    key: Option<Vec<u8>>,
+    max_age: Duration,
+    // nonce уже принятых запросов на время их действия. Кэш свой у каждой реплики, поэтому
+    // перехваченный запрос можно повторить на другой реплике в пределах SIGNATURE_MAX_AGE_SECS;
+    // подпись закрывает весь запрос, так что повтор выполняет ровно то же действие
+    seen: Mutex<HashMap<String, u64>>,
+}
+
+impl SignatureVerifier {
+    pub fn from_env() -> Arc<Self> {
+        let key = env::var("SERVICE_SIGNING_KEY")
+            .ok()
+            .filter(|k| !k.is_empty())
+            .map(String::into_bytes);
+        if key.is_none() {
+            // без ключа неподписанные запросы принимаются только при явном разрешении
+            let allow_unsigned =
+                env::var("SIGNATURE_ALLOW_UNSIGNED").is_ok_and(|v| v == "true" || v == "1");
+            if !allow_unsigned {
+                panic!(
+                    "SERVICE_SIGNING_KEY is not set and SIGNATURE_ALLOW_UNSIGNED is not enabled"
+                );
+            }
+            tracing::warn!("SERVICE_SIGNING_KEY is not set, internal requests are not verified");
+        }
+        let max_age = env::var("SIGNATURE_MAX_AGE_SECS")
+            .ok()
+            .and_then(|v| v.parse::<u64>().ok())
+            .unwrap_or(DEFAULT_MAX_AGE_SECS);
+
+        Arc::new(Self::new(key, Duration::from_secs(max_age)))
+    }
+
+    pub fn new(key: Option<Vec<u8>>, max_age: Duration) -> Self {
+        Self {
+            key,
+            max_age,
+            seen: Mutex::new(HashMap::new()),
+        }
+    }
+
+    pub fn verify(
+        &self,
+        method: &str,
+        path: &str,
+        headers: &axum::http::HeaderMap,
+        body: &[u8],
+    ) -> Result<(), &'static str> {
+        // без ключа проверка отключена
+        let Some(key) = &self.key else {
+            return Ok(());
+        };
+        let header = |name: &str| {
+            headers
+                .get(name)
+                .and_then(|v| v.to_str().ok())
+                .ok_or("missing signature headers")
+        };
+        let timestamp = header("X-Signature-Timestamp")?
+            .parse::<u64>()
+            .map_err(|_| "malformed timestamp")?;
+        let nonce = header("X-Signature-Nonce")?;
+        let signature = hex::decode(header("X-Signature")?).map_err(|_| "malformed signature")?;
+
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_secs();
+        if now.abs_diff(timestamp) > self.max_age.as_secs() {
+            return Err("expired timestamp");
+        }
+
+        let user = headers.get("X-User-Name").map_or(&[][..], |v| v.as_bytes());
+
+        let mut mac = Hmac::<Sha256>::new_from_slice(key).map_err(|_| "invalid key")?;
+        mac.update(format!("{method}\n{path}\n").as_bytes());
+        mac.update(user);
+        mac.update(
+            format!(
+                "\n{timestamp}\n{nonce}\n{}",
+                hex::encode(Sha256::digest(body))
+            )
+            .as_bytes(),
+        );
+        mac.verify_slice(&signature)
+            .map_err(|_| "signature mismatch")?;
+
+        let mut seen = self.seen.lock().unwrap();
+        let max_age = self.max_age.as_secs();
+        seen.retain(|_, at| now.abs_diff(*at) <= max_age);
+        if seen.insert(nonce.to_owned(), timestamp).is_some() {
+            return Err("replayed request");
+        }
+
+        Ok(())
+    }
+}
+
+pub async fn verify_signature(
+    State(verifier): State<Arc<SignatureVerifier>>,
+    req: Request,
+    next: Next,
+) -> Response {
+    if verifier.key.is_none() {
+        return next.run(req).await;
+    }
+
+    let (parts, body) = req.into_parts();
+    let Ok(body) = body::to_bytes(body, MAX_SIGNED_BODY_BYTES).await else {
+        return StatusCode::PAYLOAD_TOO_LARGE.into_response();
+    };
+    let path = parts
+        .uri
+        .path_and_query()
+        .map_or(parts.uri.path(), |p| p.as_str());
+
+    if let Err(e) = verifier.verify(parts.method.as_str(), path, &parts.headers, &body) {
+        tracing::warn!("Rejected {} {path}: {e}", parts.method);
+        return StatusCode::UNAUTHORIZED.into_response();
+    }
+
+    next.run(Request::from_parts(parts, Body::from(body))).await
+}
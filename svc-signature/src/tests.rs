@@ -0,0 +1,166 @@
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use axum::http::{HeaderMap, HeaderValue};
+use hmac::{Hmac, Mac};
+use sha2::{Digest, Sha256};
+
+use crate::SignatureVerifier;
+
+const KEY: &[u8] = b"secret";
+
+fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap()
+        .as_secs()
+}
+
+// подпись в том же формате, что и у шлюза
+fn signed_headers(
+    method: &str,
+    path: &str,
+    user: &str,
+    timestamp: u64,
+    nonce: &str,
+    body: &[u8],
+) -> HeaderMap {
+    let mut mac = Hmac::<Sha256>::new_from_slice(KEY).unwrap();
+    mac.update(
+        format!(
+            "{method}\n{path}\n{user}\n{timestamp}\n{nonce}\n{}",
+            hex::encode(Sha256::digest(body))
+        )
+        .as_bytes(),
+    );
+
+    let mut headers = HeaderMap::new();
+    if !user.is_empty() {
+        headers.insert("X-User-Name", HeaderValue::from_str(user).unwrap());
+    }
+    headers.insert("X-Signature-Timestamp", HeaderValue::from(timestamp));
+    headers.insert("X-Signature-Nonce", HeaderValue::from_str(nonce).unwrap());
+    headers.insert(
+        "X-Signature",
+        HeaderValue::from_str(&hex::encode(mac.finalize().into_bytes())).unwrap(),
+    );
+    headers
+}
+
+fn verifier() -> SignatureVerifier {
+    SignatureVerifier::new(Some(KEY.to_vec()), Duration::from_secs(300))
+}
+
+#[test]
+fn signature_accepts_valid_request() {
+    let body = br#"{"hotelUid":"049161bb-badd-4fa8-9d90-87c9a82b0668"}"#;
+    let headers = signed_headers(
+        "POST",
+        "/api/v1/reservations",
+        "Test Max",
+        now(),
+        "n1",
+        body,
+    );
+
+    assert_eq!(
+        verifier().verify("POST", "/api/v1/reservations", &headers, body),
+        Ok(())
+    );
+}
+
+#[test]
+fn signature_rejects_tampered_request() {
+    let verifier = verifier();
+    let headers = signed_headers(
+        "GET",
+        "/api/v1/reservations?page=1",
+        "Test Max",
+        now(),
+        "n1",
+        b"",
+    );
+
+    assert_eq!(
+        verifier.verify("GET", "/api/v1/reservations?page=2", &headers, b""),
+        Err("signature mismatch")
+    );
+    assert_eq!(
+        verifier.verify("DELETE", "/api/v1/reservations?page=1", &headers, b""),
+        Err("signature mismatch")
+    );
+    assert_eq!(
+        verifier.verify("GET", "/api/v1/reservations?page=1", &headers, b"{}"),
+        Err("signature mismatch")
+    );
+
+    // пользователь входит в подпись
+    let mut headers = headers;
+    headers.insert("X-User-Name", HeaderValue::from_static("Other"));
+    assert_eq!(
+        verifier.verify("GET", "/api/v1/reservations?page=1", &headers, b""),
+        Err("signature mismatch")
+    );
+}
+
+#[test]
+fn signature_rejects_replay() {
+    let verifier = verifier();
+    let headers = signed_headers("GET", "/api/v1/hotels", "", now(), "n1", b"");
+
+    assert_eq!(
+        verifier.verify("GET", "/api/v1/hotels", &headers, b""),
+        Ok(())
+    );
+    assert_eq!(
+        verifier.verify("GET", "/api/v1/hotels", &headers, b""),
+        Err("replayed request")
+    );
+
+    // другой nonce — другой запрос
+    let headers = signed_headers("GET", "/api/v1/hotels", "", now(), "n2", b"");
+    assert_eq!(
+        verifier.verify("GET", "/api/v1/hotels", &headers, b""),
+        Ok(())
+    );
+}
+
+#[test]
+fn signature_rejects_expired_timestamp() {
+    let verifier = verifier();
+
+    let headers = signed_headers("GET", "/api/v1/hotels", "", now() - 301, "n1", b"");
+    assert_eq!(
+        verifier.verify("GET", "/api/v1/hotels", &headers, b""),
+        Err("expired timestamp")
+    );
+    let headers = signed_headers("GET", "/api/v1/hotels", "", now() + 301, "n2", b"");
+    assert_eq!(
+        verifier.verify("GET", "/api/v1/hotels", &headers, b""),
+        Err("expired timestamp")
+    );
+}
+
+#[test]
+fn signature_rejects_missing_or_malformed_headers() {
+    let verifier = verifier();
+
+    assert_eq!(
+        verifier.verify("GET", "/api/v1/hotels", &HeaderMap::new(), b""),
+        Err("missing signature headers")
+    );
+
+    let mut headers = signed_headers("GET", "/api/v1/hotels", "", now(), "n1", b"");
+    headers.insert("X-Signature", HeaderValue::from_static("not-hex"));
+    assert_eq!(
+        verifier.verify("GET", "/api/v1/hotels", &headers, b""),
+        Err("malformed signature")
+    );
+
+    // подпись другим ключом
+    let verifier = SignatureVerifier::new(Some(b"other".to_vec()), Duration::from_secs(300));
+    let headers = signed_headers("GET", "/api/v1/hotels", "", now(), "n2", b"");
+    assert_eq!(
+        verifier.verify("GET", "/api/v1/hotels", &headers, b""),
+        Err("signature mismatch")
+    );
+}